@@ -0,0 +1,265 @@
+use super::{AppState, BridgeDirection, Device, LogLevel, Protocol, MAX_UNIVERSE, MIN_UNIVERSE};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr};
+use uuid::Uuid;
+
+/// Art-Net has no priority, its sources merge at the E1.31 default.
+pub const ARTNET_PRIORITY: u8 = 100;
+
+/// Port address 0:0:0 shows as universe 1 by default, like most consoles
+/// number them.
+pub const DEFAULT_ARTNET_UNIVERSE_OFFSET: u16 = 1;
+
+/// Largest offset that still keeps every port address a valid universe.
+pub const MAX_ARTNET_UNIVERSE_OFFSET: u16 = MAX_UNIVERSE - PortAddress::MAX;
+
+/// A node is lost once it misses this many seconds of polls.
+pub const ARTNET_NODE_TIMEOUT: chrono::Duration = chrono::Duration::seconds(10);
+
+/// A 15 bit Art-Net port address, split into its Net, Sub-Net and
+/// Universe parts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct PortAddress(u16);
+
+impl PortAddress {
+    pub const MAX: u16 = 0x7fff;
+
+    pub fn new(address: u16) -> Self {
+        Self(address & Self::MAX)
+    }
+
+    pub fn from_parts(net: u8, sub_net: u8, universe: u8) -> Self {
+        Self::new(((net as u16) << 8) | ((sub_net as u16 & 0x0f) << 4) | (universe as u16 & 0x0f))
+    }
+
+    /// The port address shown as `universe`, if the offset leaves one.
+    pub fn from_universe(universe: u16, offset: u16) -> Option<Self> {
+        let address = universe.checked_sub(offset)?;
+        (address <= Self::MAX).then_some(Self(address))
+    }
+
+    /// The universe this port address is shown and merged on, if the offset
+    /// puts it in the sACN universe range.
+    pub fn to_universe(self, offset: u16) -> Option<u16> {
+        let universe = self.0.checked_add(offset)?;
+        (MIN_UNIVERSE..=MAX_UNIVERSE)
+            .contains(&universe)
+            .then_some(universe)
+    }
+
+    pub fn net(self) -> u8 {
+        (self.0 >> 8) as u8
+    }
+
+    pub fn sub_net(self) -> u8 {
+        ((self.0 >> 4) & 0x0f) as u8
+    }
+
+    pub fn universe(self) -> u8 {
+        (self.0 & 0x0f) as u8
+    }
+
+    pub fn value(self) -> u16 {
+        self.0
+    }
+}
+
+impl fmt::Display for PortAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.net(), self.sub_net(), self.universe())
+    }
+}
+
+/// Where a transmit universe is sent as ArtDmx, on top of sACN.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ArtNetOutput {
+    #[default]
+    Off,
+    Broadcast,
+    Unicast(IpAddr),
+}
+
+impl fmt::Display for ArtNetOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArtNetOutput::Off => write!(f, "Off"),
+            ArtNetOutput::Broadcast => write!(f, "Broadcast"),
+            ArtNetOutput::Unicast(ip) => write!(f, "Unicast to {}", ip),
+        }
+    }
+}
+
+/// One DMX port of an Art-Net node and the port addresses it's patched to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodePort {
+    pub bind_index: u8,              // which reply of a multi-reply node it came from
+    pub input: Option<PortAddress>,  // sends DMX in on this port address
+    pub output: Option<PortAddress>, // outputs DMX received on this port address
+}
+
+/// What an Art-Net node reported about itself in its latest ArtPollReply.
+#[derive(Debug, Clone)]
+pub struct ArtNetNode {
+    pub short_name: String,
+    pub long_name: String,
+    pub firmware: u16,
+    pub node_report: String,
+    pub ports: Vec<NodePort>,
+    pub last_reply: DateTime<Utc>,
+}
+
+impl AppState {
+    /// The universe an Art-Net port address is shown and merged on.
+    pub fn artnet_universe(&self, port_address: PortAddress) -> Option<u16> {
+        port_address.to_universe(self.artnet_universe_offset)
+    }
+
+    /// Whether we send ArtDmx ourselves, from a transmit universe or an
+    /// sACN → Art-Net bridge mapping.
+    pub fn is_sending_artnet(&self) -> bool {
+        let sender = self
+            .transmit_session
+            .universes
+            .iter()
+            .any(|u| u.enabled && u.artnet != ArtNetOutput::Off);
+        let bridge = self.bridge_enabled
+            && self.bridge_mappings.iter().any(|mapping| {
+                mapping.enabled && mapping.direction == BridgeDirection::SacnToArtNet
+            });
+        sender || bridge
+    }
+
+    pub fn set_artnet_universe_offset(&mut self, offset: u16) {
+        self.artnet_universe_offset = offset.min(MAX_ARTNET_UNIVERSE_OFFSET);
+        self.settings.artnet_universe_offset = self.artnet_universe_offset;
+        self.add_log(
+            LogLevel::Info,
+            format!(
+                "Art-Net universe offset set to {}",
+                self.artnet_universe_offset
+            ),
+        );
+        if let Err(e) = self.save_settings() {
+            self.add_log(LogLevel::Warning, format!("Failed to save settings: {}", e));
+        }
+    }
+
+    /// Where Art-Net broadcasts go: the selected adapter's subnet broadcast
+    /// address, or the limited broadcast address if it has none.
+    pub fn artnet_broadcast_addr(&self) -> IpAddr {
+        let adapter = match &self.selected_adapter {
+            Some(name) => self.network_adapters.iter().find(|a| a.name == *name),
+            None => self.network_adapters.first(),
+        };
+        adapter
+            .and_then(|adapter| adapter.broadcast)
+            .unwrap_or(IpAddr::V4(Ipv4Addr::BROADCAST))
+    }
+
+    /// Records an ArtPollReply. Nodes with more than four ports reply once
+    /// per bind index, so only the ports of this reply are replaced.
+    pub fn update_artnet_node(&mut self, id: Uuid, ip: IpAddr, mut node: ArtNetNode) {
+        let name = if node.short_name.is_empty() {
+            format!("Art-Net {}", ip)
+        } else {
+            node.short_name.clone()
+        };
+
+        if !self.devices.contains_key(&id) {
+            self.add_log(
+                LogLevel::Info,
+                format!("Art-Net node discovered: {} ({})", name, ip),
+            );
+        }
+        let device = self
+            .devices
+            .entry(id)
+            .or_insert_with(|| Device::new(id, Protocol::ArtNet, ip, name.clone()));
+
+        if let Some(previous) = device.node.take() {
+            let bind_indexes: Vec<u8> = node.ports.iter().map(|port| port.bind_index).collect();
+            let mut ports: Vec<NodePort> = previous
+                .ports
+                .into_iter()
+                .filter(|port| !bind_indexes.contains(&port.bind_index))
+                .collect();
+            ports.append(&mut node.ports);
+            ports.sort_by_key(|port| port.bind_index);
+            node.ports = ports;
+        }
+
+        device.ip = ip;
+        device.name = name;
+        device.last_seen = node.last_reply;
+        device.node = Some(node);
+        device.refresh_lost(Utc::now());
+    }
+
+    /// Marks nodes lost once they stop answering polls. Should be called
+    /// periodically by the Art-Net listener.
+    pub fn check_artnet_nodes(&mut self) {
+        let now = Utc::now();
+        let mut lost = Vec::new();
+        for device in self.devices.values_mut() {
+            if device.node.is_some() && !device.lost {
+                device.refresh_lost(now);
+                if device.lost {
+                    lost.push(format!("{} ({})", device.name, device.ip));
+                }
+            }
+        }
+        for node in lost {
+            self.add_log(
+                LogLevel::Warning,
+                format!(
+                    "Art-Net node {} lost: no poll reply for {} s",
+                    node,
+                    ARTNET_NODE_TIMEOUT.num_seconds()
+                ),
+            );
+        }
+    }
+
+    /// Art-Net nodes that answered a poll, for picking a unicast target.
+    pub fn artnet_nodes(&self) -> Vec<&Device> {
+        let mut nodes: Vec<&Device> = self
+            .devices
+            .values()
+            .filter(|device| device.node.is_some())
+            .collect();
+        nodes.sort_by_key(|device| device.ip);
+        nodes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::BridgeMapping;
+
+    #[test]
+    fn sends_artnet_from_the_sender_or_the_bridge() {
+        let mut state = AppState::new();
+        state.transmit_session.add_universe(1);
+        assert!(!state.is_sending_artnet());
+
+        state.transmit_session.universe_mut(1).unwrap().artnet = ArtNetOutput::Broadcast;
+        assert!(state.is_sending_artnet());
+        state.transmit_session.universe_mut(1).unwrap().enabled = false;
+        assert!(!state.is_sending_artnet(), "disabled universe");
+
+        let mut mapping = BridgeMapping::new();
+        mapping.direction = BridgeDirection::ArtNetToSacn;
+        state.bridge_mappings = vec![mapping.clone()];
+        state.bridge_enabled = true;
+        assert!(!state.is_sending_artnet(), "Art-Net is only received");
+
+        mapping.direction = BridgeDirection::SacnToArtNet;
+        state.bridge_mappings = vec![mapping];
+        assert!(state.is_sending_artnet());
+        state.bridge_enabled = false;
+        assert!(!state.is_sending_artnet(), "bridge stopped");
+    }
+}
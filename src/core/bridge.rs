@@ -0,0 +1,426 @@
+use super::{
+    AppState, ArtNetOutput, LogLevel, PortAddress, Protocol, MAX_PRIORITY, MAX_UNIVERSE,
+    MIN_UNIVERSE,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fmt;
+
+/// Default rate the bridge re-sends its output at.
+pub const DEFAULT_BRIDGE_RATE: u32 = 30;
+
+/// Most universes a single mapping can cover.
+pub const MAX_BRIDGE_COUNT: u16 = 512;
+
+/// Which way a mapping converts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum BridgeDirection {
+    #[default]
+    SacnToArtNet,
+    ArtNetToSacn,
+}
+
+impl fmt::Display for BridgeDirection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BridgeDirection::SacnToArtNet => write!(f, "sACN → Art-Net"),
+            BridgeDirection::ArtNetToSacn => write!(f, "Art-Net → sACN"),
+        }
+    }
+}
+
+/// A run of consecutive sACN universes paired with as many consecutive
+/// Art-Net port addresses.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BridgeMapping {
+    pub enabled: bool,
+    pub direction: BridgeDirection,
+    pub sacn_universe: u16,        // first of the run
+    pub port_address: PortAddress, // first of the run
+    pub count: u16,
+    pub priority: u8,                // of sACN output
+    pub artnet_output: ArtNetOutput, // where Art-Net output goes
+}
+
+impl BridgeMapping {
+    pub fn new() -> Self {
+        Self {
+            enabled: true,
+            direction: BridgeDirection::default(),
+            sacn_universe: MIN_UNIVERSE,
+            port_address: PortAddress::new(0),
+            count: 1,
+            priority: super::transmit::DEFAULT_PRIORITY,
+            artnet_output: ArtNetOutput::Broadcast,
+        }
+    }
+
+    /// Each sACN universe and the port address it pairs with, stopping at
+    /// the end of either range.
+    pub fn pairs(&self) -> impl Iterator<Item = (u16, PortAddress)> + '_ {
+        (0..self.count).map_while(|i| {
+            let universe = self
+                .sacn_universe
+                .checked_add(i)
+                .filter(|u| *u <= MAX_UNIVERSE)?;
+            let address = self
+                .port_address
+                .value()
+                .checked_add(i)
+                .filter(|a| *a <= PortAddress::MAX)?;
+            Some((universe, PortAddress::new(address)))
+        })
+    }
+}
+
+impl fmt::Display for BridgeMapping {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (first, last) = match (self.pairs().next(), self.pairs().last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return write!(f, "empty mapping"),
+        };
+        let sacn = if first.0 == last.0 {
+            format!("sACN {}", first.0)
+        } else {
+            format!("sACN {}-{}", first.0, last.0)
+        };
+        let artnet = if first.1 == last.1 {
+            format!("Art-Net {}", first.1)
+        } else {
+            format!("Art-Net {}-{}", first.1, last.1)
+        };
+        match self.direction {
+            BridgeDirection::SacnToArtNet => write!(f, "{} → {}", sacn, artnet),
+            BridgeDirection::ArtNetToSacn => write!(f, "{} → {}", artnet, sacn),
+        }
+    }
+}
+
+/// What one mapping did on the bridge's latest tick.
+#[derive(Debug, Clone, Default)]
+pub struct BridgeStatus {
+    pub live: usize,  // universes with input
+    pub frames: u64,  // sent since the bridge started
+    pub looped: bool, // our own output showed up on an input and was left out
+    pub error: Option<String>,
+}
+
+/// Where one frame of bridged levels goes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BridgeTarget {
+    Sacn {
+        universe: u16,
+        priority: u8,
+    },
+    ArtNet {
+        port_address: PortAddress,
+        output: ArtNetOutput,
+    },
+}
+
+impl fmt::Display for BridgeTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BridgeTarget::Sacn { universe, .. } => write!(f, "sACN universe {}", universe),
+            BridgeTarget::ArtNet { port_address, .. } => write!(f, "Art-Net {}", port_address),
+        }
+    }
+}
+
+/// One universe of levels for the bridge to send.
+#[derive(Debug, Clone)]
+pub struct BridgeFrame {
+    pub mapping: usize, // index into the bridge mappings
+    pub target: BridgeTarget,
+    pub levels: [u8; 512],
+}
+
+impl AppState {
+    /// The merged levels of one universe's sources on `protocol`, leaving out
+    /// anything this app sent so output can't feed back into the bridge.
+    /// Returns None if there's nothing to bridge, and whether any of our own
+    /// output was left out.
+    fn bridge_input(&self, universe: u16, protocol: Protocol) -> (Option<[u8; 512]>, bool) {
+        let Some(data) = self.universes.get(&universe) else {
+            return (None, false);
+        };
+        let looped = data
+            .sources
+            .values()
+            .any(|source| source.protocol == protocol && self.is_own_source(source));
+        let levels = data.merge_filtered(self.merge_mode, |source| {
+            source.protocol == protocol && !self.is_own_source(source)
+        });
+        (levels, looped)
+    }
+
+    /// Works out what every enabled mapping should send this tick, updating
+    /// each mapping's live count and loop warning.
+    pub fn bridge_frames(&mut self) -> Vec<BridgeFrame> {
+        self.bridge_status
+            .resize_with(self.bridge_mappings.len(), BridgeStatus::default);
+        if !self.bridge_enabled {
+            return Vec::new();
+        }
+
+        let routed = self.routed_universes();
+        let transmitted: BTreeSet<u16> = self
+            .transmit_session
+            .universes
+            .iter()
+            .filter(|u| u.enabled && (self.auto_send_enabled || routed.contains(&u.universe)))
+            .map(|u| u.universe)
+            .collect();
+
+        let mut frames = Vec::new();
+        for (index, mapping) in self.bridge_mappings.iter().enumerate() {
+            let status = &mut self.bridge_status[index];
+            status.live = 0;
+            status.looped = false;
+            if !mapping.enabled {
+                status.error = None;
+                continue;
+            }
+
+            let mut error = None;
+            for (universe, port_address) in mapping.pairs() {
+                let (input, protocol, target) = match mapping.direction {
+                    BridgeDirection::SacnToArtNet => (
+                        Some(universe),
+                        Protocol::Sacn,
+                        BridgeTarget::ArtNet {
+                            port_address,
+                            output: mapping.artnet_output,
+                        },
+                    ),
+                    BridgeDirection::ArtNetToSacn => {
+                        if transmitted.contains(&universe) {
+                            error = Some(format!(
+                                "Universe {} is already sent by the DMX sender",
+                                universe
+                            ));
+                            continue;
+                        }
+                        (
+                            port_address.to_universe(self.artnet_universe_offset),
+                            Protocol::ArtNet,
+                            BridgeTarget::Sacn {
+                                universe,
+                                priority: mapping.priority,
+                            },
+                        )
+                    }
+                };
+
+                let Some(input) = input else {
+                    continue;
+                };
+                let (levels, looped) = self.bridge_input(input, protocol);
+                let status = &mut self.bridge_status[index];
+                status.looped |= looped;
+                if let Some(levels) = levels {
+                    status.live += 1;
+                    frames.push(BridgeFrame {
+                        mapping: index,
+                        target,
+                        levels,
+                    });
+                }
+            }
+            self.bridge_status[index].error = error;
+        }
+        frames
+    }
+
+    /// Counts a frame the bridge sent, or records why it couldn't.
+    pub fn record_bridge_result(&mut self, mapping: usize, result: Result<(), String>) {
+        if let Some(status) = self.bridge_status.get_mut(mapping) {
+            match result {
+                Ok(()) => status.frames += 1,
+                Err(e) => status.error = Some(e),
+            }
+        }
+    }
+
+    /// The sACN universes bridge mappings read from, so the listener joins them.
+    pub fn bridge_input_universes(&self) -> BTreeSet<u16> {
+        if !self.bridge_enabled {
+            return BTreeSet::new();
+        }
+        self.bridge_mappings
+            .iter()
+            .filter(|m| m.enabled && m.direction == BridgeDirection::SacnToArtNet)
+            .flat_map(|m| m.pairs().map(|(universe, _)| universe))
+            .collect()
+    }
+
+    pub fn set_bridge_enabled(&mut self, enabled: bool) {
+        self.bridge_enabled = enabled;
+        self.add_log(
+            LogLevel::Info,
+            format!("Bridge {}", if enabled { "started" } else { "stopped" }),
+        );
+        if !enabled {
+            for status in &mut self.bridge_status {
+                *status = BridgeStatus::default();
+            }
+        }
+        self.save_bridge();
+    }
+
+    pub fn set_bridge_rate(&mut self, rate: u32) {
+        self.bridge_rate = rate.max(1);
+        self.save_bridge();
+    }
+
+    pub fn add_bridge_mapping(&mut self, mapping: BridgeMapping) {
+        self.bridge_mappings.push(mapping);
+        self.save_bridge();
+    }
+
+    pub fn remove_bridge_mapping(&mut self, index: usize) {
+        if index < self.bridge_mappings.len() {
+            self.bridge_mappings.remove(index);
+            if index < self.bridge_status.len() {
+                self.bridge_status.remove(index);
+            }
+            self.save_bridge();
+        }
+    }
+
+    /// Copies the bridge configuration into the settings and saves them.
+    pub fn save_bridge(&mut self) {
+        for mapping in &mut self.bridge_mappings {
+            mapping.count = mapping.count.clamp(1, MAX_BRIDGE_COUNT);
+            mapping.priority = mapping.priority.min(MAX_PRIORITY);
+        }
+        self.settings.bridge_enabled = self.bridge_enabled;
+        self.settings.bridge_rate = self.bridge_rate;
+        self.settings.bridge_mappings = self.bridge_mappings.clone();
+        if let Err(e) = self.save_settings() {
+            self.add_log(LogLevel::Warning, format!("Failed to save settings: {}", e));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{NetworkAdapter, SourceData};
+    use std::net::{IpAddr, Ipv4Addr};
+    use uuid::Uuid;
+
+    const OWN_IP: IpAddr = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+
+    fn state() -> AppState {
+        let mut state = AppState::new();
+        state.network_adapters = vec![NetworkAdapter {
+            name: "eth0".to_string(),
+            ip: OWN_IP,
+            broadcast: None,
+            description: String::new(),
+            is_available: true,
+        }];
+        state.bridge_enabled = true;
+        state
+    }
+
+    /// Adds a source whose first channel is at `level`.
+    fn add_source(
+        state: &mut AppState,
+        universe: u16,
+        cid: Uuid,
+        ip: IpAddr,
+        protocol: Protocol,
+        level: u8,
+    ) {
+        let mut channels = [0; 512];
+        channels[0] = level;
+        let source = SourceData::new(cid, "Source".to_string(), ip, protocol, 100, 0, channels);
+        state.update_universe(universe, source);
+    }
+
+    fn mapping(direction: BridgeDirection, count: u16) -> BridgeMapping {
+        BridgeMapping {
+            direction,
+            count,
+            ..BridgeMapping::new()
+        }
+    }
+
+    #[test]
+    fn leaves_out_our_own_output() {
+        let mut state = state();
+        state.bridge_mappings = vec![mapping(BridgeDirection::SacnToArtNet, 3)];
+        let other = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2));
+        let own_cid = state.source_cid;
+
+        // Universe 1: our CID next to another source, which is all that's sent
+        add_source(&mut state, 1, own_cid, other, Protocol::Sacn, 255);
+        add_source(&mut state, 1, Uuid::new_v4(), other, Protocol::Sacn, 40);
+        // Universe 2: only a source from one of our addresses
+        add_source(&mut state, 2, Uuid::new_v4(), OWN_IP, Protocol::Sacn, 255);
+        // Universe 3: only another source
+        add_source(&mut state, 3, Uuid::new_v4(), other, Protocol::Sacn, 80);
+
+        let frames = state.bridge_frames();
+        let sent: Vec<(String, u8)> = frames
+            .iter()
+            .map(|frame| (frame.target.to_string(), frame.levels[0]))
+            .collect();
+        assert_eq!(
+            sent,
+            vec![
+                ("Art-Net 0:0:0".to_string(), 40),
+                ("Art-Net 0:0:2".to_string(), 80)
+            ]
+        );
+        assert_eq!(state.bridge_status[0].live, 2);
+        assert!(state.bridge_status[0].looped);
+    }
+
+    #[test]
+    fn refuses_universes_the_sender_owns() {
+        let mut state = state();
+        state.bridge_mappings = vec![mapping(BridgeDirection::ArtNetToSacn, 2)];
+        let node = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 50));
+        for universe in [1, 2] {
+            add_source(
+                &mut state,
+                universe,
+                Uuid::new_v4(),
+                node,
+                Protocol::ArtNet,
+                60,
+            );
+        }
+
+        // The sender has universe 2 rather than its default universe 1
+        state.transmit_session.remove_universe(1);
+        state.transmit_session.add_universe(2);
+        let targets = |state: &mut AppState| -> Vec<String> {
+            state
+                .bridge_frames()
+                .iter()
+                .map(|frame| frame.target.to_string())
+                .collect()
+        };
+        assert_eq!(
+            targets(&mut state),
+            vec!["sACN universe 1", "sACN universe 2"],
+            "universe 2 isn't sent while Auto Send is off"
+        );
+        assert_eq!(state.bridge_status[0].error, None);
+
+        state.auto_send_enabled = true;
+        assert_eq!(targets(&mut state), vec!["sACN universe 1"]);
+        assert_eq!(
+            state.bridge_status[0].error.as_deref(),
+            Some("Universe 2 is already sent by the DMX sender")
+        );
+
+        state.transmit_session.universe_mut(2).unwrap().enabled = false;
+        assert_eq!(targets(&mut state).len(), 2, "disabled in the sender");
+        assert_eq!(state.bridge_status[0].error, None);
+    }
+}
@@ -0,0 +1,311 @@
+use super::{AppState, LogLevel, MAX_UNIVERSE, MIN_UNIVERSE};
+use anyhow::Result;
+
+/// A parsed command line such as `U3/1 thru 24 + 30 @ full`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChannelCommand {
+    pub universe: Option<u16>, // None targets the universe being edited
+    pub channels: Vec<usize>,  // 0-based, in the order they were typed
+    pub value: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Number(u32),
+    Word(String),
+    Symbol(char),
+}
+
+fn tokenize(line: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = line.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c.is_ascii_digit() {
+            let mut number = String::new();
+            while let Some(&d) = chars.peek().filter(|d| d.is_ascii_digit()) {
+                number.push(d);
+                chars.next();
+            }
+            let number = number
+                .parse()
+                .map_err(|_| anyhow::anyhow!("Number too large: {}", number))?;
+            tokens.push(Token::Number(number));
+        } else if c.is_ascii_alphabetic() {
+            let mut word = String::new();
+            while let Some(&a) = chars.peek().filter(|a| a.is_ascii_alphabetic()) {
+                word.push(a.to_ascii_lowercase());
+                chars.next();
+            }
+            tokens.push(Token::Word(word));
+        } else if matches!(c, '@' | '+' | '-' | '/' | '%') {
+            tokens.push(Token::Symbol(c));
+            chars.next();
+        } else {
+            return Err(anyhow::anyhow!("Unexpected character '{}'", c));
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn number(&mut self, what: &str) -> Result<u32> {
+        match self.next() {
+            Some(Token::Number(n)) => Ok(n),
+            _ => Err(anyhow::anyhow!("Expected {}", what)),
+        }
+    }
+
+    fn channel(&mut self) -> Result<usize> {
+        let channel = self.number("a channel number")?;
+        if !(1..=512).contains(&channel) {
+            return Err(anyhow::anyhow!("Channel {} is outside 1-512", channel));
+        }
+        Ok(channel as usize - 1)
+    }
+
+    /// `N` or `N thru M`, descending ranges are allowed.
+    fn range(&mut self) -> Result<Vec<usize>> {
+        let first = self.channel()?;
+        if !matches!(self.peek(), Some(Token::Word(w)) if w == "thru" || w == "through") {
+            return Ok(vec![first]);
+        }
+        self.next();
+
+        let last = self.channel()?;
+        if first <= last {
+            Ok((first..=last).collect())
+        } else {
+            Ok((last..=first).rev().collect())
+        }
+    }
+
+    fn universe(&mut self) -> Result<Option<u16>> {
+        if !matches!(self.peek(), Some(Token::Word(w)) if w == "u") {
+            return Ok(None);
+        }
+        self.next();
+
+        let universe = self.number("a universe number after 'U'")?;
+        if !(MIN_UNIVERSE as u32..=MAX_UNIVERSE as u32).contains(&universe) {
+            return Err(anyhow::anyhow!(
+                "Universe {} is outside {}-{}",
+                universe,
+                MIN_UNIVERSE,
+                MAX_UNIVERSE
+            ));
+        }
+        if self.next() != Some(Token::Symbol('/')) {
+            return Err(anyhow::anyhow!("Expected '/' after the universe number"));
+        }
+        Ok(Some(universe as u16))
+    }
+
+    fn value(&mut self) -> Result<u8> {
+        match self.next() {
+            Some(Token::Number(n)) if self.peek() == Some(&Token::Symbol('%')) => {
+                self.next();
+                if n > 100 {
+                    return Err(anyhow::anyhow!("Level {}% is outside 0-100%", n));
+                }
+                Ok(((n * 255 + 50) / 100) as u8)
+            }
+            Some(Token::Number(n)) if n <= 255 => Ok(n as u8),
+            Some(Token::Number(n)) => Err(anyhow::anyhow!("Level {} is outside 0-255", n)),
+            Some(Token::Word(w)) if w == "full" => Ok(255),
+            Some(Token::Word(w)) if w == "out" => Ok(0),
+            _ => Err(anyhow::anyhow!(
+                "Expected a level (0-255, a percentage, full or out)"
+            )),
+        }
+    }
+}
+
+impl ChannelCommand {
+    /// Parses `[U<universe>/] <selection> @ <level>`, where the selection is
+    /// ranges joined with `+` (add) and `-` (remove), and the level is
+    /// 0-255, a percentage such as `50%`, `full` or `out`.
+    pub fn parse(line: &str) -> Result<Self> {
+        let mut parser = Parser {
+            tokens: tokenize(line)?,
+            pos: 0,
+        };
+
+        let universe = parser.universe()?;
+        let mut channels = parser.range()?;
+        loop {
+            match parser.next() {
+                Some(Token::Symbol('+')) => {
+                    for channel in parser.range()? {
+                        if !channels.contains(&channel) {
+                            channels.push(channel);
+                        }
+                    }
+                }
+                Some(Token::Symbol('-')) => {
+                    let removed = parser.range()?;
+                    channels.retain(|c| !removed.contains(c));
+                }
+                Some(Token::Symbol('@')) => break,
+                _ => return Err(anyhow::anyhow!("Expected 'thru', '+', '-' or '@'")),
+            }
+        }
+        let value = parser.value()?;
+
+        if parser.peek().is_some() {
+            return Err(anyhow::anyhow!("Unexpected input after the level"));
+        }
+
+        Ok(Self {
+            universe,
+            channels,
+            value,
+        })
+    }
+}
+
+impl AppState {
+    /// Runs a command line against the transmit session. Commands without a
+    /// universe prefix go to `default_universe`, and the new levels are saved
+    /// straight away. Returns true on success, errors are logged.
+    pub fn run_channel_command(&mut self, line: &str, default_universe: u16) -> bool {
+        let command = match ChannelCommand::parse(line) {
+            Ok(command) => command,
+            Err(e) => {
+                self.add_log(LogLevel::Error, format!("Command '{}': {}", line, e));
+                return false;
+            }
+        };
+
+        let universe = command.universe.unwrap_or(default_universe);
+        let Some(target) = self.transmit_session.universe_mut(universe) else {
+            self.add_log(
+                LogLevel::Error,
+                format!(
+                    "Command '{}': universe {} is not in the transmit session",
+                    line, universe
+                ),
+            );
+            return false;
+        };
+
+        target.set_channels(&command.channels, command.value);
+        self.save_transmit_session();
+        self.add_log(
+            LogLevel::Info,
+            format!(
+                "Command '{}': set {} channels on universe {} to {}",
+                line,
+                command.channels.len(),
+                universe,
+                command.value
+            ),
+        );
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(line: &str) -> ChannelCommand {
+        ChannelCommand::parse(line).unwrap()
+    }
+
+    fn error(line: &str) -> String {
+        ChannelCommand::parse(line).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn parses_ranges() {
+        assert_eq!(
+            parse("1 thru 4 @ 50"),
+            ChannelCommand {
+                universe: None,
+                channels: vec![0, 1, 2, 3],
+                value: 50,
+            }
+        );
+        assert_eq!(parse("3 through 1 @ 0").channels, vec![2, 1, 0]);
+        assert_eq!(parse("7@9").channels, vec![6]);
+        assert_eq!(parse("1 THRU 512 @ 1").channels.len(), 512);
+    }
+
+    #[test]
+    fn adds_and_removes_channels() {
+        assert_eq!(parse("12 + 14 + 16 @ full").channels, vec![11, 13, 15]);
+        assert_eq!(parse("1 thru 3 + 2 + 5 @ 1").channels, vec![0, 1, 2, 4]);
+        assert_eq!(
+            parse("1 thru 10 - 5 - 8 thru 9 @ out").channels,
+            vec![0, 1, 2, 3, 5, 6, 9]
+        );
+        assert_eq!(parse("1 - 1 + 1 @ 1").channels, vec![0]);
+    }
+
+    #[test]
+    fn targets_a_universe() {
+        let command = parse("U3/1 thru 512 @ 255");
+        assert_eq!(command.universe, Some(3));
+        assert_eq!(command.channels.len(), 512);
+        assert_eq!(command.value, 255);
+        assert_eq!(parse("u 63999 / 1 @ 1").universe, Some(63999));
+
+        assert_eq!(error("U0/1 @ 1"), "Universe 0 is outside 1-63999");
+        assert_eq!(error("U3 1 @ 1"), "Expected '/' after the universe number");
+    }
+
+    #[test]
+    fn parses_levels() {
+        assert_eq!(parse("1 @ full").value, 255);
+        assert_eq!(parse("1 @ Out").value, 0);
+        assert_eq!(parse("1 @ 0").value, 0);
+        assert_eq!(parse("1 @ 255").value, 255);
+        assert_eq!(parse("1 @ 0%").value, 0);
+        assert_eq!(parse("1 @ 50%").value, 128);
+        assert_eq!(parse("1 @ 100 %").value, 255);
+
+        assert_eq!(error("1 @ 256"), "Level 256 is outside 0-255");
+        assert_eq!(error("1 @ 101%"), "Level 101% is outside 0-100%");
+        assert_eq!(
+            error("1 @ half"),
+            "Expected a level (0-255, a percentage, full or out)"
+        );
+    }
+
+    #[test]
+    fn rejects_out_of_range_channels() {
+        assert_eq!(error("0 @ 1"), "Channel 0 is outside 1-512");
+        assert_eq!(error("1 thru 513 @ 1"), "Channel 513 is outside 1-512");
+        assert_eq!(error("1 + 600 @ 1"), "Channel 600 is outside 1-512");
+        assert_eq!(error("99999999999 @ 1"), "Number too large: 99999999999");
+    }
+
+    #[test]
+    fn rejects_malformed_commands() {
+        assert_eq!(error(""), "Expected a channel number");
+        assert_eq!(error("1 thru @ 1"), "Expected a channel number");
+        assert_eq!(error("1 2 @ 1"), "Expected 'thru', '+', '-' or '@'");
+        assert_eq!(error("1"), "Expected 'thru', '+', '-' or '@'");
+        assert_eq!(error("1 @ 1 2"), "Unexpected input after the level");
+        assert_eq!(error("1 # 1"), "Unexpected character '#'");
+    }
+}
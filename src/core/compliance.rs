@@ -0,0 +1,883 @@
+use super::{AppState, LogLevel, NETWORK_DATA_LOSS_TIMEOUT};
+use crate::network::e131::{
+    u16_at, u32_at, ACN_PACKET_IDENTIFIER, CID_OFFSET, DATA_UNIVERSE_OFFSET, DISCOVERY_PDU_OFFSET,
+    DMP_ADDRESS_AND_DATA_TYPE, DMP_PDU_OFFSET, FRAMING_PDU_OFFSET, MAX_DISCOVERY_UNIVERSES,
+    MAX_PRIORITY, MAX_PROPERTY_VALUES, MAX_UNIVERSE, PROPERTY_VALUES_OFFSET, ROOT_LAYER_END,
+    ROOT_PDU_OFFSET, SOURCE_NAME_FIELD, STREAM_TERMINATED, SYNC_PACKET_LEN, UNIVERSE_LIST_OFFSET,
+    VECTOR_DMP_SET_PROPERTY, VECTOR_E131_DATA_PACKET, VECTOR_E131_EXTENDED_DISCOVERY,
+    VECTOR_E131_EXTENDED_SYNCHRONIZATION, VECTOR_ROOT_E131_DATA, VECTOR_ROOT_E131_EXTENDED,
+    VECTOR_UNIVERSE_DISCOVERY_UNIVERSE_LIST,
+};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use uuid::Uuid;
+
+/// Null start code data must be repeated at least every second while it
+/// isn't changing (E1.31 6.6.1). Packets are timed when they arrive rather
+/// than when they were sent, so the limit sits 100 ms above the spec's
+/// maximum: network and scheduling delay alone shouldn't flag a sender that
+/// repeats on time.
+const KEEP_ALIVE_LIMIT: chrono::Duration = chrono::Duration::milliseconds(1100);
+
+/// An E1.31-2018 requirement that a packet or stream can break.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Rule {
+    Truncated,
+    Preamble,
+    Postamble,
+    PacketIdentifier,
+    FlagsAndLength,
+    RootVector,
+    FramingVector,
+    SourceName,
+    Priority,
+    SyncAddress,
+    Universe,
+    Reserved,
+    DmpLayer,
+    PropertyCount,
+    DiscoveryLayer,
+    Sequence,
+    KeepAlive,
+    Termination,
+}
+
+impl Rule {
+    pub fn description(self) -> &'static str {
+        match self {
+            Rule::Truncated => "Packets must be long enough for all of their layers",
+            Rule::Preamble => "Preamble size must be 0x0010",
+            Rule::Postamble => "Post-amble size must be 0x0000",
+            Rule::PacketIdentifier => "ACN packet identifier must be \"ASC-E1.17\"",
+            Rule::FlagsAndLength => "PDU flags must be 0x7 and PDU lengths must match the packet",
+            Rule::RootVector => {
+                "Root vector must be VECTOR_ROOT_E131_DATA or VECTOR_ROOT_E131_EXTENDED"
+            }
+            Rule::FramingVector => "Framing and discovery vectors must match the packet type",
+            Rule::SourceName => "Source name must be null-terminated UTF-8",
+            Rule::Priority => "Priority must be 0-200",
+            Rule::SyncAddress => {
+                "Sync address must be 0-63999 in data packets, 1-63999 in sync packets"
+            }
+            Rule::Universe => "Universe must be 1-63999",
+            Rule::Reserved => "Reserved fields and option bits must be 0",
+            Rule::DmpLayer => {
+                "DMP layer must be Set Property, type 0xA1, first address 0, increment 1"
+            }
+            Rule::PropertyCount => "Property value count must be 1-513 and match the packet length",
+            Rule::DiscoveryLayer => {
+                "Universe discovery lists must be sorted, in range and on a valid page"
+            }
+            Rule::Sequence => {
+                "Packets must not repeat or fall up to 19 behind the last sequence number"
+            }
+            Rule::KeepAlive => "Unchanged DMX data must be re-sent at least every second",
+            Rule::Termination => {
+                "Sources must send within the data loss timeout or terminate the stream"
+            }
+        }
+    }
+}
+
+/// How often a source broke one rule, with the latest example.
+#[derive(Debug, Clone)]
+pub struct Violation {
+    pub count: u64,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+    pub detail: String,
+}
+
+/// Sequence and timing of one universe sent by a source.
+#[derive(Debug, Clone)]
+struct StreamTiming {
+    last_sequence: u8,
+    last_data: Option<DateTime<Utc>>, // last null start code packet
+    last_seen: DateTime<Utc>,
+    terminated: bool,
+    stopped: bool, // went silent without terminating, already reported
+}
+
+impl StreamTiming {
+    /// A stream that stopped or terminated and then sends data again starts
+    /// its sequence and timing afresh.
+    fn restarted_by(&self, terminated: bool) -> bool {
+        self.stopped || (self.terminated && !terminated)
+    }
+}
+
+/// Everything the compliance checker found about one source.
+#[derive(Debug, Clone)]
+pub struct SourceCompliance {
+    pub source_ip: IpAddr,
+    pub cid: Option<Uuid>, // None if its packets were too short to carry one
+    pub source_name: String,
+    pub packets: u64,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+    pub violations: BTreeMap<Rule, Violation>,
+    streams: HashMap<u16, StreamTiming>,
+    sync_sequences: HashMap<u16, u8>, // by sync address
+}
+
+impl SourceCompliance {
+    fn new(source_ip: IpAddr, cid: Option<Uuid>, now: DateTime<Utc>) -> Self {
+        Self {
+            source_ip,
+            cid,
+            source_name: String::new(),
+            packets: 0,
+            first_seen: now,
+            last_seen: now,
+            violations: BTreeMap::new(),
+            streams: HashMap::new(),
+            sync_sequences: HashMap::new(),
+        }
+    }
+
+    fn report(&mut self, rule: Rule, detail: String, now: DateTime<Utc>) {
+        let violation = self.violations.entry(rule).or_insert_with(|| Violation {
+            count: 0,
+            first_seen: now,
+            last_seen: now,
+            detail: String::new(),
+        });
+        violation.count += 1;
+        violation.last_seen = now;
+        violation.detail = detail;
+    }
+
+    pub fn violation_count(&self) -> u64 {
+        self.violations
+            .values()
+            .map(|violation| violation.count)
+            .sum()
+    }
+
+    /// Checks a data packet's sequence number and timing against the
+    /// previous packet on its universe.
+    fn check_data(&mut self, data: DataFields, now: DateTime<Utc>) {
+        let DataFields {
+            universe,
+            sequence,
+            start_code,
+            terminated,
+        } = data;
+
+        let fresh = StreamTiming {
+            last_sequence: sequence,
+            last_data: (start_code == 0).then_some(now),
+            last_seen: now,
+            terminated,
+            stopped: false,
+        };
+        let stream = match self.streams.get_mut(&universe) {
+            Some(stream) if !stream.restarted_by(terminated) => stream,
+            _ => {
+                self.streams.insert(universe, fresh);
+                return;
+            }
+        };
+
+        let mut violations = Vec::new();
+        if let Some(detail) = check_sequence(stream.last_sequence, sequence) {
+            violations.push((Rule::Sequence, format!("Universe {}: {}", universe, detail)));
+        } else {
+            stream.last_sequence = sequence;
+        }
+
+        if start_code == 0 && !terminated {
+            if let Some(last_data) = stream.last_data {
+                let gap = now - last_data;
+                if gap > KEEP_ALIVE_LIMIT {
+                    violations.push((
+                        Rule::KeepAlive,
+                        format!(
+                            "Universe {}: {} ms between data packets",
+                            universe,
+                            gap.num_milliseconds()
+                        ),
+                    ));
+                }
+            }
+            stream.last_data = Some(now);
+        }
+        stream.last_seen = now;
+        stream.terminated = terminated;
+
+        for (rule, detail) in violations {
+            self.report(rule, detail, now);
+        }
+    }
+
+    fn check_sync(&mut self, sync_address: u16, sequence: u8, now: DateTime<Utc>) {
+        if let Some(&last) = self.sync_sequences.get(&sync_address) {
+            if let Some(detail) = check_sequence(last, sequence) {
+                self.report(
+                    Rule::Sequence,
+                    format!("Sync universe {}: {}", sync_address, detail),
+                    now,
+                );
+                return;
+            }
+        }
+        self.sync_sequences.insert(sync_address, sequence);
+    }
+}
+
+/// Describes a sequence number that repeats or goes back on the last one.
+/// A jump back of 20 or more is a source restarting, which is allowed.
+fn check_sequence(last: u8, sequence: u8) -> Option<String> {
+    let step = sequence.wrapping_sub(last) as i8;
+    if step == 0 {
+        Some(format!("sequence {} repeated", sequence))
+    } else if step < 0 && step > -20 {
+        Some(format!("sequence {} arrived after {}", sequence, last))
+    } else {
+        None
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct DataFields {
+    universe: u16,
+    sequence: u8,
+    start_code: u8,
+    terminated: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum PacketKind {
+    Data(DataFields),
+    Sync { sync_address: u16, sequence: u8 },
+    Other,
+}
+
+/// What a single packet's bytes say, found without trusting any of them.
+struct PacketCheck {
+    cid: Option<Uuid>,
+    source_name: Option<String>,
+    kind: PacketKind,
+    violations: Vec<(Rule, String)>,
+}
+
+impl PacketCheck {
+    fn fail(&mut self, rule: Rule, detail: String) {
+        self.violations.push((rule, detail));
+    }
+}
+
+/// Checks every field of a packet received on the sACN port.
+fn check_packet(buf: &[u8]) -> PacketCheck {
+    let mut check = PacketCheck {
+        cid: None,
+        source_name: None,
+        kind: PacketKind::Other,
+        violations: Vec::new(),
+    };
+
+    if buf.len() < ROOT_LAYER_END {
+        check.fail(
+            Rule::Truncated,
+            format!("{} bytes, too short for the root layer", buf.len()),
+        );
+        return check;
+    }
+
+    let preamble = u16_at(buf, 0);
+    if preamble != 0x0010 {
+        check.fail(Rule::Preamble, format!("Preamble size {:#06x}", preamble));
+    }
+    let postamble = u16_at(buf, 2);
+    if postamble != 0x0000 {
+        check.fail(
+            Rule::Postamble,
+            format!("Post-amble size {:#06x}", postamble),
+        );
+    }
+    if &buf[4..16] != ACN_PACKET_IDENTIFIER {
+        check.fail(
+            Rule::PacketIdentifier,
+            format!("Identifier {:?}", String::from_utf8_lossy(&buf[4..16])),
+        );
+    }
+    check_flags_and_length(&mut check, buf, ROOT_PDU_OFFSET, "Root");
+    check.cid = Uuid::from_bytes(&buf[CID_OFFSET..ROOT_LAYER_END]).ok();
+
+    match u32_at(buf, 18) {
+        VECTOR_ROOT_E131_DATA => check_data_packet(&mut check, buf),
+        VECTOR_ROOT_E131_EXTENDED if buf.len() < FRAMING_PDU_OFFSET + 6 => check.fail(
+            Rule::Truncated,
+            format!("{} bytes, too short for the framing layer", buf.len()),
+        ),
+        VECTOR_ROOT_E131_EXTENDED => match u32_at(buf, 40) {
+            VECTOR_E131_EXTENDED_SYNCHRONIZATION => check_sync_packet(&mut check, buf),
+            VECTOR_E131_EXTENDED_DISCOVERY => check_discovery_packet(&mut check, buf),
+            vector => check.fail(
+                Rule::FramingVector,
+                format!("Extended framing vector {:#010x}", vector),
+            ),
+        },
+        vector => check.fail(Rule::RootVector, format!("Root vector {:#010x}", vector)),
+    }
+    check
+}
+
+/// Checks the flags and length field of the PDU starting at `offset`, which
+/// should run to the end of the packet.
+fn check_flags_and_length(check: &mut PacketCheck, buf: &[u8], offset: usize, layer: &str) {
+    let value = u16_at(buf, offset);
+    let flags = value >> 12;
+    let length = (value & 0x0fff) as usize;
+    if flags != 0x7 {
+        check.fail(
+            Rule::FlagsAndLength,
+            format!("{} layer flags {:#x}", layer, flags),
+        );
+    }
+    if length != buf.len() - offset {
+        check.fail(
+            Rule::FlagsAndLength,
+            format!(
+                "{} layer length {} but {} bytes follow",
+                layer,
+                length,
+                buf.len() - offset
+            ),
+        );
+    }
+}
+
+/// Checks the 64 byte source name field.
+fn check_source_name(check: &mut PacketCheck, buf: &[u8]) {
+    let field = &buf[SOURCE_NAME_FIELD];
+    let Some(end) = field.iter().position(|&b| b == 0) else {
+        check.fail(
+            Rule::SourceName,
+            "Source name is not null-terminated".to_string(),
+        );
+        check.source_name = Some(String::from_utf8_lossy(field).to_string());
+        return;
+    };
+    match std::str::from_utf8(&field[..end]) {
+        Ok(name) => check.source_name = Some(name.to_string()),
+        Err(e) => {
+            check.fail(Rule::SourceName, format!("Source name is not UTF-8: {}", e));
+            check.source_name = Some(String::from_utf8_lossy(&field[..end]).to_string());
+        }
+    }
+}
+
+fn check_data_packet(check: &mut PacketCheck, buf: &[u8]) {
+    if buf.len() <= PROPERTY_VALUES_OFFSET {
+        check.fail(
+            Rule::Truncated,
+            format!("{} bytes, too short for a data packet", buf.len()),
+        );
+        return;
+    }
+
+    check_flags_and_length(check, buf, FRAMING_PDU_OFFSET, "Framing");
+    let vector = u32_at(buf, 40);
+    if vector != VECTOR_E131_DATA_PACKET {
+        check.fail(
+            Rule::FramingVector,
+            format!("Data framing vector {:#010x}", vector),
+        );
+    }
+    check_source_name(check, buf);
+
+    let priority = buf[108];
+    if priority > MAX_PRIORITY {
+        check.fail(Rule::Priority, format!("Priority {}", priority));
+    }
+    let sync_address = u16_at(buf, 109);
+    if sync_address > MAX_UNIVERSE {
+        check.fail(
+            Rule::SyncAddress,
+            format!("Synchronization address {}", sync_address),
+        );
+    }
+    let options = buf[112];
+    if options & 0x1f != 0 {
+        check.fail(Rule::Reserved, format!("Options {:#04x}", options));
+    }
+    let universe = u16_at(buf, DATA_UNIVERSE_OFFSET);
+    if !(1..=MAX_UNIVERSE).contains(&universe) {
+        check.fail(Rule::Universe, format!("Universe {}", universe));
+    }
+
+    check_flags_and_length(check, buf, DMP_PDU_OFFSET, "DMP");
+    if buf[117] != VECTOR_DMP_SET_PROPERTY {
+        check.fail(Rule::DmpLayer, format!("DMP vector {:#04x}", buf[117]));
+    }
+    if buf[118] != DMP_ADDRESS_AND_DATA_TYPE {
+        check.fail(
+            Rule::DmpLayer,
+            format!("Address and data type {:#04x}", buf[118]),
+        );
+    }
+    let first_address = u16_at(buf, 119);
+    if first_address != 0 {
+        check.fail(
+            Rule::DmpLayer,
+            format!("First property address {}", first_address),
+        );
+    }
+    let increment = u16_at(buf, 121);
+    if increment != 1 {
+        check.fail(Rule::DmpLayer, format!("Address increment {}", increment));
+    }
+    let count = u16_at(buf, 123) as usize;
+    let values = buf.len() - PROPERTY_VALUES_OFFSET;
+    if !(1..=MAX_PROPERTY_VALUES).contains(&count) || count != values {
+        check.fail(
+            Rule::PropertyCount,
+            format!("Property value count {} with {} values", count, values),
+        );
+    }
+
+    check.kind = PacketKind::Data(DataFields {
+        universe,
+        sequence: buf[111],
+        start_code: buf[PROPERTY_VALUES_OFFSET],
+        terminated: options & STREAM_TERMINATED != 0,
+    });
+}
+
+fn check_sync_packet(check: &mut PacketCheck, buf: &[u8]) {
+    if buf.len() < SYNC_PACKET_LEN {
+        check.fail(
+            Rule::Truncated,
+            format!("{} bytes, too short for a sync packet", buf.len()),
+        );
+        return;
+    }
+
+    check_flags_and_length(check, buf, FRAMING_PDU_OFFSET, "Framing");
+    let sync_address = u16_at(buf, 45);
+    if !(1..=MAX_UNIVERSE).contains(&sync_address) {
+        check.fail(
+            Rule::SyncAddress,
+            format!("Sync packet address {}", sync_address),
+        );
+    }
+    let reserved = u16_at(buf, 47);
+    if reserved != 0 {
+        check.fail(Rule::Reserved, format!("Sync reserved {:#06x}", reserved));
+    }
+
+    check.kind = PacketKind::Sync {
+        sync_address,
+        sequence: buf[44],
+    };
+}
+
+fn check_discovery_packet(check: &mut PacketCheck, buf: &[u8]) {
+    if buf.len() < UNIVERSE_LIST_OFFSET {
+        check.fail(
+            Rule::Truncated,
+            format!("{} bytes, too short for a discovery packet", buf.len()),
+        );
+        return;
+    }
+
+    check_flags_and_length(check, buf, FRAMING_PDU_OFFSET, "Framing");
+    check_source_name(check, buf);
+    let reserved = u32_at(buf, 108);
+    if reserved != 0 {
+        check.fail(
+            Rule::Reserved,
+            format!("Discovery reserved {:#010x}", reserved),
+        );
+    }
+
+    check_flags_and_length(check, buf, DISCOVERY_PDU_OFFSET, "Universe discovery");
+    let vector = u32_at(buf, 114);
+    if vector != VECTOR_UNIVERSE_DISCOVERY_UNIVERSE_LIST {
+        check.fail(
+            Rule::FramingVector,
+            format!("Universe discovery vector {:#010x}", vector),
+        );
+    }
+    let (page, last_page) = (buf[118], buf[119]);
+    if page > last_page {
+        check.fail(
+            Rule::DiscoveryLayer,
+            format!("Page {} of last page {}", page, last_page),
+        );
+    }
+
+    let list = &buf[UNIVERSE_LIST_OFFSET..];
+    if !list.len().is_multiple_of(2) {
+        check.fail(
+            Rule::DiscoveryLayer,
+            format!("Universe list of {} bytes", list.len()),
+        );
+    }
+    let universes: Vec<u16> = list
+        .chunks_exact(2)
+        .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+        .collect();
+    if universes.len() > MAX_DISCOVERY_UNIVERSES {
+        check.fail(
+            Rule::DiscoveryLayer,
+            format!("{} universes on one page", universes.len()),
+        );
+    }
+    if let Some(&universe) = universes.iter().find(|u| !(1..=MAX_UNIVERSE).contains(*u)) {
+        check.fail(
+            Rule::DiscoveryLayer,
+            format!("Universe {} in the list", universe),
+        );
+    }
+    if let Some(pair) = universes.windows(2).find(|pair| pair[0] >= pair[1]) {
+        check.fail(
+            Rule::DiscoveryLayer,
+            format!("Universe {} listed after {}", pair[1], pair[0]),
+        );
+    }
+}
+
+impl AppState {
+    /// Checks a packet received on the sACN port against E1.31-2018,
+    /// before it is parsed, so malformed packets are reported too.
+    pub fn check_compliance(&mut self, buf: &[u8], source_ip: IpAddr) {
+        let now = Utc::now();
+        let check = check_packet(buf);
+        let source = self
+            .compliance
+            .entry((source_ip, check.cid))
+            .or_insert_with(|| SourceCompliance::new(source_ip, check.cid, now));
+
+        source.packets += 1;
+        source.last_seen = now;
+        if let Some(name) = check.source_name {
+            source.source_name = name;
+        }
+        for (rule, detail) in check.violations {
+            source.report(rule, detail, now);
+        }
+
+        match check.kind {
+            PacketKind::Data(data) => source.check_data(data, now),
+            PacketKind::Sync {
+                sync_address,
+                sequence,
+            } => source.check_sync(sync_address, sequence, now),
+            PacketKind::Other => {}
+        }
+    }
+
+    /// Reports streams that went silent without terminating. Should be called
+    /// periodically by the listener.
+    pub fn check_compliance_timeout(&mut self) {
+        let now = Utc::now();
+        for source in self.compliance.values_mut() {
+            source.streams.retain(|_, stream| {
+                !(stream.terminated && now - stream.last_seen > NETWORK_DATA_LOSS_TIMEOUT)
+            });
+
+            let stopped: Vec<u16> = source
+                .streams
+                .iter_mut()
+                .filter(|(_, stream)| {
+                    !stream.stopped && now - stream.last_seen > NETWORK_DATA_LOSS_TIMEOUT
+                })
+                .map(|(&universe, stream)| {
+                    stream.stopped = true;
+                    universe
+                })
+                .collect();
+            for universe in stopped {
+                source.report(
+                    Rule::Termination,
+                    format!(
+                        "Universe {} silent for {} ms without Stream_Terminated",
+                        universe,
+                        NETWORK_DATA_LOSS_TIMEOUT.num_milliseconds()
+                    ),
+                    now,
+                );
+            }
+        }
+    }
+
+    /// Forgets every source's findings, so checking starts over.
+    pub fn reset_compliance(&mut self) {
+        self.compliance.clear();
+    }
+
+    /// Sources the checker has seen, ordered by address then name.
+    pub fn compliance_sources(&self) -> Vec<&SourceCompliance> {
+        let mut sources: Vec<&SourceCompliance> = self.compliance.values().collect();
+        sources.sort_by(|a, b| {
+            (a.source_ip, &a.source_name, a.cid).cmp(&(b.source_ip, &b.source_name, b.cid))
+        });
+        sources
+    }
+
+    /// A plain text report of every source and the rules it broke.
+    pub fn compliance_report(&self) -> String {
+        let format_time = |time: DateTime<Utc>| {
+            time.with_timezone(&chrono::Local)
+                .format("%Y-%m-%d %H:%M:%S%.3f")
+                .to_string()
+        };
+
+        let mut report = String::new();
+        let _ = writeln!(report, "E1.31-2018 compliance report");
+        let _ = writeln!(report, "Generated: {}", format_time(Utc::now()));
+        let _ = writeln!(report, "Sources: {}", self.compliance.len());
+
+        for source in self.compliance_sources() {
+            let _ = writeln!(report);
+            let _ = writeln!(
+                report,
+                "{} ({})",
+                if source.source_name.is_empty() {
+                    "<unnamed>"
+                } else {
+                    &source.source_name
+                },
+                source.source_ip
+            );
+            let _ = writeln!(
+                report,
+                "  CID: {}",
+                source
+                    .cid
+                    .map_or("unknown".to_string(), |cid| cid.to_string())
+            );
+            let _ = writeln!(
+                report,
+                "  Packets checked: {} from {} to {}",
+                source.packets,
+                format_time(source.first_seen),
+                format_time(source.last_seen)
+            );
+
+            if source.violations.is_empty() {
+                let _ = writeln!(report, "  No violations");
+            }
+            for (rule, violation) in &source.violations {
+                let _ = writeln!(report, "  [{}x] {}", violation.count, rule.description());
+                let _ = writeln!(
+                    report,
+                    "        first {}, last {}: {}",
+                    format_time(violation.first_seen),
+                    format_time(violation.last_seen),
+                    violation.detail
+                );
+            }
+        }
+        report
+    }
+
+    /// Writes the compliance report to a file in the app's data directory.
+    pub fn export_compliance_report(&mut self) {
+        match self.write_compliance_report() {
+            Ok(path) => self.add_log(
+                LogLevel::Info,
+                format!("Saved compliance report to {}", path.display()),
+            ),
+            Err(e) => self.add_log(
+                LogLevel::Error,
+                format!("Failed to save compliance report: {}", e),
+            ),
+        }
+    }
+
+    fn write_compliance_report(&self) -> Result<PathBuf> {
+        let dirs = directories::ProjectDirs::from("com", "sacn-viewer", "sACN Viewer")
+            .ok_or_else(|| anyhow::anyhow!("No home directory to save reports in"))?;
+        let path = dirs.data_dir().join("reports").join(format!(
+            "compliance-{}.txt",
+            chrono::Local::now().format("%Y%m%d-%H%M%S")
+        ));
+        std::fs::create_dir_all(dirs.data_dir().join("reports"))?;
+        std::fs::write(&path, self.compliance_report())?;
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::e131_fixtures::{data_fixture, discovery_fixture, SYNC_FIXTURE};
+    use std::net::Ipv4Addr;
+
+    const SOURCE_IP: IpAddr = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 5));
+
+    /// The rules a single packet breaks, in the order they were found.
+    fn rules(buf: &[u8]) -> Vec<Rule> {
+        check_packet(buf)
+            .violations
+            .into_iter()
+            .map(|(rule, _)| rule)
+            .collect()
+    }
+
+    /// A data packet fixture with one change made to it.
+    fn data_with(change: impl FnOnce(&mut Vec<u8>)) -> Vec<u8> {
+        let mut buf = data_fixture();
+        change(&mut buf);
+        buf
+    }
+
+    #[test]
+    fn accepts_well_formed_packets() {
+        assert_eq!(rules(&data_fixture()), vec![]);
+        assert_eq!(rules(&SYNC_FIXTURE), vec![]);
+        assert_eq!(rules(&discovery_fixture()), vec![]);
+
+        let check = check_packet(&data_fixture());
+        assert_eq!(check.source_name.as_deref(), Some("Fixture"));
+        assert!(check.cid.is_some());
+    }
+
+    #[test]
+    fn reports_root_layer_rules() {
+        assert_eq!(rules(&data_fixture()[..20]), vec![Rule::Truncated]);
+        assert_eq!(rules(&data_with(|b| b[1] = 0x20)), vec![Rule::Preamble]);
+        assert_eq!(rules(&data_with(|b| b[3] = 0x01)), vec![Rule::Postamble]);
+        assert_eq!(
+            rules(&data_with(|b| b[4] = b'X')),
+            vec![Rule::PacketIdentifier]
+        );
+        assert_eq!(
+            rules(&data_with(|b| b[16] = 0x30)),
+            vec![Rule::FlagsAndLength]
+        );
+        assert_eq!(rules(&data_with(|b| b[21] = 0x05)), vec![Rule::RootVector]);
+    }
+
+    #[test]
+    fn reports_data_packet_rules() {
+        assert_eq!(
+            rules(&data_with(|b| b[43] = 0x03)),
+            vec![Rule::FramingVector]
+        );
+        assert_eq!(
+            rules(&data_with(|b| b[44..108].fill(b'a'))),
+            vec![Rule::SourceName]
+        );
+        assert_eq!(rules(&data_with(|b| b[44] = 0xff)), vec![Rule::SourceName]);
+        assert_eq!(rules(&data_with(|b| b[108] = 201)), vec![Rule::Priority]);
+        assert_eq!(
+            rules(&data_with(
+                |b| b[109..111].copy_from_slice(&64000u16.to_be_bytes())
+            )),
+            vec![Rule::SyncAddress]
+        );
+        assert_eq!(rules(&data_with(|b| b[112] = 0x01)), vec![Rule::Reserved]);
+        assert_eq!(
+            rules(&data_with(|b| b[113..115].copy_from_slice(&[0, 0]))),
+            vec![Rule::Universe]
+        );
+        assert_eq!(rules(&data_with(|b| b[117] = 0x01)), vec![Rule::DmpLayer]);
+        assert_eq!(
+            rules(&data_with(|b| b[124] = 0x06)),
+            vec![Rule::PropertyCount]
+        );
+    }
+
+    #[test]
+    fn reports_sync_and_discovery_rules() {
+        let mut sync = SYNC_FIXTURE;
+        sync[45..47].copy_from_slice(&[0, 0]);
+        assert_eq!(rules(&sync), vec![Rule::SyncAddress]);
+
+        let mut sync = SYNC_FIXTURE;
+        sync[48] = 0x01;
+        assert_eq!(rules(&sync), vec![Rule::Reserved]);
+
+        let mut discovery = discovery_fixture();
+        discovery[118] = 1;
+        assert_eq!(rules(&discovery), vec![Rule::DiscoveryLayer]);
+
+        let mut discovery = discovery_fixture();
+        discovery[120..124].copy_from_slice(&[0x00, 0x02, 0x00, 0x01]);
+        assert_eq!(rules(&discovery), vec![Rule::DiscoveryLayer]);
+    }
+
+    #[test]
+    fn reports_every_broken_rule_in_one_packet() {
+        let buf = data_with(|b| {
+            b[1] = 0x20;
+            b[108] = 250;
+            b[112] = 0x02;
+        });
+        assert_eq!(
+            rules(&buf),
+            vec![Rule::Preamble, Rule::Priority, Rule::Reserved]
+        );
+    }
+
+    #[test]
+    fn checks_sequences() {
+        assert_eq!(check_sequence(10, 11), None);
+        assert_eq!(check_sequence(255, 0), None, "wrap");
+        assert_eq!(check_sequence(10, 40), None, "skipped packets");
+        assert_eq!(check_sequence(10, 246), None, "restart");
+        assert_eq!(
+            check_sequence(10, 10),
+            Some("sequence 10 repeated".to_string())
+        );
+        assert_eq!(
+            check_sequence(10, 9),
+            Some("sequence 9 arrived after 10".to_string())
+        );
+    }
+
+    #[test]
+    fn reports_stream_rules() {
+        let mut state = AppState::new();
+        let buf = data_fixture();
+        state.check_compliance(&buf, SOURCE_IP);
+        state.check_compliance(&buf, SOURCE_IP);
+
+        let source = state.compliance_sources()[0];
+        assert_eq!(source.packets, 2);
+        assert_eq!(
+            source.violations.keys().copied().collect::<Vec<_>>(),
+            vec![Rule::Sequence]
+        );
+
+        // Let the stream go quiet past the data loss timeout
+        for source in state.compliance.values_mut() {
+            for stream in source.streams.values_mut() {
+                stream.last_seen -= chrono::Duration::seconds(3);
+            }
+        }
+        state.check_compliance_timeout();
+        let source = state.compliance_sources()[0];
+        assert_eq!(source.violations[&Rule::Termination].count, 1);
+    }
+
+    #[test]
+    fn reports_missed_keep_alives() {
+        let start = Utc::now();
+        let mut source = SourceCompliance::new(SOURCE_IP, None, start);
+        let data = |sequence| DataFields {
+            universe: 1,
+            sequence,
+            start_code: 0,
+            terminated: false,
+        };
+
+        source.check_data(data(1), start);
+        source.check_data(data(2), start + chrono::Duration::milliseconds(1000));
+        assert!(source.violations.is_empty());
+
+        source.check_data(data(3), start + chrono::Duration::milliseconds(2500));
+        assert_eq!(
+            source.violations.keys().copied().collect::<Vec<_>>(),
+            vec![Rule::KeepAlive]
+        );
+    }
+}
@@ -0,0 +1,169 @@
+use super::{AppState, LogLevel};
+use chrono::{DateTime, Utc};
+use std::collections::VecDeque;
+
+/// Default and largest history depth offered, in seconds.
+pub const DEFAULT_HISTORY_SECONDS: u32 = 60;
+pub const MAX_HISTORY_SECONDS: u32 = 600;
+
+/// Most samples kept per channel, about as many as a graph is wide in pixels.
+/// A channel changing in every packet of a 44 Hz stream fills this in 23 s,
+/// then loses its oldest samples first, so a universe never holds more than
+/// 8 MB of history however deep it is set.
+const MAX_CHANNEL_SAMPLES: usize = 1024;
+
+/// One merged level and when it was set.
+pub type HistorySample = (DateTime<Utc>, u8);
+
+/// Recent merged levels of every channel in a universe.
+///
+/// A sample is stored whenever a channel's value changes, so fades are kept
+/// at packet resolution while static channels cost nothing. Samples older
+/// than the depth are dropped by [`AppState::prune_history`].
+#[derive(Debug, Clone)]
+pub struct ChannelHistory {
+    depth: chrono::Duration,
+    samples: Vec<VecDeque<HistorySample>>, // per channel, oldest first
+}
+
+impl ChannelHistory {
+    pub fn new(depth: chrono::Duration) -> Self {
+        Self {
+            depth,
+            samples: vec![VecDeque::new(); 512],
+        }
+    }
+
+    pub fn depth(&self) -> chrono::Duration {
+        self.depth
+    }
+
+    pub fn set_depth(&mut self, depth: chrono::Duration) {
+        self.depth = depth;
+        self.prune(Utc::now());
+    }
+
+    /// Records the channels that changed since the last call.
+    pub fn record(&mut self, levels: &[u8; 512], now: DateTime<Utc>) {
+        for (samples, &value) in self.samples.iter_mut().zip(levels) {
+            if samples.back().map(|&(_, last)| last) != Some(value) {
+                if samples.len() == MAX_CHANNEL_SAMPLES {
+                    samples.pop_front();
+                }
+                samples.push_back((now, value));
+            }
+        }
+    }
+
+    /// Drops samples older than the depth, keeping the last one before it so
+    /// the level at the start of the window is still known.
+    fn prune(&mut self, now: DateTime<Utc>) {
+        let cutoff = now - self.depth;
+        for samples in &mut self.samples {
+            while samples.len() > 1 && samples[1].0 <= cutoff {
+                samples.pop_front();
+            }
+        }
+    }
+
+    /// Samples for a 0-based channel, oldest first.
+    pub fn samples(&self, channel: usize) -> &VecDeque<HistorySample> {
+        &self.samples[channel]
+    }
+}
+
+impl AppState {
+    /// Drops history older than the depth from every universe. Called
+    /// periodically by the listener rather than on every merge.
+    pub fn prune_history(&mut self) {
+        let now = Utc::now();
+        for data in self.universes.values_mut() {
+            data.history.prune(now);
+        }
+    }
+
+    /// Changes how far back channel history goes, for every universe.
+    pub fn set_history_seconds(&mut self, seconds: u32) {
+        self.history_seconds = seconds.clamp(1, MAX_HISTORY_SECONDS);
+        self.settings.history_seconds = self.history_seconds;
+
+        let depth = self.history_depth();
+        for data in self.universes.values_mut() {
+            data.history.set_depth(depth);
+        }
+
+        if let Err(e) = self.save_settings() {
+            self.add_log(LogLevel::Warning, format!("Failed to save settings: {}", e));
+        }
+    }
+
+    pub fn history_depth(&self) -> chrono::Duration {
+        chrono::Duration::seconds(self.history_seconds as i64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn levels(first: u8) -> [u8; 512] {
+        let mut levels = [0; 512];
+        levels[0] = first;
+        levels
+    }
+
+    #[test]
+    fn records_only_changes() {
+        let start = Utc::now();
+        let mut history = ChannelHistory::new(chrono::Duration::seconds(60));
+        for (ms, value) in [(0, 10), (25, 10), (50, 20), (75, 20), (100, 10)] {
+            history.record(&levels(value), start + chrono::Duration::milliseconds(ms));
+        }
+
+        let values: Vec<u8> = history.samples(0).iter().map(|&(_, v)| v).collect();
+        assert_eq!(values, vec![10, 20, 10]);
+        assert_eq!(history.samples(1).len(), 1, "static at 0 since the start");
+    }
+
+    #[test]
+    fn caps_samples_per_channel() {
+        let start = Utc::now();
+        let mut history = ChannelHistory::new(chrono::Duration::seconds(600));
+        for i in 0..MAX_CHANNEL_SAMPLES + 10 {
+            let now = start + chrono::Duration::milliseconds(i as i64 * 23);
+            history.record(&levels(i as u8), now);
+        }
+
+        let samples = history.samples(0);
+        assert_eq!(samples.len(), MAX_CHANNEL_SAMPLES);
+        assert_eq!(samples[0].1, 10, "oldest dropped first");
+    }
+
+    #[test]
+    fn prune_keeps_the_sample_before_the_cutoff() {
+        let start = Utc::now();
+        let mut history = ChannelHistory::new(chrono::Duration::seconds(10));
+        for (s, value) in [(0, 1), (4, 2), (8, 3), (15, 4)] {
+            history.record(&levels(value), start + chrono::Duration::seconds(s));
+        }
+
+        history.prune(start + chrono::Duration::seconds(20));
+        let values: Vec<u8> = history.samples(0).iter().map(|&(_, v)| v).collect();
+        assert_eq!(values, vec![3, 4], "3 is the level at the cutoff");
+        assert_eq!(history.samples(1).len(), 1, "a single sample is kept");
+    }
+
+    #[test]
+    fn set_depth_prunes_to_the_new_depth() {
+        let now = Utc::now();
+        let mut history = ChannelHistory::new(chrono::Duration::seconds(60));
+        for (s, value) in [(50, 1), (30, 2), (20, 3), (5, 4)] {
+            history.record(&levels(value), now - chrono::Duration::seconds(s));
+        }
+
+        history.set_depth(chrono::Duration::seconds(10));
+        assert_eq!(history.depth(), chrono::Duration::seconds(10));
+        let values: Vec<u8> = history.samples(0).iter().map(|&(_, v)| v).collect();
+        assert_eq!(values, vec![3, 4]);
+    }
+}
@@ -0,0 +1,293 @@
+use super::{ChannelHistory, SourceData, UniverseData};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use uuid::Uuid;
+
+/// How channels are resolved between sources sending at the same priority.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum MergeMode {
+    /// Highest takes precedence
+    #[default]
+    Htp,
+    /// Latest takes precedence, the source that last changed a channel wins
+    Ltp,
+}
+
+impl fmt::Display for MergeMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MergeMode::Htp => write!(f, "HTP"),
+            MergeMode::Ltp => write!(f, "LTP"),
+        }
+    }
+}
+
+impl UniverseData {
+    pub fn new(universe: u16, history_depth: chrono::Duration) -> Self {
+        Self {
+            universe,
+            sources: HashMap::new(),
+            channels: [0; 512],
+            winners: [None; 512],
+            last_updated: Utc::now(),
+            history: ChannelHistory::new(history_depth),
+            change_counter: 0,
+        }
+    }
+
+    /// Stores the latest levels from one source, stamping every channel whose
+    /// value changed so LTP can tell which source moved it last.
+    pub fn update_source(&mut self, mut source: SourceData) {
+        self.change_counter += 1;
+        let stamp = self.change_counter;
+
+        match self.sources.get(&source.cid) {
+            Some(previous) => {
+                source.address_priorities = previous.address_priorities;
+                source.address_priorities_received = previous.address_priorities_received;
+                for i in 0..512 {
+                    source.change_stamps[i] = if previous.channels[i] != source.channels[i] {
+                        stamp
+                    } else {
+                        previous.change_stamps[i]
+                    };
+                }
+            }
+            None => source.change_stamps = [stamp; 512],
+        }
+
+        self.last_updated = source.last_updated;
+        self.sources.insert(source.cid, source);
+    }
+
+    /// Stores a source's per-address priorities (start code 0xDD).
+    ///
+    /// Returns false if the source hasn't sent any levels to this universe yet.
+    pub fn update_address_priorities(
+        &mut self,
+        cid: Uuid,
+        priorities: [u8; 512],
+        received: DateTime<Utc>,
+    ) -> bool {
+        match self.sources.get_mut(&cid) {
+            Some(source) => {
+                source.address_priorities = Some(priorities);
+                source.address_priorities_received = received;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// A universe is lost once every source has timed out or terminated.
+    pub fn is_lost(&self) -> bool {
+        self.sources.is_empty()
+    }
+
+    /// Recomputes the merged output and per-channel winners, and records
+    /// any changed levels in the history.
+    ///
+    /// The highest priority source always wins, ties are resolved with `mode`.
+    /// Sources with a per-address priority of 0 on a channel are left out.
+    pub fn merge(&mut self, mode: MergeMode) {
+        for i in 0..512 {
+            let winner = self
+                .sources
+                .values()
+                .filter(|source| source.drives_channel(i))
+                .max_by_key(|source| Self::merge_key(source, i, mode));
+
+            self.channels[i] = winner.map_or(0, |source| source.channels[i]);
+            self.winners[i] = winner.map(|source| source.cid);
+        }
+        self.history.record(&self.channels, Utc::now());
+    }
+
+    /// The merged levels of only the sources `include` accepts, resolved the
+    /// same way as [`merge`](Self::merge). Returns None if it accepts none.
+    pub fn merge_filtered(
+        &self,
+        mode: MergeMode,
+        include: impl Fn(&SourceData) -> bool,
+    ) -> Option<[u8; 512]> {
+        let sources: Vec<&SourceData> = self.sources.values().filter(|s| include(s)).collect();
+        if sources.is_empty() {
+            return None;
+        }
+
+        let mut levels = [0u8; 512];
+        for (i, level) in levels.iter_mut().enumerate() {
+            *level = sources
+                .iter()
+                .filter(|source| source.drives_channel(i))
+                .max_by_key(|source| Self::merge_key(source, i, mode))
+                .map_or(0, |source| source.channels[i]);
+        }
+        Some(levels)
+    }
+
+    /// Ordering used to pick a channel's winner. Remaining ties fall back to
+    /// the CID so the winner doesn't flicker between equal sources.
+    fn merge_key(source: &SourceData, channel: usize, mode: MergeMode) -> (u8, u8, u64, [u8; 16]) {
+        let value = source.channels[channel];
+        let stamp = source.change_stamps[channel];
+        let cid = *source.cid.as_bytes();
+        let priority = source.channel_priority(channel);
+        match mode {
+            MergeMode::Htp => (priority, value, stamp, cid),
+            MergeMode::Ltp => (priority, 0, stamp, cid),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{AppState, Protocol};
+    use std::net::{IpAddr, Ipv4Addr};
+
+    /// A source with the given CID byte and priority, at `level` on every channel.
+    fn source(id: u8, priority: u8, level: u8) -> SourceData {
+        SourceData::new(
+            Uuid::from_bytes(&[id; 16]).unwrap(),
+            format!("Source {}", id),
+            IpAddr::V4(Ipv4Addr::new(10, 0, 0, id)),
+            Protocol::Sacn,
+            priority,
+            0,
+            [level; 512],
+        )
+    }
+
+    fn universe() -> UniverseData {
+        UniverseData::new(1, chrono::Duration::seconds(1))
+    }
+
+    /// A source's levels, with only the first two channels set.
+    fn levels(id: u8, priority: u8, first: u8, second: u8) -> SourceData {
+        let mut source = source(id, priority, 0);
+        source.channels[0] = first;
+        source.channels[1] = second;
+        source
+    }
+
+    #[test]
+    fn htp_tie_takes_the_highest_level() {
+        let (a, b) = (levels(1, 100, 200, 10), levels(2, 100, 50, 20));
+        for order in [[&a, &b], [&b, &a]] {
+            let mut data = universe();
+            for source in order {
+                data.update_source(source.clone());
+            }
+            data.merge(MergeMode::Htp);
+            assert_eq!(&data.channels[..2], &[200, 20]);
+            assert_eq!(&data.winners[..2], &[Some(a.cid), Some(b.cid)]);
+        }
+    }
+
+    #[test]
+    fn ltp_tie_takes_the_latest_change() {
+        let mut data = universe();
+        data.update_source(levels(1, 100, 200, 200));
+        data.update_source(levels(2, 100, 50, 50));
+        data.merge(MergeMode::Ltp);
+        assert_eq!(&data.channels[..2], &[50, 50], "source 2 changed last");
+
+        // Re-sending unchanged levels doesn't take a channel back
+        data.update_source(levels(1, 100, 200, 180));
+        data.merge(MergeMode::Ltp);
+        assert_eq!(&data.channels[..2], &[50, 180]);
+
+        data.merge(MergeMode::Htp);
+        assert_eq!(&data.channels[..2], &[200, 180]);
+    }
+
+    #[test]
+    fn higher_priority_wins_in_either_mode() {
+        for mode in [MergeMode::Htp, MergeMode::Ltp] {
+            let mut data = universe();
+            data.update_source(levels(1, 150, 10, 10));
+            data.update_source(levels(2, 100, 255, 255));
+            data.merge(mode);
+            assert_eq!(&data.channels[..2], &[10, 10], "{}", mode);
+
+            let merged = data.merge_filtered(mode, |_| true).unwrap();
+            assert_eq!(&merged[..2], &[10, 10], "{}", mode);
+        }
+    }
+
+    #[test]
+    fn per_address_priority_decides_each_channel() {
+        let mut data = universe();
+        let first = levels(1, 100, 200, 200);
+        let cid = first.cid;
+        data.update_source(first);
+        data.update_source(levels(2, 100, 100, 100));
+
+        let mut priorities = [100; 512];
+        priorities[0] = 50;
+        priorities[1] = 150;
+        assert!(data.update_address_priorities(cid, priorities, Utc::now()));
+
+        for mode in [MergeMode::Htp, MergeMode::Ltp] {
+            data.merge(mode);
+            assert_eq!(&data.channels[..2], &[100, 200], "{}", mode);
+        }
+    }
+
+    #[test]
+    fn universe_priority_zero_still_drives() {
+        let mut data = universe();
+        let quiet = source(1, 0, 50);
+        let cid = quiet.cid;
+        data.update_source(quiet);
+        data.merge(MergeMode::Htp);
+
+        assert_eq!(data.channels[0], 50);
+        assert_eq!(data.winners[0], Some(cid));
+        assert_eq!(
+            data.merge_filtered(MergeMode::Htp, |_| true).unwrap()[0],
+            50
+        );
+    }
+
+    #[test]
+    fn per_address_priority_zero_releases_channel() {
+        let mut data = universe();
+        let fixture = source(1, 100, 50);
+        let cid = fixture.cid;
+        data.update_source(fixture);
+
+        let mut priorities = [100; 512];
+        priorities[0] = 0;
+        assert!(data.update_address_priorities(cid, priorities, Utc::now()));
+        data.merge(MergeMode::Htp);
+
+        assert_eq!(data.channels[0], 0);
+        assert_eq!(data.winners[0], None);
+        assert_eq!(data.channels[1], 50);
+        assert_eq!(data.merge_filtered(MergeMode::Htp, |_| true).unwrap()[0], 0);
+    }
+
+    #[test]
+    fn per_address_priority_outlives_level_updates_until_it_times_out() {
+        let mut state = AppState::new();
+        let fixture = source(1, 100, 50);
+        let cid = fixture.cid;
+        state.update_universe(1, fixture.clone());
+
+        let received = Utc::now() - chrono::Duration::seconds(3);
+        assert!(state.update_address_priorities(1, cid, [0; 512], received));
+        state.update_universe(1, fixture);
+        let data = &state.universes[&1];
+        assert!(data.sources[&cid].address_priorities.is_some());
+        assert_eq!(data.channels[0], 0);
+
+        state.check_source_loss();
+        let data = &state.universes[&1];
+        assert!(data.sources[&cid].address_priorities.is_none());
+        assert_eq!(data.channels[0], 50, "back on the universe priority");
+    }
+}
@@ -1,12 +1,45 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 use std::net::IpAddr;
+use std::path::PathBuf;
+use uuid::Uuid;
+
+mod artnet;
+mod bridge;
+mod command;
+mod compliance;
+mod history;
+mod merge;
+mod router;
+mod sequence;
+mod stats;
+mod subscription;
+mod sync;
+mod transmit;
+
+/// E1.31 network data loss timeout, a source is lost after this long without data.
+pub const NETWORK_DATA_LOSS_TIMEOUT: chrono::Duration = chrono::Duration::milliseconds(2500);
+
+pub use artnet::{
+    ArtNetNode, ArtNetOutput, NodePort, PortAddress, ARTNET_PRIORITY, MAX_ARTNET_UNIVERSE_OFFSET,
+};
+pub use bridge::{BridgeDirection, BridgeMapping, BridgeStatus, BridgeTarget, MAX_BRIDGE_COUNT};
+pub use compliance::SourceCompliance;
+pub use history::{ChannelHistory, HistorySample, DEFAULT_HISTORY_SECONDS, MAX_HISTORY_SECONDS};
+pub use merge::MergeMode;
+pub use router::{ChannelPatch, Route, RouteStatus, MAX_ROUTE_SCALE};
+pub use stats::StreamStats;
+pub use subscription::{UniverseDiscovery, MAX_UNIVERSE, MIN_UNIVERSE};
+pub use sync::SyncGate;
+pub use transmit::{TransmitSession, TransmitUniverse, MAX_PRIORITY, MAX_SOURCE_NAME_LEN};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NetworkAdapter {
     pub name: String,
     pub ip: IpAddr,
+    #[serde(default)]
+    pub broadcast: Option<IpAddr>, // directed broadcast address, IPv4 only
     pub description: String,
     pub is_available: bool,
 }
@@ -17,6 +50,44 @@ pub struct AppSettings {
     pub window_size: Option<(f32, f32)>,
     pub auto_send_enabled: bool,
     pub send_rate: u32,
+    #[serde(default)]
+    pub merge_mode: MergeMode,
+    #[serde(default)]
+    pub subscribed_universes: Vec<u16>,
+    #[serde(default = "default_auto_subscribe")]
+    pub auto_subscribe: bool,
+    #[serde(default)]
+    pub source_cid: Option<Uuid>, // generated on first run so receivers see a stable source
+    #[serde(default)]
+    pub transmit_session: TransmitSession,
+    #[serde(default = "default_history_seconds")]
+    pub history_seconds: u32,
+    #[serde(default = "default_artnet_universe_offset")]
+    pub artnet_universe_offset: u16,
+    #[serde(default)]
+    pub bridge_enabled: bool,
+    #[serde(default = "default_bridge_rate")]
+    pub bridge_rate: u32,
+    #[serde(default)]
+    pub bridge_mappings: Vec<BridgeMapping>,
+    #[serde(default)]
+    pub routes: Vec<Route>,
+}
+
+fn default_auto_subscribe() -> bool {
+    true
+}
+
+fn default_history_seconds() -> u32 {
+    DEFAULT_HISTORY_SECONDS
+}
+
+fn default_artnet_universe_offset() -> u16 {
+    artnet::DEFAULT_ARTNET_UNIVERSE_OFFSET
+}
+
+fn default_bridge_rate() -> u32 {
+    bridge::DEFAULT_BRIDGE_RATE
 }
 
 impl Default for AppSettings {
@@ -26,26 +97,197 @@ impl Default for AppSettings {
             window_size: None,
             auto_send_enabled: false,
             send_rate: 20,
+            merge_mode: MergeMode::default(),
+            subscribed_universes: Vec::new(),
+            auto_subscribe: default_auto_subscribe(),
+            source_cid: None,
+            transmit_session: TransmitSession::default(),
+            history_seconds: default_history_seconds(),
+            artnet_universe_offset: default_artnet_universe_offset(),
+            bridge_enabled: false,
+            bridge_rate: default_bridge_rate(),
+            bridge_mappings: Vec::new(),
+            routes: Vec::new(),
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct SacnDevice {
+/// The protocol a source sends on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Protocol {
+    #[default]
+    Sacn,
+    ArtNet,
+}
+
+impl std::fmt::Display for Protocol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Protocol::Sacn => write!(f, "sACN"),
+            Protocol::ArtNet => write!(f, "Art-Net"),
+        }
+    }
+}
+
+/// A source or node seen on the network, whichever protocol it speaks.
+#[derive(Debug, Clone)]
+pub struct Device {
+    pub id: Uuid, // the source CID, or a stand-in for Art-Net nodes
+    pub protocol: Protocol,
     pub ip: IpAddr,
-    pub universes: Vec<u16>,
+    pub name: String,
+    pub universes: Vec<u16>, // received from it
     pub last_seen: DateTime<Utc>,
+    pub priority: u8,
+    pub lost: bool,               // timed out or terminated on every universe
+    pub node: Option<ArtNetNode>, // from Art-Net poll replies
+}
+
+impl Device {
+    pub fn new(id: Uuid, protocol: Protocol, ip: IpAddr, name: String) -> Self {
+        Self {
+            id,
+            protocol,
+            ip,
+            name,
+            universes: Vec::new(),
+            last_seen: Utc::now(),
+            priority: 0,
+            lost: false,
+            node: None,
+        }
+    }
+
+    /// A device is lost once it sends nothing and, for Art-Net nodes, stops
+    /// answering polls.
+    fn refresh_lost(&mut self, now: DateTime<Utc>) {
+        let polled = self
+            .node
+            .as_ref()
+            .is_some_and(|node| now - node.last_reply <= artnet::ARTNET_NODE_TIMEOUT);
+        self.lost = self.universes.is_empty() && !polled;
+    }
+}
+
+/// The latest levels one source sent to a universe.
+#[derive(Debug, Clone)]
+pub struct SourceData {
+    pub cid: Uuid,
     pub source_name: String,
+    pub source_ip: IpAddr,
+    pub protocol: Protocol,
     pub priority: u8,
+    pub sequence: u8,
+    pub channels: [u8; 512],
+    pub change_stamps: [u64; 512], // per channel, when the value last changed
+    pub address_priorities: Option<[u8; 512]>, // from 0xDD start code packets
+    pub address_priorities_received: DateTime<Utc>, // when the last 0xDD packet arrived
+    pub last_updated: DateTime<Utc>, // capture time for replayed data
+    pub replayed: bool,            // from an offline replay, ages on the capture's clock
+}
+
+impl SourceData {
+    pub fn new(
+        cid: Uuid,
+        source_name: String,
+        source_ip: IpAddr,
+        protocol: Protocol,
+        priority: u8,
+        sequence: u8,
+        channels: [u8; 512],
+    ) -> Self {
+        Self {
+            cid,
+            source_name,
+            source_ip,
+            protocol,
+            priority,
+            sequence,
+            channels,
+            change_stamps: [0; 512],
+            address_priorities: None,
+            address_priorities_received: Utc::now(),
+            last_updated: Utc::now(),
+            replayed: false,
+        }
+    }
+
+    /// Marks data played back from a capture, stamped with when it was
+    /// captured.
+    pub fn replayed_at(mut self, captured: DateTime<Utc>) -> Self {
+        self.last_updated = captured;
+        self.replayed = true;
+        self
+    }
+
+    /// The priority this source has on a channel. Per-address priority
+    /// overrides the universe priority.
+    pub fn channel_priority(&self, channel: usize) -> u8 {
+        self.address_priorities
+            .map_or(self.priority, |priorities| priorities[channel])
+    }
+
+    /// Whether this source takes part in a channel's merge. A per-address
+    /// priority of 0 means the source doesn't drive the channel, while a
+    /// universe priority of 0 is just the lowest priority.
+    pub fn drives_channel(&self, channel: usize) -> bool {
+        self.address_priorities
+            .is_none_or(|priorities| priorities[channel] > 0)
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct UniverseData {
     pub universe: u16,
-    pub channels: [u8; 512],
+    pub sources: HashMap<Uuid, SourceData>,
+    pub channels: [u8; 512],          // merged output
+    pub winners: [Option<Uuid>; 512], // CID of the source driving each channel
     pub last_updated: DateTime<Utc>,
-    pub source_ip: IpAddr,
-    pub sequence: u8,
+    pub history: ChannelHistory, // recent merged levels
+    change_counter: u64,
+}
+
+/// The clocks received data ages on: the wall clock for live data and the
+/// capture's clock for data from an offline replay, which stands still
+/// while the replay is paused.
+#[derive(Debug, Clone, Copy)]
+pub struct Clocks {
+    pub now: DateTime<Utc>,
+    pub replay: Option<DateTime<Utc>>, // None when no offline replay is loaded
+}
+
+impl Clocks {
+    /// Whether more than `timeout` has passed since `time` on the data's
+    /// clock. Replayed data is also stale once the replay is closed or jumps
+    /// back to before it was captured.
+    /// The current time on the data's clock.
+    pub fn now_for(&self, replayed: bool) -> DateTime<Utc> {
+        match self.replay {
+            Some(replay) if replayed => replay,
+            _ => self.now,
+        }
+    }
+
+    pub fn timed_out(
+        &self,
+        time: DateTime<Utc>,
+        replayed: bool,
+        timeout: chrono::Duration,
+    ) -> bool {
+        if !replayed {
+            return self.now - time > timeout;
+        }
+        match self.replay {
+            Some(replay) => replay - time > timeout || time > replay,
+            None => true,
+        }
+    }
+}
+
+/// settings.json in the platform's config directory.
+fn default_config_path() -> Option<PathBuf> {
+    directories::ProjectDirs::from("com", "sacn-viewer", "sACN Viewer")
+        .map(|dirs| dirs.config_dir().join("settings.json"))
 }
 
 #[derive(Debug, Clone)]
@@ -76,17 +318,61 @@ impl std::fmt::Display for LogLevel {
     }
 }
 
+/// State of a receive loop, reported by the listener task.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ListenerStatus {
+    Stopped,
+    Rebinding,
+    Listening(IpAddr),
+    Failed(String),
+}
+
+impl std::fmt::Display for ListenerStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ListenerStatus::Stopped => write!(f, "stopped"),
+            ListenerStatus::Rebinding => write!(f, "rebinding"),
+            ListenerStatus::Listening(ip) => write!(f, "listening on {}", ip),
+            ListenerStatus::Failed(e) => write!(f, "failed: {}", e),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct AppState {
-    pub devices: HashMap<IpAddr, SacnDevice>,
+    pub devices: HashMap<Uuid, Device>, // keyed by source CID or Art-Net node ID
     pub universes: HashMap<u16, UniverseData>,
     pub logs: Vec<LogEntry>,
     pub selected_universe: Option<u16>,
     pub auto_send_enabled: bool,
     pub send_rate: u32, // packets per second
+    pub merge_mode: MergeMode,
+    pub subscribed_universes: BTreeSet<u16>, // joined regardless of discovery
+    pub auto_subscribe: bool,                // join universes learned from discovery
+    pub discovered_universes: HashMap<Uuid, UniverseDiscovery>,
+    pub joined_universes: BTreeSet<u16>, // reported by the listener
+    pub sync_gates: HashMap<(u16, Uuid), SyncGate>, // keyed by universe and source CID
+    pub stream_stats: HashMap<(u16, Uuid), StreamStats>, // keyed by universe and source CID
+    pub replay_time: Option<DateTime<Utc>>, // capture time an offline replay is at
+    pub last_sequences: HashMap<(u16, Uuid), u8>, // last accepted, keyed by universe and source CID
+    pub compliance: HashMap<(IpAddr, Option<Uuid>), SourceCompliance>, // keyed by sender
+    pub listener_status: ListenerStatus,
+    pub artnet_status: ListenerStatus,
+    pub artnet_universe_offset: u16, // added to Art-Net port addresses
+    pub bridge_enabled: bool,
+    pub bridge_rate: u32, // frames per second
+    pub bridge_mappings: Vec<BridgeMapping>,
+    pub bridge_status: Vec<BridgeStatus>, // one per mapping, written by the bridge task
+    pub routes: Vec<Route>,
+    pub route_status: Vec<RouteStatus>, // one per route, written by the transmitter
+    pub source_cid: Uuid,
+    pub transmit_session: TransmitSession, // streamed while auto send is enabled
+    pub history_seconds: u32,              // depth of channel history
     pub network_adapters: Vec<NetworkAdapter>,
     pub selected_adapter: Option<String>,
     pub settings: AppSettings,
+    pub settings_path: Option<PathBuf>, // given on the command line, otherwise the default file
+    pub default_settings_path: Option<PathBuf>, // settings.json in the platform's config directory
 }
 
 impl AppState {
@@ -98,13 +384,46 @@ impl AppState {
             selected_universe: None,
             auto_send_enabled: false,
             send_rate: 20, // 20 Hz default
+            merge_mode: MergeMode::default(),
+            subscribed_universes: BTreeSet::new(),
+            auto_subscribe: default_auto_subscribe(),
+            discovered_universes: HashMap::new(),
+            joined_universes: BTreeSet::new(),
+            sync_gates: HashMap::new(),
+            stream_stats: HashMap::new(),
+            replay_time: None,
+            last_sequences: HashMap::new(),
+            compliance: HashMap::new(),
+            listener_status: ListenerStatus::Stopped,
+            artnet_status: ListenerStatus::Stopped,
+            artnet_universe_offset: default_artnet_universe_offset(),
+            bridge_enabled: false,
+            bridge_rate: default_bridge_rate(),
+            bridge_mappings: Vec::new(),
+            bridge_status: Vec::new(),
+            routes: Vec::new(),
+            route_status: Vec::new(),
+            source_cid: Uuid::new_v4(),
+            transmit_session: TransmitSession::default(),
+            history_seconds: default_history_seconds(),
             network_adapters: Vec::new(),
             selected_adapter: None,
             settings: AppSettings::default(),
+            settings_path: None,
+            default_settings_path: default_config_path(),
         }
     }
 
+    /// Adds an entry to the log panel and passes it on to the logger, which
+    /// is all a headless run has to show it.
     pub fn add_log(&mut self, level: LogLevel, message: String) {
+        match level {
+            LogLevel::Error => log::error!("{}", message),
+            LogLevel::Warning => log::warn!("{}", message),
+            LogLevel::Info => log::info!("{}", message),
+            LogLevel::Rx | LogLevel::Tx => log::debug!("{} {}", level, message),
+        }
+
         self.logs.push(LogEntry {
             timestamp: Utc::now(),
             level,
@@ -117,17 +436,24 @@ impl AppState {
         }
     }
 
-    pub fn update_device(&mut self, ip: IpAddr, universe: u16, source_name: String, priority: u8) {
-        let device = self.devices.entry(ip).or_insert_with(|| SacnDevice {
-            ip,
-            universes: Vec::new(),
-            last_seen: Utc::now(),
-            source_name: source_name.clone(),
-            priority,
-        });
+    pub fn update_device(
+        &mut self,
+        cid: Uuid,
+        ip: IpAddr,
+        universe: u16,
+        source_name: String,
+        priority: u8,
+        protocol: Protocol,
+    ) {
+        let device = self
+            .devices
+            .entry(cid)
+            .or_insert_with(|| Device::new(cid, protocol, ip, source_name.clone()));
 
         device.last_seen = Utc::now();
-        device.source_name = source_name;
+        device.lost = false;
+        device.ip = ip;
+        device.name = source_name;
         device.priority = priority;
 
         if !device.universes.contains(&universe) {
@@ -136,30 +462,189 @@ impl AppState {
         }
     }
 
-    pub fn update_universe(
+    pub fn update_universe(&mut self, universe: u16, source: SourceData) {
+        let merge_mode = self.merge_mode;
+        let history_depth = self.history_depth();
+        let data = self
+            .universes
+            .entry(universe)
+            .or_insert_with(|| UniverseData::new(universe, history_depth));
+        data.update_source(source);
+        data.merge(merge_mode);
+    }
+
+    /// Removes a source that set the Stream_Terminated option bit.
+    pub fn terminate_source(&mut self, universe: u16, cid: Uuid) {
+        self.sync_gates.remove(&(universe, cid));
+        if let Some(source) = self.remove_source(universe, cid) {
+            self.add_log(
+                LogLevel::Warning,
+                format!(
+                    "Source {} ({}) terminated universe {}",
+                    source.source_name, source.source_ip, universe
+                ),
+            );
+        }
+    }
+
+    pub fn clocks(&self) -> Clocks {
+        Clocks {
+            now: Utc::now(),
+            replay: self.replay_time,
+        }
+    }
+
+    /// Drops every source that hasn't sent data within the network data loss
+    /// timeout. Should be called periodically by the listener.
+    pub fn check_source_loss(&mut self) {
+        let clocks = self.clocks();
+        let expired: Vec<(u16, Uuid)> = self
+            .universes
+            .values()
+            .flat_map(|data| {
+                data.sources
+                    .values()
+                    .filter(|source| {
+                        clocks.timed_out(
+                            source.last_updated,
+                            source.replayed,
+                            NETWORK_DATA_LOSS_TIMEOUT,
+                        )
+                    })
+                    .map(|source| (data.universe, source.cid))
+            })
+            .collect();
+
+        for (universe, cid) in expired {
+            if let Some(source) = self.remove_source(universe, cid) {
+                self.add_log(
+                    LogLevel::Warning,
+                    format!(
+                        "Source {} ({}) lost on universe {}: no data for {} ms",
+                        source.source_name,
+                        source.source_ip,
+                        universe,
+                        NETWORK_DATA_LOSS_TIMEOUT.num_milliseconds()
+                    ),
+                );
+            }
+        }
+
+        self.remove_lost_stream_stats();
+
+        // Per-address priority expires on its own when a source stops sending
+        // 0xDD packets but keeps sending levels
+        let stale: Vec<(u16, Uuid)> = self
+            .universes
+            .values()
+            .flat_map(|data| {
+                data.sources
+                    .values()
+                    .filter(|source| {
+                        source.address_priorities.is_some()
+                            && clocks.timed_out(
+                                source.address_priorities_received,
+                                source.replayed,
+                                NETWORK_DATA_LOSS_TIMEOUT,
+                            )
+                    })
+                    .map(|source| (data.universe, source.cid))
+            })
+            .collect();
+
+        let merge_mode = self.merge_mode;
+        for (universe, cid) in stale {
+            let Some(data) = self.universes.get_mut(&universe) else {
+                continue;
+            };
+            let Some(source) = data.sources.get_mut(&cid) else {
+                continue;
+            };
+            source.address_priorities = None;
+            let message = format!(
+                "Per-address priority from {} ({}) lost on universe {}, using priority {}",
+                source.source_name, source.source_ip, universe, source.priority
+            );
+            data.merge(merge_mode);
+            self.add_log(LogLevel::Warning, message);
+        }
+    }
+
+    /// Takes a source out of a universe's merge, marking the universe and
+    /// device lost once nothing is left.
+    fn remove_source(&mut self, universe: u16, cid: Uuid) -> Option<SourceData> {
+        let merge_mode = self.merge_mode;
+        let data = self.universes.get_mut(&universe)?;
+        let source = data.sources.remove(&cid)?;
+        data.merge(merge_mode);
+        self.last_sequences.remove(&(universe, cid));
+        let universe_lost = data.is_lost();
+
+        if let Some(device) = self.devices.get_mut(&cid) {
+            device.universes.retain(|&u| u != universe);
+            device.refresh_lost(Utc::now());
+        }
+
+        if universe_lost {
+            self.add_log(
+                LogLevel::Warning,
+                format!("Universe {} lost: no active sources", universe),
+            );
+        }
+
+        Some(source)
+    }
+
+    pub fn update_address_priorities(
         &mut self,
         universe: u16,
-        channels: [u8; 512],
-        source_ip: IpAddr,
-        sequence: u8,
-    ) {
-        self.universes.insert(
-            universe,
-            UniverseData {
-                universe,
-                channels,
-                last_updated: Utc::now(),
-                source_ip,
-                sequence,
-            },
-        );
+        cid: Uuid,
+        priorities: [u8; 512],
+        received: DateTime<Utc>,
+    ) -> bool {
+        let merge_mode = self.merge_mode;
+        let Some(data) = self.universes.get_mut(&universe) else {
+            return false;
+        };
+        if !data.update_address_priorities(cid, priorities, received) {
+            return false;
+        }
+        data.merge(merge_mode);
+        true
+    }
+
+    /// Whether a source is our own output, by CID or by coming from one of
+    /// this machine's adapters, so it isn't fed back into what we send.
+    pub fn is_own_source(&self, source: &SourceData) -> bool {
+        source.cid == self.source_cid || self.is_own_address(source.source_ip)
+    }
+
+    /// Whether an address belongs to one of this machine's adapters.
+    pub fn is_own_address(&self, ip: IpAddr) -> bool {
+        self.network_adapters.iter().any(|adapter| adapter.ip == ip)
+    }
+
+    pub fn set_merge_mode(&mut self, merge_mode: MergeMode) {
+        self.merge_mode = merge_mode;
+        self.settings.merge_mode = merge_mode;
+        for data in self.universes.values_mut() {
+            data.merge(merge_mode);
+        }
+        if let Err(e) = self.save_settings() {
+            self.add_log(LogLevel::Warning, format!("Failed to save settings: {}", e));
+        }
+    }
+
+    /// The settings file in use: the one set in `settings_path`, or the
+    /// default one.
+    pub fn config_path(&self) -> Option<PathBuf> {
+        self.settings_path
+            .clone()
+            .or_else(|| self.default_settings_path.clone())
     }
 
     pub fn load_settings(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        if let Some(config_dir) =
-            directories::ProjectDirs::from("com", "sacn-viewer", "sACN Viewer")
-        {
-            let config_path = config_dir.config_dir().join("settings.json");
+        if let Some(config_path) = self.config_path() {
             if config_path.exists() {
                 let contents = std::fs::read_to_string(&config_path)?;
                 let settings: AppSettings = serde_json::from_str(&contents)?;
@@ -167,24 +652,95 @@ impl AppState {
                 self.selected_adapter = self.settings.selected_adapter.clone();
                 self.auto_send_enabled = self.settings.auto_send_enabled;
                 self.send_rate = self.settings.send_rate;
+                self.merge_mode = self.settings.merge_mode;
+                self.subscribed_universes =
+                    self.settings.subscribed_universes.iter().copied().collect();
+                self.auto_subscribe = self.settings.auto_subscribe;
+                self.transmit_session = self.settings.transmit_session.clone();
+                self.transmit_session.limit_source_name();
+                self.history_seconds = self.settings.history_seconds;
+                self.artnet_universe_offset = self.settings.artnet_universe_offset;
+                self.bridge_enabled = self.settings.bridge_enabled;
+                self.bridge_rate = self.settings.bridge_rate;
+                self.bridge_mappings = self.settings.bridge_mappings.clone();
+                self.routes = self.settings.routes.clone();
                 self.add_log(LogLevel::Info, "Settings loaded successfully".to_string());
             }
         }
+
+        match self.settings.source_cid {
+            Some(cid) => self.source_cid = cid,
+            // A settings file given on the command line may be read-only or
+            // copied between machines, so this machine's CID is kept in the
+            // default settings file instead
+            None if self.settings_path.is_some() => match self.default_settings_cid() {
+                Ok(cid) => self.source_cid = cid,
+                Err(e) => self.add_log(
+                    LogLevel::Warning,
+                    format!(
+                        "Failed to save the source CID to the default settings, using {} until restart: {}",
+                        self.source_cid, e
+                    ),
+                ),
+            },
+            None => {
+                self.settings.source_cid = Some(self.source_cid);
+                self.save_settings()?;
+            }
+        }
         Ok(())
     }
 
+    /// The CID in the default settings file, saving ours there if it has none.
+    fn default_settings_cid(&self) -> Result<Uuid, Box<dyn std::error::Error>> {
+        let path = self
+            .default_settings_path
+            .clone()
+            .ok_or("No home directory to save settings in")?;
+        let mut settings = if path.exists() {
+            serde_json::from_str(&std::fs::read_to_string(&path)?)?
+        } else {
+            AppSettings::default()
+        };
+        if let Some(cid) = settings.source_cid {
+            return Ok(cid);
+        }
+
+        settings.source_cid = Some(self.source_cid);
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(&path, serde_json::to_string_pretty(&settings)?)?;
+        Ok(self.source_cid)
+    }
+
     pub fn save_settings(&self) -> Result<(), Box<dyn std::error::Error>> {
-        if let Some(config_dir) =
-            directories::ProjectDirs::from("com", "sacn-viewer", "sACN Viewer")
-        {
-            std::fs::create_dir_all(config_dir.config_dir())?;
-            let config_path = config_dir.config_dir().join("settings.json");
+        if let Some(config_path) = self.config_path() {
+            if let Some(dir) = config_path.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
             let contents = serde_json::to_string_pretty(&self.settings)?;
             std::fs::write(&config_path, contents)?;
         }
         Ok(())
     }
 
+    pub fn set_auto_send(&mut self, enabled: bool) {
+        self.auto_send_enabled = enabled;
+        self.settings.auto_send_enabled = enabled;
+        if let Err(e) = self.save_settings() {
+            self.add_log(LogLevel::Warning, format!("Failed to save settings: {}", e));
+        }
+    }
+
+    pub fn set_send_rate(&mut self, send_rate: u32) {
+        self.send_rate = send_rate;
+        self.settings.send_rate = send_rate;
+        if let Err(e) = self.save_settings() {
+            self.add_log(LogLevel::Warning, format!("Failed to save settings: {}", e));
+        }
+    }
+
     pub fn update_adapter_selection(&mut self, adapter_name: Option<String>) {
         self.selected_adapter = adapter_name.clone();
         self.settings.selected_adapter = adapter_name;
@@ -199,9 +755,14 @@ impl AppState {
                 self.network_adapters.clear();
                 for interface in interfaces {
                     if !interface.is_loopback() {
+                        let broadcast = match &interface.addr {
+                            if_addrs::IfAddr::V4(addr) => addr.broadcast.map(IpAddr::V4),
+                            if_addrs::IfAddr::V6(_) => None,
+                        };
                         let adapter = NetworkAdapter {
                             name: interface.name.clone(),
                             ip: interface.ip(),
+                            broadcast,
                             description: format!("{} ({})", interface.name, interface.ip()),
                             is_available: true,
                         };
@@ -234,3 +795,66 @@ impl AppState {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("sacn-viewer-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn keeps_the_cid_out_of_a_given_settings_file() {
+        let dir = temp_dir("settings");
+        let given = dir.join("given.json");
+        let default = dir.join("default.json");
+        let contents = serde_json::to_string_pretty(&AppSettings::default()).unwrap();
+        std::fs::write(&given, &contents).unwrap();
+
+        let mut state = AppState::new();
+        state.settings_path = Some(given.clone());
+        state.default_settings_path = Some(default.clone());
+        state.load_settings().unwrap();
+
+        assert_eq!(std::fs::read_to_string(&given).unwrap(), contents);
+        let saved: AppSettings =
+            serde_json::from_str(&std::fs::read_to_string(&default).unwrap()).unwrap();
+        assert_eq!(saved.source_cid, Some(state.source_cid));
+
+        // The next run picks the same CID up from the default file
+        let mut next = AppState::new();
+        next.settings_path = Some(given.clone());
+        next.default_settings_path = Some(default);
+        next.load_settings().unwrap();
+        assert_eq!(next.source_cid, state.source_cid);
+        assert_eq!(std::fs::read_to_string(&given).unwrap(), contents);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn saves_a_new_cid_to_the_default_settings_file() {
+        let dir = temp_dir("default-settings");
+        let default = dir.join("settings.json");
+        let settings = AppSettings {
+            send_rate: 30,
+            ..AppSettings::default()
+        };
+        std::fs::write(&default, serde_json::to_string_pretty(&settings).unwrap()).unwrap();
+
+        let mut state = AppState::new();
+        state.default_settings_path = Some(default.clone());
+        state.load_settings().unwrap();
+
+        let saved: AppSettings =
+            serde_json::from_str(&std::fs::read_to_string(&default).unwrap()).unwrap();
+        assert_eq!(saved.source_cid, Some(state.source_cid));
+        assert_eq!(saved.send_rate, 30);
+        assert_eq!(state.send_rate, 30);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,328 @@
+use super::{AppState, LogLevel, MAX_UNIVERSE, MIN_UNIVERSE};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+/// Highest level scaling a route allows, in percent.
+pub const MAX_ROUTE_SCALE: u16 = 400;
+
+/// One input channel moved somewhere else in a route's output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChannelPatch {
+    pub input: u16,  // 1-512
+    pub output: u16, // 1-512
+}
+
+/// Routes the merged levels of a received universe into a transmit universe.
+///
+/// Input channels `first_channel` onwards land on the output from
+/// `output_channel` onwards, unless the remap table moves them. The remap
+/// table can also pick up channels outside the range.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Route {
+    pub enabled: bool,
+    pub input_universe: u16,
+    pub first_channel: u16, // first input channel of the range, 1-512
+    pub channel_count: u16, // 0 routes only the remap table
+    pub output_universe: u16,
+    pub output_channel: u16, // where the first channel lands, 1-512
+    pub scale: u16,          // percent, applied to every routed level
+    #[serde(default)]
+    pub remap: Vec<ChannelPatch>,
+}
+
+impl Route {
+    pub fn new(input_universe: u16, output_universe: u16) -> Self {
+        Self {
+            enabled: true,
+            input_universe,
+            first_channel: 1,
+            channel_count: 512,
+            output_universe,
+            output_channel: 1,
+            scale: 100,
+            remap: Vec::new(),
+        }
+    }
+
+    /// Every (input, output) channel pair this route copies, 0-based.
+    /// Channels the range would push past 512 are dropped.
+    pub fn channel_pairs(&self) -> Vec<(usize, usize)> {
+        let mut pairs: Vec<(usize, usize)> = (0..self.channel_count)
+            .map(|i| {
+                (
+                    self.first_channel.saturating_add(i),
+                    self.output_channel.saturating_add(i),
+                )
+            })
+            .filter(|&(input, output)| {
+                (1..=512).contains(&input)
+                    && (1..=512).contains(&output)
+                    && !self.remap.iter().any(|patch| patch.input == input)
+            })
+            .map(|(input, output)| (input as usize - 1, output as usize - 1))
+            .collect();
+        pairs.extend(
+            self.remap
+                .iter()
+                .filter(|patch| {
+                    (1..=512).contains(&patch.input) && (1..=512).contains(&patch.output)
+                })
+                .map(|patch| (patch.input as usize - 1, patch.output as usize - 1)),
+        );
+        pairs
+    }
+
+    fn scaled(&self, level: u8) -> u8 {
+        ((level as u32 * self.scale as u32 + 50) / 100).min(255) as u8
+    }
+}
+
+impl fmt::Display for Route {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "universe {} → universe {}",
+            self.input_universe, self.output_universe
+        )
+    }
+}
+
+/// What one route did on the transmitter's latest tick.
+#[derive(Debug, Clone, Default)]
+pub struct RouteStatus {
+    pub live: bool, // the input universe has sources
+    pub error: Option<String>,
+}
+
+impl AppState {
+    /// Runs every enabled route against the received universes and returns
+    /// the routed levels per transmit universe, HTP merged where several
+    /// routes share an output. Updates each route's status on the way.
+    pub fn routed_levels(&mut self) -> BTreeMap<u16, [u8; 512]> {
+        self.route_status
+            .resize_with(self.routes.len(), RouteStatus::default);
+
+        let mut outputs: BTreeMap<u16, [u8; 512]> = BTreeMap::new();
+        for (index, route) in self.routes.iter().enumerate() {
+            let mut status = RouteStatus::default();
+            if route.enabled {
+                match self.transmit_session.universe(route.output_universe) {
+                    None => {
+                        status.error = Some(format!(
+                            "Universe {} is not a transmit universe",
+                            route.output_universe
+                        ))
+                    }
+                    Some(output) if !output.enabled => {
+                        status.error = Some(format!(
+                            "Transmit universe {} is disabled",
+                            route.output_universe
+                        ))
+                    }
+                    Some(_) => {
+                        // Our own output never feeds back into a route
+                        let input = self.universes.get(&route.input_universe).and_then(|data| {
+                            data.merge_filtered(self.merge_mode, |source| {
+                                !self.is_own_source(source)
+                            })
+                        });
+                        if let Some(input) = input {
+                            status.live = true;
+                            let levels = outputs.entry(route.output_universe).or_insert([0; 512]);
+                            for (from, to) in route.channel_pairs() {
+                                levels[to] = levels[to].max(route.scaled(input[from]));
+                            }
+                        }
+                    }
+                }
+            }
+            self.route_status[index] = status;
+        }
+        outputs
+    }
+
+    /// Transmit universes a route fed on the latest tick. They are sent even
+    /// while auto send is off.
+    pub fn routed_universes(&self) -> BTreeSet<u16> {
+        self.routes
+            .iter()
+            .zip(&self.route_status)
+            .filter(|(route, status)| route.enabled && status.live)
+            .map(|(route, _)| route.output_universe)
+            .collect()
+    }
+
+    /// The universes routes read from, so the listener joins them.
+    pub fn route_input_universes(&self) -> BTreeSet<u16> {
+        self.routes
+            .iter()
+            .filter(|route| route.enabled)
+            .map(|route| route.input_universe)
+            .collect()
+    }
+
+    pub fn add_route(&mut self, route: Route) {
+        for universe in [route.input_universe, route.output_universe] {
+            if !(MIN_UNIVERSE..=MAX_UNIVERSE).contains(&universe) {
+                self.add_log(
+                    LogLevel::Error,
+                    format!(
+                        "Universe {} is outside {}-{}",
+                        universe, MIN_UNIVERSE, MAX_UNIVERSE
+                    ),
+                );
+                return;
+            }
+        }
+
+        self.add_log(LogLevel::Info, format!("Routing {}", route));
+        self.routes.push(route);
+        self.save_routes();
+    }
+
+    pub fn remove_route(&mut self, index: usize) {
+        if index < self.routes.len() {
+            let route = self.routes.remove(index);
+            if index < self.route_status.len() {
+                self.route_status.remove(index);
+            }
+            self.add_log(LogLevel::Info, format!("Stopped routing {}", route));
+            self.save_routes();
+        }
+    }
+
+    /// Copies the routes into the settings and saves them.
+    pub fn save_routes(&mut self) {
+        for route in &mut self.routes {
+            route.input_universe = route.input_universe.clamp(MIN_UNIVERSE, MAX_UNIVERSE);
+            route.output_universe = route.output_universe.clamp(MIN_UNIVERSE, MAX_UNIVERSE);
+            route.first_channel = route.first_channel.clamp(1, 512);
+            route.output_channel = route.output_channel.clamp(1, 512);
+            route.channel_count = route.channel_count.min(512);
+            route.scale = route.scale.min(MAX_ROUTE_SCALE);
+        }
+        self.settings.routes = self.routes.clone();
+        if let Err(e) = self.save_settings() {
+            self.add_log(LogLevel::Warning, format!("Failed to save settings: {}", e));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{Protocol, SourceData};
+    use std::net::{IpAddr, Ipv4Addr};
+    use uuid::Uuid;
+
+    fn route(first_channel: u16, channel_count: u16, output_channel: u16) -> Route {
+        Route {
+            first_channel,
+            channel_count,
+            output_channel,
+            ..Route::new(1, 2)
+        }
+    }
+
+    fn patch(input: u16, output: u16) -> ChannelPatch {
+        ChannelPatch { input, output }
+    }
+
+    #[test]
+    fn pairs_a_channel_range() {
+        assert_eq!(
+            route(1, 3, 101).channel_pairs(),
+            vec![(0, 100), (1, 101), (2, 102)]
+        );
+        assert_eq!(route(1, 512, 1).channel_pairs().len(), 512);
+        assert_eq!(route(5, 0, 1).channel_pairs(), vec![]);
+    }
+
+    #[test]
+    fn drops_channels_past_512() {
+        assert_eq!(
+            route(1, 4, 510).channel_pairs(),
+            vec![(0, 509), (1, 510), (2, 511)]
+        );
+        assert_eq!(
+            route(510, 4, 1).channel_pairs(),
+            vec![(509, 0), (510, 1), (511, 2)]
+        );
+        assert_eq!(route(1, 512, 512).channel_pairs(), vec![(0, 511)]);
+    }
+
+    #[test]
+    fn remap_overrides_the_range() {
+        let mut remapped = route(1, 4, 11);
+        remapped.remap = vec![patch(2, 100), patch(20, 1), patch(3, 600)];
+        assert_eq!(
+            remapped.channel_pairs(),
+            vec![(0, 10), (3, 13), (1, 99), (19, 0)],
+            "2 moved, 20 picked up, 3 dropped by its out of range patch"
+        );
+
+        let mut only_patches = route(1, 0, 1);
+        only_patches.remap = vec![patch(7, 8)];
+        assert_eq!(only_patches.channel_pairs(), vec![(6, 7)]);
+    }
+
+    #[test]
+    fn scales_levels() {
+        let mut scaled = route(1, 1, 1);
+        for (scale, expected) in [(0, [0, 0, 0]), (100, [0, 128, 255]), (50, [0, 64, 128])] {
+            scaled.scale = scale;
+            assert_eq!(
+                [0, 128, 255].map(|l| scaled.scaled(l)),
+                expected,
+                "{}%",
+                scale
+            );
+        }
+
+        scaled.scale = MAX_ROUTE_SCALE;
+        assert_eq!(
+            [0, 1, 63, 64, 255].map(|l| scaled.scaled(l)),
+            [0, 4, 252, 255, 255]
+        );
+    }
+
+    #[test]
+    fn routes_without_auto_send() {
+        let mut state = AppState::new();
+        assert!(!state.auto_send_enabled);
+        state.transmit_session.add_universe(2);
+        state.routes = vec![route(1, 2, 1)];
+
+        // No input yet
+        assert!(state.routed_levels().is_empty());
+        assert!(state.routed_universes().is_empty());
+
+        let mut channels = [0; 512];
+        channels[..2].copy_from_slice(&[10, 20]);
+        let cid = Uuid::from_bytes(&[1; 16]).unwrap();
+        let ip = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+        state.update_universe(
+            1,
+            SourceData::new(
+                cid,
+                "Input".to_string(),
+                ip,
+                Protocol::Sacn,
+                100,
+                0,
+                channels,
+            ),
+        );
+
+        let routed = state.routed_levels();
+        assert_eq!(&routed[&2][..3], &[10, 20, 0]);
+        assert!(state.route_status[0].live);
+        assert_eq!(state.routed_universes(), BTreeSet::from([2]));
+
+        state.transmit_session.universe_mut(2).unwrap().enabled = false;
+        assert!(state.routed_levels().is_empty());
+        assert!(state.route_status[0].error.is_some());
+        assert!(state.routed_universes().is_empty());
+    }
+}
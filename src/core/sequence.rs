@@ -0,0 +1,100 @@
+use super::AppState;
+use uuid::Uuid;
+
+/// A sequence number up to this far behind the last one is a late packet,
+/// anything further back is taken as the source restarting (E1.31 6.7.2).
+pub const SEQUENCE_WINDOW: i8 = -20;
+
+/// Whether `sequence` repeats or falls just behind `last`, making the packet
+/// a duplicate or a late one that must be discarded (E1.31 6.7.2).
+pub fn is_stale_sequence(last: u8, sequence: u8) -> bool {
+    let step = sequence.wrapping_sub(last) as i8;
+    step <= 0 && step > SEQUENCE_WINDOW
+}
+
+impl AppState {
+    /// Checks a data packet's sequence number against the last one accepted
+    /// from the same source on the same universe, and remembers it if the
+    /// packet is new. Returns false for packets to discard.
+    pub fn accept_sequence(&mut self, universe: u16, cid: Uuid, sequence: u8) -> bool {
+        match self.last_sequences.get(&(universe, cid)) {
+            Some(&last) if is_stale_sequence(last, sequence) => false,
+            _ => {
+                self.last_sequences.insert((universe, cid), sequence);
+                true
+            }
+        }
+    }
+
+    /// Forgets every sequence number, so the next packet of each source is
+    /// accepted whatever its number, e.g. after a replay jumps back in time.
+    pub fn reset_sequences(&mut self) {
+        self.last_sequences.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stale_sequences() {
+        assert!(is_stale_sequence(10, 10), "duplicate");
+        assert!(is_stale_sequence(10, 9), "one behind");
+        assert!(is_stale_sequence(10, 247), "19 behind across the wrap");
+        assert!(!is_stale_sequence(10, 246), "20 behind is a restart");
+        assert!(!is_stale_sequence(10, 11));
+        assert!(!is_stale_sequence(10, 40), "skipped packets");
+        assert!(!is_stale_sequence(255, 0), "wrap to 0");
+        assert!(
+            is_stale_sequence(0, 255),
+            "late packet from before the wrap"
+        );
+    }
+
+    #[test]
+    fn discards_duplicate_and_reordered_packets() {
+        let mut state = AppState::new();
+        let cid = Uuid::new_v4();
+
+        assert!(state.accept_sequence(1, cid, 100));
+        assert!(!state.accept_sequence(1, cid, 100), "duplicate");
+        assert!(state.accept_sequence(1, cid, 102));
+        assert!(!state.accept_sequence(1, cid, 101), "arrived after 102");
+        assert!(state.accept_sequence(1, cid, 103));
+    }
+
+    #[test]
+    fn accepts_wrapped_sequences() {
+        let mut state = AppState::new();
+        let cid = Uuid::new_v4();
+
+        assert!(state.accept_sequence(1, cid, 254));
+        assert!(state.accept_sequence(1, cid, 255));
+        assert!(state.accept_sequence(1, cid, 0));
+        assert!(!state.accept_sequence(1, cid, 255), "late across the wrap");
+        assert!(state.accept_sequence(1, cid, 1));
+    }
+
+    #[test]
+    fn tracks_each_universe_and_source_separately() {
+        let mut state = AppState::new();
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+
+        assert!(state.accept_sequence(1, a, 50));
+        assert!(state.accept_sequence(2, a, 50));
+        assert!(state.accept_sequence(1, b, 50));
+        assert!(!state.accept_sequence(1, a, 50));
+    }
+
+    #[test]
+    fn accepts_a_restart_and_anything_after_a_reset() {
+        let mut state = AppState::new();
+        let cid = Uuid::new_v4();
+
+        assert!(state.accept_sequence(1, cid, 100));
+        assert!(state.accept_sequence(1, cid, 50), "far behind is a restart");
+        state.reset_sequences();
+        assert!(state.accept_sequence(1, cid, 49));
+    }
+}
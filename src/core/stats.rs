@@ -0,0 +1,347 @@
+use super::sequence::SEQUENCE_WINDOW;
+use super::{AppState, NETWORK_DATA_LOSS_TIMEOUT};
+use chrono::{DateTime, Utc};
+use std::collections::VecDeque;
+use std::net::IpAddr;
+use uuid::Uuid;
+
+/// Receive statistics for one source on one universe.
+#[derive(Debug, Clone)]
+pub struct StreamStats {
+    pub universe: u16,
+    pub cid: Uuid,
+    pub source_name: String,
+    pub source_ip: IpAddr,
+    pub packets: u64,
+    pub gaps: u64,         // sequence numbers skipped
+    pub out_of_order: u64, // arrived behind a later packet
+    pub duplicates: u64,   // repeated the last sequence number
+    pub jitter_ms: f64,    // smoothed variation between packet intervals
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>, // capture time for replayed packets
+    pub replayed: bool,           // from an offline replay, timed on the capture's clock
+    last_sequence: u8,
+    last_interval_ms: Option<f64>,
+    last_arrival: Option<DateTime<Utc>>, // what the next interval is timed from
+    arrivals: VecDeque<DateTime<Utc>>,   // within the last second
+}
+
+impl StreamStats {
+    pub fn new(
+        universe: u16,
+        cid: Uuid,
+        source_name: String,
+        source_ip: IpAddr,
+        sequence: u8,
+        now: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            universe,
+            cid,
+            source_name,
+            source_ip,
+            packets: 1,
+            gaps: 0,
+            out_of_order: 0,
+            duplicates: 0,
+            jitter_ms: 0.0,
+            first_seen: now,
+            last_seen: now,
+            replayed: false,
+            last_sequence: sequence,
+            last_interval_ms: None,
+            last_arrival: Some(now),
+            arrivals: VecDeque::from([now]),
+        }
+    }
+
+    /// Counts a packet and checks its sequence number against the last one.
+    pub fn record(&mut self, sequence: u8, now: DateTime<Utc>) {
+        self.packets += 1;
+
+        let step = sequence.wrapping_sub(self.last_sequence) as i8;
+        if step == 0 {
+            self.duplicates += 1;
+        } else if step < 0 && step > SEQUENCE_WINDOW {
+            self.out_of_order += 1;
+        } else {
+            if step > 1 {
+                self.gaps += step as u64 - 1;
+            }
+            self.last_sequence = sequence;
+        }
+
+        // A replay jumping back starts the timing over
+        if self.last_arrival.is_some_and(|last| now < last) {
+            self.restart_timing();
+        }
+
+        // Interarrival jitter as in RFC 3550, a running mean of how much each
+        // interval differs from the one before it
+        if let Some(last_arrival) = self.last_arrival {
+            let interval_ms = (now - last_arrival).num_microseconds().unwrap_or(0) as f64 / 1000.0;
+            if let Some(last_interval_ms) = self.last_interval_ms {
+                let deviation = (interval_ms - last_interval_ms).abs();
+                self.jitter_ms += (deviation - self.jitter_ms) / 16.0;
+            }
+            self.last_interval_ms = Some(interval_ms);
+        }
+        self.last_arrival = Some(now);
+        self.last_seen = now;
+
+        self.arrivals.push_back(now);
+        self.prune(now);
+    }
+
+    /// Forgets the last interval and arrival times, so the time since the
+    /// last packet isn't taken as an interval, e.g. after a replay seeks.
+    /// The jitter so far is kept.
+    pub fn restart_timing(&mut self) {
+        self.last_interval_ms = None;
+        self.last_arrival = None;
+        self.arrivals.clear();
+    }
+
+    fn prune(&mut self, now: DateTime<Utc>) {
+        let cutoff = now - chrono::Duration::seconds(1);
+        while self.arrivals.front().is_some_and(|&time| time <= cutoff) {
+            self.arrivals.pop_front();
+        }
+    }
+
+    /// Packets received in the last second.
+    pub fn packets_per_second(&self, now: DateTime<Utc>) -> usize {
+        let cutoff = now - chrono::Duration::seconds(1);
+        self.arrivals.iter().filter(|&&time| time > cutoff).count()
+    }
+
+    /// The last packet's interval, as a rough guide next to the jitter.
+    pub fn last_interval_ms(&self) -> Option<f64> {
+        self.last_interval_ms
+    }
+}
+
+impl AppState {
+    /// Updates the statistics of a source's stream on a universe. Called for
+    /// every data packet, whatever its start code. `captured` is when a
+    /// replayed packet was captured, its intervals are timed on that clock.
+    pub fn record_stream_stats(
+        &mut self,
+        universe: u16,
+        cid: Uuid,
+        source_name: &str,
+        source_ip: IpAddr,
+        sequence: u8,
+        captured: Option<DateTime<Utc>>,
+    ) {
+        let now = captured.unwrap_or_else(Utc::now);
+        match self.stream_stats.get_mut(&(universe, cid)) {
+            Some(stats) => {
+                if stats.replayed != captured.is_some() {
+                    stats.restart_timing();
+                    stats.replayed = captured.is_some();
+                }
+                stats.record(sequence, now);
+                stats.source_ip = source_ip;
+                if stats.source_name != source_name {
+                    stats.source_name = source_name.to_string();
+                }
+            }
+            None => {
+                let mut stats = StreamStats::new(
+                    universe,
+                    cid,
+                    source_name.to_string(),
+                    source_ip,
+                    sequence,
+                    now,
+                );
+                stats.replayed = captured.is_some();
+                self.stream_stats.insert((universe, cid), stats);
+            }
+        }
+    }
+
+    /// Restarts the timing of replayed streams after a replay seeks, so the
+    /// jump isn't counted as an interval.
+    pub fn restart_replayed_stream_timing(&mut self) {
+        for stats in self.stream_stats.values_mut() {
+            if stats.replayed {
+                stats.restart_timing();
+            }
+        }
+    }
+
+    /// Drops the statistics of streams that stopped or terminated, on the
+    /// clock their packets were timed on.
+    pub(super) fn remove_lost_stream_stats(&mut self) {
+        let clocks = self.clocks();
+        self.stream_stats.retain(|_, stats| {
+            !clocks.timed_out(stats.last_seen, stats.replayed, NETWORK_DATA_LOSS_TIMEOUT)
+        });
+    }
+
+    /// Forgets every stream's statistics, so counting starts over.
+    pub fn reset_stream_stats(&mut self) {
+        self.stream_stats.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    fn start() -> DateTime<Utc> {
+        DateTime::from_timestamp(1_700_000_000, 0).unwrap()
+    }
+
+    fn ms(ms: i64) -> chrono::Duration {
+        chrono::Duration::milliseconds(ms)
+    }
+
+    /// Statistics after packets with these sequence numbers, 25 ms apart.
+    fn received(sequences: &[u8]) -> StreamStats {
+        let mut stats = StreamStats::new(
+            1,
+            Uuid::from_bytes(&[1; 16]).unwrap(),
+            "Source".to_string(),
+            IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)),
+            sequences[0],
+            start(),
+        );
+        for (i, &sequence) in sequences.iter().enumerate().skip(1) {
+            stats.record(sequence, start() + ms(25 * i as i64));
+        }
+        stats
+    }
+
+    #[test]
+    fn counts_a_clean_stream() {
+        let stats = received(&[10, 11, 12, 13]);
+        assert_eq!(stats.packets, 4);
+        assert_eq!(
+            (stats.gaps, stats.out_of_order, stats.duplicates),
+            (0, 0, 0)
+        );
+        assert_eq!(stats.jitter_ms, 0.0);
+        assert_eq!(stats.last_interval_ms(), Some(25.0));
+        assert_eq!(stats.packets_per_second(start() + ms(75)), 4);
+    }
+
+    #[test]
+    fn counts_gaps() {
+        let stats = received(&[1, 2, 5, 6, 10]);
+        assert_eq!(stats.gaps, 5);
+        assert_eq!((stats.out_of_order, stats.duplicates), (0, 0));
+    }
+
+    #[test]
+    fn counts_duplicates() {
+        let stats = received(&[1, 2, 2, 2, 3]);
+        assert_eq!(stats.duplicates, 2);
+        assert_eq!((stats.gaps, stats.out_of_order), (0, 0));
+    }
+
+    #[test]
+    fn counts_out_of_order_packets() {
+        let stats = received(&[1, 3, 2, 4]);
+        assert_eq!(stats.out_of_order, 1);
+        assert_eq!(stats.gaps, 1, "2 was missing when 3 arrived");
+        assert_eq!(stats.duplicates, 0);
+
+        // A jump back past the window is the source restarting
+        let stats = received(&[100, 50, 51]);
+        assert_eq!(
+            (stats.gaps, stats.out_of_order, stats.duplicates),
+            (0, 0, 0)
+        );
+    }
+
+    #[test]
+    fn follows_sequences_across_the_wrap() {
+        let stats = received(&[254, 255, 0, 1]);
+        assert_eq!(
+            (stats.gaps, stats.out_of_order, stats.duplicates),
+            (0, 0, 0)
+        );
+
+        let stats = received(&[254, 1, 255]);
+        assert_eq!(stats.gaps, 2);
+        assert_eq!(stats.out_of_order, 1);
+    }
+
+    #[test]
+    fn measures_jitter() {
+        let mut stats = received(&[1]);
+        stats.record(2, start() + ms(20));
+        assert_eq!(stats.jitter_ms, 0.0, "needs two intervals");
+        stats.record(3, start() + ms(50));
+        assert_eq!(stats.jitter_ms, 10.0 / 16.0);
+        stats.record(4, start() + ms(70));
+        assert_eq!(stats.jitter_ms, 10.0 / 16.0 + (10.0 - 10.0 / 16.0) / 16.0);
+        assert_eq!(stats.last_interval_ms(), Some(20.0));
+    }
+
+    #[test]
+    fn times_replayed_packets_on_the_capture_clock() {
+        let mut state = AppState::new();
+        let cid = Uuid::from_bytes(&[2; 16]).unwrap();
+        let ip = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2));
+        // Played back at 10x, the packets arrive 2.5 ms apart
+        for (i, sequence) in (1..=4).enumerate() {
+            let captured = start() + ms(25 * i as i64);
+            state.record_stream_stats(1, cid, "Replay", ip, sequence, Some(captured));
+        }
+
+        let stats = &state.stream_stats[&(1, cid)];
+        assert!(stats.replayed);
+        assert_eq!(stats.last_interval_ms(), Some(25.0));
+        assert_eq!(stats.jitter_ms, 0.0);
+        assert_eq!(stats.last_seen, start() + ms(75));
+    }
+
+    #[test]
+    fn restarts_timing_when_a_replay_jumps_back() {
+        let mut stats = received(&[1, 2, 3]);
+        stats.record(4, start() - ms(5000));
+        assert_eq!(
+            stats.last_interval_ms(),
+            None,
+            "no interval across the jump"
+        );
+        assert_eq!(stats.jitter_ms, 0.0);
+        stats.record(5, start() - ms(4975));
+        assert_eq!(stats.last_interval_ms(), Some(25.0));
+
+        // A seek forward restarts it explicitly
+        stats.restart_timing();
+        stats.record(6, start() + ms(60_000));
+        assert_eq!(stats.last_interval_ms(), None);
+        assert_eq!(stats.jitter_ms, 0.0);
+    }
+
+    #[test]
+    fn forgets_lost_streams() {
+        let mut state = AppState::new();
+        let ip = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 3));
+        let (live, lost) = (
+            Uuid::from_bytes(&[3; 16]).unwrap(),
+            Uuid::from_bytes(&[4; 16]).unwrap(),
+        );
+        state.record_stream_stats(1, live, "Live", ip, 1, None);
+        state.record_stream_stats(1, lost, "Lost", ip, 1, None);
+        state.stream_stats.get_mut(&(1, lost)).unwrap().last_seen -= chrono::Duration::seconds(3);
+
+        state.check_source_loss();
+        assert!(state.stream_stats.contains_key(&(1, live)));
+        assert!(!state.stream_stats.contains_key(&(1, lost)));
+    }
+
+    #[test]
+    fn rates_over_the_last_second() {
+        let stats = received(&(0..48).collect::<Vec<u8>>());
+        assert_eq!(stats.packets_per_second(start() + ms(47 * 25)), 40);
+        assert_eq!(stats.packets_per_second(start() + ms(3000)), 0);
+    }
+}
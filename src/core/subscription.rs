@@ -0,0 +1,232 @@
+use super::{AppState, LogLevel};
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, BTreeSet};
+use std::net::IpAddr;
+use uuid::Uuid;
+
+/// A source's universe list is dropped if no discovery packet arrives for
+/// this long. Sources announce every 10 s, so this allows for one lost packet.
+pub const UNIVERSE_DISCOVERY_TIMEOUT: chrono::Duration = chrono::Duration::seconds(25);
+
+/// Lowest and highest universe that can be subscribed to.
+pub const MIN_UNIVERSE: u16 = 1;
+pub const MAX_UNIVERSE: u16 = 63999;
+
+/// The universes a source advertised in its E1.31 Universe Discovery packets.
+#[derive(Debug, Clone)]
+pub struct UniverseDiscovery {
+    pub cid: Uuid,
+    pub source_name: String,
+    pub source_ip: IpAddr,
+    pub pages: BTreeMap<u8, Vec<u16>>,
+    pub last_page: u8,
+    pub last_seen: DateTime<Utc>,
+}
+
+impl UniverseDiscovery {
+    pub fn universes(&self) -> BTreeSet<u16> {
+        self.pages.values().flatten().copied().collect()
+    }
+}
+
+impl AppState {
+    /// Records one page of a source's universe discovery list.
+    pub fn update_discovery(
+        &mut self,
+        cid: Uuid,
+        source_name: String,
+        source_ip: IpAddr,
+        page: u8,
+        last_page: u8,
+        universes: &[u16],
+    ) {
+        if !self.discovered_universes.contains_key(&cid) {
+            self.add_log(
+                LogLevel::Info,
+                format!("Source discovered: {} ({})", source_name, source_ip),
+            );
+        }
+
+        let discovery = self
+            .discovered_universes
+            .entry(cid)
+            .or_insert_with(|| UniverseDiscovery {
+                cid,
+                source_name: source_name.clone(),
+                source_ip,
+                pages: BTreeMap::new(),
+                last_page,
+                last_seen: Utc::now(),
+            });
+
+        discovery.source_name = source_name;
+        discovery.source_ip = source_ip;
+        discovery.last_page = last_page;
+        discovery.last_seen = Utc::now();
+        discovery.pages.retain(|&p, _| p <= last_page);
+        discovery.pages.insert(page, universes.to_vec());
+    }
+
+    /// Forgets sources that stopped sending universe discovery packets.
+    pub fn check_discovery_timeout(&mut self) {
+        let now = Utc::now();
+        let expired: Vec<UniverseDiscovery> = self
+            .discovered_universes
+            .values()
+            .filter(|discovery| now - discovery.last_seen > UNIVERSE_DISCOVERY_TIMEOUT)
+            .cloned()
+            .collect();
+
+        for discovery in expired {
+            self.discovered_universes.remove(&discovery.cid);
+            self.add_log(
+                LogLevel::Warning,
+                format!(
+                    "Source {} ({}) stopped announcing its universes",
+                    discovery.source_name, discovery.source_ip
+                ),
+            );
+        }
+    }
+
+    /// The universes the listener should currently be joined to, including
+    /// the sync universes of synchronized data and the bridge's inputs.
+    pub fn wanted_universes(&self) -> BTreeSet<u16> {
+        let mut universes = self.subscribed_universes.clone();
+        if self.auto_subscribe {
+            for discovery in self.discovered_universes.values() {
+                universes.extend(
+                    discovery
+                        .universes()
+                        .into_iter()
+                        .filter(|u| (MIN_UNIVERSE..=MAX_UNIVERSE).contains(u)),
+                );
+            }
+        }
+        universes.extend(self.sync_addresses());
+        universes.extend(self.bridge_input_universes());
+        universes.extend(self.route_input_universes());
+        universes
+    }
+
+    pub fn subscribe_universe(&mut self, universe: u16) {
+        if !(MIN_UNIVERSE..=MAX_UNIVERSE).contains(&universe) {
+            self.add_log(
+                LogLevel::Error,
+                format!(
+                    "Universe {} is outside {}-{}",
+                    universe, MIN_UNIVERSE, MAX_UNIVERSE
+                ),
+            );
+            return;
+        }
+
+        if self.subscribed_universes.insert(universe) {
+            self.save_subscriptions();
+        }
+    }
+
+    pub fn unsubscribe_universe(&mut self, universe: u16) {
+        if self.subscribed_universes.remove(&universe) {
+            self.save_subscriptions();
+        }
+    }
+
+    pub fn set_auto_subscribe(&mut self, auto_subscribe: bool) {
+        self.auto_subscribe = auto_subscribe;
+        self.save_subscriptions();
+    }
+
+    fn save_subscriptions(&mut self) {
+        self.settings.subscribed_universes = self.subscribed_universes.iter().copied().collect();
+        self.settings.auto_subscribe = self.auto_subscribe;
+        if let Err(e) = self.save_settings() {
+            self.add_log(LogLevel::Warning, format!("Failed to save settings: {}", e));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{BridgeDirection, BridgeMapping, Protocol, Route, SourceData};
+    use std::net::Ipv4Addr;
+
+    const SOURCE_IP: IpAddr = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+
+    fn discover(state: &mut AppState, universes: &[u16]) {
+        let cid = Uuid::from_bytes(&[1; 16]).unwrap();
+        state.update_discovery(cid, "Console".to_string(), SOURCE_IP, 0, 0, universes);
+    }
+
+    #[test]
+    fn wants_nothing_by_default() {
+        assert!(AppState::new().wanted_universes().is_empty());
+    }
+
+    #[test]
+    fn wants_manual_subscriptions() {
+        let mut state = AppState::new();
+        state.subscribed_universes = BTreeSet::from([3, 12]);
+        assert_eq!(state.wanted_universes(), BTreeSet::from([3, 12]));
+    }
+
+    #[test]
+    fn wants_discovered_universes_while_auto_subscribing() {
+        let mut state = AppState::new();
+        state.subscribed_universes = BTreeSet::from([3]);
+        discover(&mut state, &[1, 2, 0, 64000]);
+        assert_eq!(
+            state.wanted_universes(),
+            BTreeSet::from([1, 2, 3]),
+            "out of range universes are left out"
+        );
+
+        state.auto_subscribe = false;
+        assert_eq!(state.wanted_universes(), BTreeSet::from([3]));
+    }
+
+    #[test]
+    fn wants_sync_addresses() {
+        let mut state = AppState::new();
+        let source = SourceData::new(
+            Uuid::from_bytes(&[2; 16]).unwrap(),
+            "Synced".to_string(),
+            SOURCE_IP,
+            Protocol::Sacn,
+            100,
+            0,
+            [0; 512],
+        );
+        state.subscribed_universes = BTreeSet::from([1]);
+        state.receive_synchronized(1, source, 7, false);
+        assert_eq!(state.wanted_universes(), BTreeSet::from([1, 7]));
+    }
+
+    #[test]
+    fn wants_bridge_inputs_while_bridging() {
+        let mut state = AppState::new();
+        let mut mapping = BridgeMapping::new();
+        mapping.sacn_universe = 10;
+        mapping.count = 2;
+        state.bridge_mappings = vec![mapping.clone()];
+        assert!(state.wanted_universes().is_empty(), "bridge stopped");
+
+        state.bridge_enabled = true;
+        assert_eq!(state.wanted_universes(), BTreeSet::from([10, 11]));
+
+        mapping.direction = BridgeDirection::ArtNetToSacn;
+        state.bridge_mappings = vec![mapping];
+        assert!(state.wanted_universes().is_empty(), "input is Art-Net");
+    }
+
+    #[test]
+    fn wants_route_inputs() {
+        let mut state = AppState::new();
+        state.routes = vec![Route::new(4, 5), Route::new(6, 7)];
+        assert_eq!(state.wanted_universes(), BTreeSet::from([4, 6]));
+
+        state.routes[1].enabled = false;
+        assert_eq!(state.wanted_universes(), BTreeSet::from([4]));
+    }
+}
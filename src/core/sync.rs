@@ -0,0 +1,306 @@
+use super::{
+    AppState, LogLevel, SourceData, MAX_UNIVERSE, MIN_UNIVERSE, NETWORK_DATA_LOSS_TIMEOUT,
+};
+use chrono::{DateTime, Utc};
+use std::collections::BTreeSet;
+use uuid::Uuid;
+
+/// Data from one source on one universe that is latched by E1.31
+/// synchronization packets instead of being shown as it arrives.
+#[derive(Debug, Clone)]
+pub struct SyncGate {
+    pub universe: u16,
+    pub cid: Uuid,
+    pub source_name: String,
+    pub sync_address: u16,
+    pub force: bool, // Force_Synchronization: keep holding while sync is missing
+    pub pending: Option<SourceData>,
+    pub last_data: DateTime<Utc>,
+    pub last_sync: DateTime<Utc>, // or when the gate was created
+    pub waiting: bool,            // sync packets stopped arriving
+    pub replayed: bool,           // from an offline replay, ages on the capture's clock
+}
+
+impl AppState {
+    /// Holds a synchronized data packet until its sync packet arrives.
+    pub fn receive_synchronized(
+        &mut self,
+        universe: u16,
+        source: SourceData,
+        sync_address: u16,
+        force: bool,
+    ) {
+        let key = (universe, source.cid);
+        if !self.sync_gates.contains_key(&key) {
+            self.add_log(
+                LogLevel::Info,
+                format!(
+                    "Universe {} from {} is synchronized on universe {}",
+                    universe, source.source_name, sync_address
+                ),
+            );
+        }
+
+        let now = source.last_updated;
+        let gate = self.sync_gates.entry(key).or_insert_with(|| SyncGate {
+            universe,
+            cid: source.cid,
+            source_name: source.source_name.clone(),
+            sync_address,
+            force,
+            pending: None,
+            last_data: now,
+            last_sync: now,
+            waiting: false,
+            replayed: source.replayed,
+        });
+        if gate.sync_address != sync_address {
+            gate.sync_address = sync_address;
+            gate.last_sync = now;
+            gate.waiting = false;
+        }
+        gate.source_name = source.source_name.clone();
+        gate.force = force;
+        gate.last_data = now;
+        gate.replayed = source.replayed;
+
+        // Without sync packets the data is shown as it arrives, unless the
+        // source asked receivers to hold it
+        if gate.waiting && !force {
+            gate.pending = None;
+            self.update_universe(universe, source);
+        } else {
+            gate.pending = Some(source);
+        }
+    }
+
+    /// Drops the gate when a source switches back to unsynchronized data.
+    pub fn receive_unsynchronized(&mut self, universe: u16, cid: Uuid) {
+        if let Some(gate) = self.sync_gates.remove(&(universe, cid)) {
+            self.add_log(
+                LogLevel::Info,
+                format!(
+                    "Universe {} from {} is no longer synchronized",
+                    universe, gate.source_name
+                ),
+            );
+        }
+    }
+
+    /// Releases every frame the source is holding for this sync address.
+    /// `received` is when the sync packet arrived, or was captured if it's
+    /// replayed.
+    pub fn apply_sync(&mut self, cid: Uuid, sync_address: u16, received: DateTime<Utc>) {
+        let now = received;
+        let mut released = Vec::new();
+        let mut resumed = Vec::new();
+
+        for gate in self.sync_gates.values_mut() {
+            if gate.cid != cid || gate.sync_address != sync_address {
+                continue;
+            }
+            gate.last_sync = now;
+            if gate.waiting {
+                gate.waiting = false;
+                resumed.push((gate.universe, gate.source_name.clone()));
+            }
+            if let Some(source) = gate.pending.take() {
+                released.push((gate.universe, source));
+            }
+        }
+
+        for (universe, source_name) in resumed {
+            self.add_log(
+                LogLevel::Info,
+                format!(
+                    "Sync on universe {} from {} resumed for universe {}",
+                    sync_address, source_name, universe
+                ),
+            );
+        }
+        for (universe, source) in released {
+            self.update_universe(universe, source);
+        }
+    }
+
+    /// Flags gates whose sync packets stopped and forgets gates whose data
+    /// stopped.
+    pub fn check_sync_timeout(&mut self) {
+        let clocks = self.clocks();
+        self.sync_gates.retain(|_, gate| {
+            !clocks.timed_out(gate.last_data, gate.replayed, NETWORK_DATA_LOSS_TIMEOUT)
+        });
+
+        let mut missing = Vec::new();
+        for gate in self.sync_gates.values_mut() {
+            if gate.waiting
+                || !clocks.timed_out(gate.last_sync, gate.replayed, NETWORK_DATA_LOSS_TIMEOUT)
+            {
+                continue;
+            }
+            gate.waiting = true;
+            let pending = if gate.force {
+                None
+            } else {
+                gate.pending.take()
+            };
+            missing.push((gate.clone(), pending));
+        }
+
+        for (gate, pending) in missing {
+            self.add_log(
+                LogLevel::Warning,
+                format!(
+                    "Universe {} from {} is waiting on missing sync on universe {}{}",
+                    gate.universe,
+                    gate.source_name,
+                    gate.sync_address,
+                    if gate.force {
+                        ", holding data"
+                    } else {
+                        ", showing data unsynchronized"
+                    }
+                ),
+            );
+            if let Some(source) = pending {
+                self.update_universe(gate.universe, source);
+            }
+        }
+    }
+
+    /// Sync universes that must be joined to hear the sync packets.
+    pub fn sync_addresses(&self) -> BTreeSet<u16> {
+        self.sync_gates
+            .values()
+            .map(|gate| gate.sync_address)
+            .filter(|u| (MIN_UNIVERSE..=MAX_UNIVERSE).contains(u))
+            .collect()
+    }
+
+    /// Gates on this universe, sorted by source name.
+    pub fn universe_sync_gates(&self, universe: u16) -> Vec<&SyncGate> {
+        let mut gates: Vec<&SyncGate> = self
+            .sync_gates
+            .values()
+            .filter(|gate| gate.universe == universe)
+            .collect();
+        gates.sort_by(|a, b| a.source_name.cmp(&b.source_name));
+        gates
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Protocol;
+    use std::net::{IpAddr, Ipv4Addr};
+
+    const SYNC_ADDRESS: u16 = 7;
+
+    fn cid() -> Uuid {
+        Uuid::from_bytes(&[1; 16]).unwrap()
+    }
+
+    /// Levels from the test source at `level` on every channel, received
+    /// `age` ago.
+    fn source(level: u8, age: chrono::Duration) -> SourceData {
+        let mut source = SourceData::new(
+            cid(),
+            "Synced".to_string(),
+            IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)),
+            Protocol::Sacn,
+            100,
+            0,
+            [level; 512],
+        );
+        source.last_updated = Utc::now() - age;
+        source
+    }
+
+    fn shown(state: &AppState) -> Option<u8> {
+        state.universes.get(&1).map(|data| data.channels[0])
+    }
+
+    #[test]
+    fn holds_data_until_its_sync_packet() {
+        let mut state = AppState::new();
+        state.receive_synchronized(1, source(50, chrono::Duration::zero()), SYNC_ADDRESS, false);
+        assert_eq!(shown(&state), None);
+        assert_eq!(state.sync_addresses(), BTreeSet::from([SYNC_ADDRESS]));
+
+        state.apply_sync(cid(), SYNC_ADDRESS + 1, Utc::now());
+        state.apply_sync(
+            Uuid::from_bytes(&[2; 16]).unwrap(),
+            SYNC_ADDRESS,
+            Utc::now(),
+        );
+        assert_eq!(shown(&state), None, "sync from another address or source");
+
+        state.apply_sync(cid(), SYNC_ADDRESS, Utc::now());
+        assert_eq!(shown(&state), Some(50));
+
+        // Only the latest frame before a sync is shown
+        state.receive_synchronized(1, source(60, chrono::Duration::zero()), SYNC_ADDRESS, false);
+        state.receive_synchronized(1, source(70, chrono::Duration::zero()), SYNC_ADDRESS, false);
+        assert_eq!(shown(&state), Some(50));
+        state.apply_sync(cid(), SYNC_ADDRESS, Utc::now());
+        assert_eq!(shown(&state), Some(70));
+    }
+
+    #[test]
+    fn releases_data_when_sync_times_out() {
+        let mut state = AppState::new();
+        state.receive_synchronized(
+            1,
+            source(50, chrono::Duration::seconds(3)),
+            SYNC_ADDRESS,
+            false,
+        );
+        state.receive_synchronized(1, source(60, chrono::Duration::zero()), SYNC_ADDRESS, false);
+        assert_eq!(shown(&state), None);
+
+        state.check_sync_timeout();
+        assert_eq!(shown(&state), Some(60));
+        assert!(state.universe_sync_gates(1)[0].waiting);
+
+        // Data is shown as it arrives until sync packets resume
+        state.receive_synchronized(1, source(70, chrono::Duration::zero()), SYNC_ADDRESS, false);
+        assert_eq!(shown(&state), Some(70));
+        state.apply_sync(cid(), SYNC_ADDRESS, Utc::now());
+        assert!(!state.universe_sync_gates(1)[0].waiting);
+        state.receive_synchronized(1, source(80, chrono::Duration::zero()), SYNC_ADDRESS, false);
+        assert_eq!(shown(&state), Some(70));
+    }
+
+    #[test]
+    fn forced_sync_keeps_holding_on_timeout() {
+        let mut state = AppState::new();
+        state.receive_synchronized(
+            1,
+            source(50, chrono::Duration::seconds(3)),
+            SYNC_ADDRESS,
+            true,
+        );
+        state.receive_synchronized(1, source(60, chrono::Duration::zero()), SYNC_ADDRESS, true);
+
+        state.check_sync_timeout();
+        assert!(state.universe_sync_gates(1)[0].waiting);
+        assert_eq!(shown(&state), None);
+    }
+
+    #[test]
+    fn forgets_gates_when_data_stops() {
+        let mut state = AppState::new();
+        state.receive_synchronized(
+            1,
+            source(50, chrono::Duration::seconds(3)),
+            SYNC_ADDRESS,
+            false,
+        );
+
+        state.check_sync_timeout();
+        assert!(state.universe_sync_gates(1).is_empty());
+        assert!(state.sync_addresses().is_empty());
+        assert_eq!(shown(&state), None);
+    }
+}
@@ -0,0 +1,205 @@
+use super::{AppState, ArtNetOutput, LogLevel, MAX_UNIVERSE, MIN_UNIVERSE};
+use serde::{Deserialize, Serialize};
+
+/// Highest priority allowed by E1.31.
+pub const MAX_PRIORITY: u8 = 200;
+
+/// Default priority for new transmit universes.
+pub const DEFAULT_PRIORITY: u8 = 100;
+
+/// Default source name for the transmit session.
+pub const DEFAULT_SOURCE_NAME: &str = "sACN Viewer";
+
+/// Longest source name in bytes, the 64 byte field ends with a null.
+pub const MAX_SOURCE_NAME_LEN: usize = 63;
+
+/// One outgoing universe with its own levels and packet options.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransmitUniverse {
+    pub universe: u16,
+    pub enabled: bool,
+    pub priority: u8,
+    pub preview: bool, // sets the Preview_Data option bit
+    #[serde(default)]
+    pub sync_universe: u16, // universes sharing a sync universe latch together, 0 for none
+    #[serde(default)]
+    pub artnet: ArtNetOutput, // also sent as ArtDmx
+    #[serde(with = "levels")]
+    pub levels: [u8; 512],
+}
+
+impl TransmitUniverse {
+    pub fn new(universe: u16) -> Self {
+        Self {
+            universe,
+            enabled: true,
+            priority: DEFAULT_PRIORITY,
+            preview: false,
+            sync_universe: 0,
+            artnet: ArtNetOutput::Off,
+            levels: [0; 512],
+        }
+    }
+
+    /// Sets every given channel (0-based) to `value`.
+    pub fn set_channels(&mut self, channels: &[usize], value: u8) {
+        for &channel in channels {
+            self.levels[channel] = value;
+        }
+    }
+
+    /// Moves every given channel by `delta`, clamping at 0 and 255.
+    pub fn nudge_channels(&mut self, channels: &[usize], delta: i16) {
+        for &channel in channels {
+            self.levels[channel] = (self.levels[channel] as i16 + delta).clamp(0, 255) as u8;
+        }
+    }
+
+    /// Spreads levels linearly from `start` on the first channel to `end` on
+    /// the last, in the order given.
+    pub fn fan_channels(&mut self, channels: &[usize], start: u8, end: u8) {
+        let steps = channels.len().saturating_sub(1).max(1) as f32;
+        for (i, &channel) in channels.iter().enumerate() {
+            let t = i as f32 / steps;
+            self.levels[channel] = (start as f32 + (end as f32 - start as f32) * t).round() as u8;
+        }
+    }
+}
+
+/// Everything the app transmits: a source name and the universes sent under it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransmitSession {
+    pub source_name: String,
+    pub universes: Vec<TransmitUniverse>, // kept sorted by universe
+}
+
+impl Default for TransmitSession {
+    fn default() -> Self {
+        Self {
+            source_name: DEFAULT_SOURCE_NAME.to_string(),
+            universes: vec![TransmitUniverse::new(1)],
+        }
+    }
+}
+
+impl TransmitSession {
+    /// Cuts the source name to [`MAX_SOURCE_NAME_LEN`] bytes at a character
+    /// boundary, returns false if it already fit.
+    pub fn limit_source_name(&mut self) -> bool {
+        if self.source_name.len() <= MAX_SOURCE_NAME_LEN {
+            return false;
+        }
+        let end = (0..=MAX_SOURCE_NAME_LEN)
+            .rev()
+            .find(|&i| self.source_name.is_char_boundary(i))
+            .unwrap_or(0);
+        self.source_name.truncate(end);
+        true
+    }
+
+    pub fn universe(&self, universe: u16) -> Option<&TransmitUniverse> {
+        self.universes.iter().find(|u| u.universe == universe)
+    }
+
+    pub fn universe_mut(&mut self, universe: u16) -> Option<&mut TransmitUniverse> {
+        self.universes.iter_mut().find(|u| u.universe == universe)
+    }
+
+    /// Adds a universe with default options, returns false if it already exists.
+    pub fn add_universe(&mut self, universe: u16) -> bool {
+        if self.universe(universe).is_some() {
+            return false;
+        }
+        self.universes.push(TransmitUniverse::new(universe));
+        self.universes.sort_by_key(|u| u.universe);
+        true
+    }
+
+    pub fn remove_universe(&mut self, universe: u16) -> bool {
+        let len = self.universes.len();
+        self.universes.retain(|u| u.universe != universe);
+        self.universes.len() != len
+    }
+}
+
+impl AppState {
+    pub fn add_transmit_universe(&mut self, universe: u16) {
+        if !(MIN_UNIVERSE..=MAX_UNIVERSE).contains(&universe) {
+            self.add_log(
+                LogLevel::Error,
+                format!(
+                    "Universe {} is outside {}-{}",
+                    universe, MIN_UNIVERSE, MAX_UNIVERSE
+                ),
+            );
+            return;
+        }
+
+        if self.transmit_session.add_universe(universe) {
+            self.save_transmit_session();
+        }
+    }
+
+    pub fn remove_transmit_universe(&mut self, universe: u16) {
+        if self.transmit_session.remove_universe(universe) {
+            self.save_transmit_session();
+        }
+    }
+
+    /// Copies the transmit session into the settings and saves them. A source
+    /// name too long to send is cut short first.
+    pub fn save_transmit_session(&mut self) {
+        if self.transmit_session.limit_source_name() {
+            self.add_log(
+                LogLevel::Warning,
+                format!(
+                    "Source name cut to {} bytes: \"{}\"",
+                    MAX_SOURCE_NAME_LEN, self.transmit_session.source_name
+                ),
+            );
+        }
+        self.settings.transmit_session = self.transmit_session.clone();
+        if let Err(e) = self.save_settings() {
+            self.add_log(LogLevel::Warning, format!("Failed to save settings: {}", e));
+        }
+    }
+}
+
+/// Serializes a universe's levels as a plain list, serde only handles
+/// fixed-size arrays up to 32 elements.
+mod levels {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(levels: &[u8; 512], serializer: S) -> Result<S::Ok, S::Error> {
+        levels.as_slice().serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<[u8; 512], D::Error> {
+        let values = Vec::<u8>::deserialize(deserializer)?;
+        let mut levels = [0u8; 512];
+        let len = values.len().min(512);
+        levels[..len].copy_from_slice(&values[..len]);
+        Ok(levels)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limits_source_name_at_a_char_boundary() {
+        let mut session = TransmitSession::default();
+        assert!(!session.limit_source_name());
+        assert_eq!(session.source_name, DEFAULT_SOURCE_NAME);
+
+        session.source_name = "a".repeat(70);
+        assert!(session.limit_source_name());
+        assert_eq!(session.source_name, "a".repeat(63));
+
+        // 62 bytes, then a 2 byte character that would end at byte 64
+        session.source_name = format!("{}é", "a".repeat(62));
+        assert!(session.limit_source_name());
+        assert_eq!(session.source_name, "a".repeat(62));
+    }
+}
@@ -0,0 +1,79 @@
+use crate::core::AppState;
+use crate::network::SacnNetwork;
+use anyhow::Result;
+use log::{info, warn};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// Runs the network side without a window until SIGTERM or Ctrl+C, then
+/// terminates every stream so receivers don't wait out the timeout.
+pub async fn run(app_state: Arc<RwLock<AppState>>, network: Arc<SacnNetwork>) -> Result<()> {
+    {
+        let state = app_state.read().await;
+        let universes: Vec<String> = state
+            .transmit_session
+            .universes
+            .iter()
+            .filter(|u| u.enabled)
+            .map(|u| u.universe.to_string())
+            .collect();
+        if state.auto_send_enabled {
+            info!(
+                "Sending universes [{}] at {} Hz as \"{}\"",
+                universes.join(", "),
+                state.send_rate,
+                state.transmit_session.source_name
+            );
+        } else {
+            warn!("Auto send is off in the settings, only routed universes will be sent");
+        }
+        info!(
+            "{} of {} routes enabled, bridge {}",
+            state.routes.iter().filter(|r| r.enabled).count(),
+            state.routes.len(),
+            if state.bridge_enabled { "on" } else { "off" }
+        );
+    }
+
+    info!("Running headless, stop with SIGTERM or Ctrl+C");
+    wait_for_shutdown().await?;
+    info!("Shutting down");
+
+    // Holding the lock keeps the transmitter and bridge from starting new
+    // streams while ours are terminated. Routes send without auto send, so
+    // they're switched off too, without saving.
+    let mut state = app_state.write().await;
+    state.auto_send_enabled = false;
+    state.bridge_enabled = false;
+    for route in &mut state.routes {
+        route.enabled = false;
+    }
+    network.stop_transmitting();
+    info!("Streams terminated");
+    Ok(())
+}
+
+#[cfg(unix)]
+async fn wait_for_shutdown() -> Result<()> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut terminate = signal(SignalKind::terminate())
+        .map_err(|e| anyhow::anyhow!("Failed to listen for SIGTERM: {}", e))?;
+    tokio::select! {
+        _ = terminate.recv() => info!("Received SIGTERM"),
+        result = tokio::signal::ctrl_c() => {
+            result.map_err(|e| anyhow::anyhow!("Failed to listen for Ctrl+C: {}", e))?;
+            info!("Received Ctrl+C");
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+async fn wait_for_shutdown() -> Result<()> {
+    tokio::signal::ctrl_c()
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to listen for Ctrl+C: {}", e))?;
+    info!("Received Ctrl+C");
+    Ok(())
+}
@@ -1,10 +1,12 @@
 use anyhow::Result;
 use eframe::egui;
 use log::info;
+use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
 mod core;
+mod headless;
 mod network;
 mod ui;
 
@@ -12,9 +14,52 @@ use core::AppState;
 use network::SacnNetwork;
 use ui::MainWindow;
 
+const USAGE: &str = "Usage: sacn-viewer [--headless] [--config <settings.json>]";
+
+/// Command line options.
+#[derive(Debug, PartialEq)]
+struct Options {
+    help: bool,              // print the usage and quit
+    headless: bool,          // run without the window, until SIGTERM
+    config: Option<PathBuf>, // settings file to use instead of the default one
+}
+
+impl Options {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self> {
+        let mut options = Options {
+            help: false,
+            headless: false,
+            config: None,
+        };
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--headless" => options.headless = true,
+                "--config" => {
+                    let path = args
+                        .next()
+                        .ok_or_else(|| anyhow::anyhow!("--config needs a file\n{}", USAGE))?;
+                    options.config = Some(PathBuf::from(path));
+                }
+                "-h" | "--help" => options.help = true,
+                _ => return Err(anyhow::anyhow!("Unknown argument {}\n{}", arg, USAGE)),
+            }
+        }
+        Ok(options)
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
-    env_logger::init();
+    let options = Options::parse(std::env::args().skip(1))?;
+    if options.help {
+        println!("{}", USAGE);
+        return Ok(());
+    }
+
+    // Headless runs have nothing but the logger to report to
+    let default_filter = if options.headless { "info" } else { "error" };
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(default_filter))
+        .init();
     info!("Starting sACN Desktop Viewer");
 
     let app_state = Arc::new(RwLock::new(AppState::new()));
@@ -22,8 +67,21 @@ async fn main() -> Result<()> {
     // Initialize network adapters and load settings
     {
         let mut state = app_state.write().await;
+        if let Some(config) = options.config {
+            if !config.exists() {
+                return Err(anyhow::anyhow!(
+                    "Settings file {} not found",
+                    config.display()
+                ));
+            }
+            state.settings_path = Some(config);
+        }
         state.refresh_network_adapters();
         if let Err(e) = state.load_settings() {
+            // Running on defaults would quietly send nothing
+            if options.headless {
+                return Err(anyhow::anyhow!("Failed to load settings: {}", e));
+            }
             log::warn!("Failed to load settings: {}", e);
         }
     }
@@ -38,6 +96,43 @@ async fn main() -> Result<()> {
         }
     });
 
+    // Receive Art-Net next to sACN
+    let network_clone = sacn_network.clone();
+    tokio::spawn(async move {
+        if let Err(e) = network_clone.start_artnet_listener().await {
+            log::error!("Art-Net listener error: {}", e);
+        }
+    });
+
+    // Stream the transmit universes in another background task
+    let network_clone = sacn_network.clone();
+    tokio::spawn(async move {
+        if let Err(e) = network_clone.start_transmitter().await {
+            log::error!("Network transmitter error: {}", e);
+        }
+    });
+
+    // Convert between sACN and Art-Net
+    let network_clone = sacn_network.clone();
+    tokio::spawn(async move {
+        if let Err(e) = network_clone.start_bridge().await {
+            log::error!("Protocol bridge error: {}", e);
+        }
+    });
+
+    // Everything else needs the window to drive it
+    if options.headless {
+        return headless::run(app_state, sacn_network).await;
+    }
+
+    // Play back loaded captures in another
+    let network_clone = sacn_network.clone();
+    tokio::spawn(async move {
+        if let Err(e) = network_clone.start_replayer().await {
+            log::error!("Replay engine error: {}", e);
+        }
+    });
+
     // Run the GUI
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
@@ -53,3 +148,53 @@ async fn main() -> Result<()> {
     )
     .map_err(|e| anyhow::anyhow!("GUI error: {}", e))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options> {
+        Options::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn parses_no_arguments_to_the_window() {
+        let options = parse(&[]).unwrap();
+        assert_eq!(
+            options,
+            Options {
+                help: false,
+                headless: false,
+                config: None,
+            }
+        );
+    }
+
+    #[test]
+    fn parses_headless_and_config() {
+        let options = parse(&["--config", "show.json", "--headless"]).unwrap();
+        assert!(options.headless);
+        assert!(!options.help);
+        assert_eq!(options.config, Some(PathBuf::from("show.json")));
+    }
+
+    #[test]
+    fn parses_help() {
+        assert!(parse(&["--help"]).unwrap().help);
+        assert!(parse(&["--headless", "-h"]).unwrap().help);
+    }
+
+    #[test]
+    fn needs_a_file_after_config() {
+        let error = parse(&["--headless", "--config"]).unwrap_err().to_string();
+        assert!(error.starts_with("--config needs a file"));
+        assert!(error.ends_with(USAGE));
+    }
+
+    #[test]
+    fn rejects_unknown_arguments() {
+        let error = parse(&["--verbose"]).unwrap_err().to_string();
+        assert!(error.starts_with("Unknown argument --verbose"));
+        assert!(error.ends_with(USAGE));
+    }
+}
@@ -0,0 +1,387 @@
+use crate::core::{NodePort, PortAddress};
+use std::borrow::Cow;
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr};
+use uuid::Uuid;
+
+/// UDP port every Art-Net node sends and listens on.
+pub const ARTNET_PORT: u16 = 6454;
+
+/// Largest Art-Net packet we act on (an ArtDmx packet with 512 slots).
+pub const MAX_PACKET_SIZE: usize = 530;
+
+const ARTNET_ID: &[u8; 8] = b"Art-Net\0";
+const OP_POLL: u16 = 0x2000;
+const OP_POLL_REPLY: u16 = 0x2100;
+const OP_DMX: u16 = 0x5000;
+const PROTOCOL_VERSION: u16 = 14;
+
+/// Where the fields of an ArtDmx packet start.
+const OPCODE_OFFSET: usize = 8;
+const DMX_HEADER_LEN: usize = 18;
+const MAX_DMX_LENGTH: usize = 512;
+
+/// ArtPollReply fields, up to the output switches every revision has.
+const POLL_REPLY_SHORT_NAME: usize = 26;
+const POLL_REPLY_LONG_NAME: usize = 44;
+const POLL_REPLY_NODE_REPORT: usize = 108;
+const POLL_REPLY_PORTS: usize = 172;
+const POLL_REPLY_MIN_LEN: usize = 194;
+const POLL_REPLY_BIND_INDEX: usize = 211;
+const MAX_NODE_PORTS: usize = 4;
+
+/// ArtPollReply port type bits.
+const PORT_OUTPUT: u8 = 0x80;
+const PORT_INPUT: u8 = 0x40;
+
+/// Prefix of the stand-in CIDs given to Art-Net nodes, which have none.
+const NODE_ID_PREFIX: [u8; 12] = *b"Art-Net node";
+
+/// An Art-Net packet we act on, borrowing from the receive buffer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArtPacket<'a> {
+    Dmx(ArtDmx<'a>),
+    PollReply(ArtPollReply<'a>),
+}
+
+/// An ArtDmx packet carrying the levels of one port address.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArtDmx<'a> {
+    pub sequence: u8, // 0 when the sender doesn't sequence its packets
+    pub physical: u8,
+    pub port_address: PortAddress,
+    pub data: &'a [u8],
+}
+
+/// A node describing itself in answer to an ArtPoll.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArtPollReply<'a> {
+    pub ip: Ipv4Addr,
+    pub firmware: u16,
+    pub net_switch: u8,
+    pub sub_switch: u8,
+    pub short_name: Cow<'a, str>,
+    pub long_name: Cow<'a, str>,
+    pub node_report: Cow<'a, str>,
+    pub port_count: usize,
+    pub port_types: [u8; MAX_NODE_PORTS],
+    pub input_switches: [u8; MAX_NODE_PORTS],
+    pub output_switches: [u8; MAX_NODE_PORTS],
+    pub bind_index: u8, // 0 from nodes older than Art-Net 4
+}
+
+impl ArtPollReply<'_> {
+    /// The node's ports with the port addresses each direction is patched to.
+    pub fn ports(&self) -> Vec<NodePort> {
+        (0..self.port_count)
+            .map(|i| {
+                let address =
+                    |switch: u8| PortAddress::from_parts(self.net_switch, self.sub_switch, switch);
+                NodePort {
+                    bind_index: self.bind_index,
+                    input: (self.port_types[i] & PORT_INPUT != 0)
+                        .then(|| address(self.input_switches[i])),
+                    output: (self.port_types[i] & PORT_OUTPUT != 0)
+                        .then(|| address(self.output_switches[i])),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Why a buffer isn't an Art-Net packet we can use.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    Truncated { needed: usize, available: usize },
+    Identifier,
+    OpCode(u16), // valid Art-Net, just not an operation we handle
+    ProtocolVersion(u16),
+    DmxLength(usize),
+    PortCount(u16),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Truncated { needed, available } => {
+                write!(f, "Packet too short: {} bytes, need {}", available, needed)
+            }
+            ParseError::Identifier => write!(f, "Not an Art-Net packet"),
+            ParseError::OpCode(opcode) => write!(f, "Unsupported OpCode {:#06x}", opcode),
+            ParseError::ProtocolVersion(version) => {
+                write!(f, "Unsupported protocol version {}", version)
+            }
+            ParseError::DmxLength(length) => write!(f, "Invalid DMX length {}", length),
+            ParseError::PortCount(count) => write!(f, "Invalid port count {}", count),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+fn check_len(buf: &[u8], needed: usize) -> Result<(), ParseError> {
+    if buf.len() < needed {
+        return Err(ParseError::Truncated {
+            needed,
+            available: buf.len(),
+        });
+    }
+    Ok(())
+}
+
+/// Decodes an Art-Net packet. Trailing bytes after the DMX data are
+/// ignored, some nodes pad their packets.
+pub fn parse(buf: &[u8]) -> Result<ArtPacket<'_>, ParseError> {
+    check_len(buf, OPCODE_OFFSET + 2)?;
+    if &buf[..OPCODE_OFFSET] != ARTNET_ID {
+        return Err(ParseError::Identifier);
+    }
+
+    let opcode = u16::from_le_bytes([buf[8], buf[9]]);
+    match opcode {
+        OP_DMX => parse_dmx(buf).map(ArtPacket::Dmx),
+        OP_POLL_REPLY => parse_poll_reply(buf).map(ArtPacket::PollReply),
+        other => Err(ParseError::OpCode(other)),
+    }
+}
+
+fn parse_dmx(buf: &[u8]) -> Result<ArtDmx<'_>, ParseError> {
+    check_len(buf, DMX_HEADER_LEN)?;
+
+    let version = u16::from_be_bytes([buf[10], buf[11]]);
+    if version < PROTOCOL_VERSION {
+        return Err(ParseError::ProtocolVersion(version));
+    }
+
+    let length = u16::from_be_bytes([buf[16], buf[17]]) as usize;
+    if length == 0 || length > MAX_DMX_LENGTH {
+        return Err(ParseError::DmxLength(length));
+    }
+    check_len(buf, DMX_HEADER_LEN + length)?;
+
+    Ok(ArtDmx {
+        sequence: buf[12],
+        physical: buf[13],
+        port_address: PortAddress::new(u16::from_le_bytes([buf[14], buf[15]])),
+        data: &buf[DMX_HEADER_LEN..DMX_HEADER_LEN + length],
+    })
+}
+
+/// Decodes an ArtPollReply, which has no protocol version field.
+fn parse_poll_reply(buf: &[u8]) -> Result<ArtPollReply<'_>, ParseError> {
+    check_len(buf, POLL_REPLY_MIN_LEN)?;
+
+    let port_count = u16::from_be_bytes([buf[POLL_REPLY_PORTS], buf[POLL_REPLY_PORTS + 1]]);
+    if port_count as usize > MAX_NODE_PORTS {
+        return Err(ParseError::PortCount(port_count));
+    }
+    let ports = |offset: usize| -> [u8; MAX_NODE_PORTS] {
+        let start = POLL_REPLY_PORTS + 2 + offset * MAX_NODE_PORTS;
+        [buf[start], buf[start + 1], buf[start + 2], buf[start + 3]]
+    };
+
+    Ok(ArtPollReply {
+        ip: Ipv4Addr::new(buf[10], buf[11], buf[12], buf[13]),
+        firmware: u16::from_be_bytes([buf[16], buf[17]]),
+        net_switch: buf[18] & 0x7f,
+        sub_switch: buf[19] & 0x0f,
+        short_name: text(&buf[POLL_REPLY_SHORT_NAME..POLL_REPLY_LONG_NAME]),
+        long_name: text(&buf[POLL_REPLY_LONG_NAME..POLL_REPLY_NODE_REPORT]),
+        node_report: text(&buf[POLL_REPLY_NODE_REPORT..POLL_REPLY_PORTS]),
+        port_count: port_count as usize,
+        port_types: ports(0),
+        input_switches: ports(3),
+        output_switches: ports(4),
+        bind_index: buf.get(POLL_REPLY_BIND_INDEX).copied().unwrap_or(0),
+    })
+}
+
+/// A null-terminated text field.
+fn text(field: &[u8]) -> Cow<'_, str> {
+    let end = field
+        .iter()
+        .position(|&byte| byte == 0)
+        .unwrap_or(field.len());
+    String::from_utf8_lossy(&field[..end])
+}
+
+fn header(opcode: u16) -> Vec<u8> {
+    let mut packet = ARTNET_ID.to_vec();
+    packet.extend_from_slice(&opcode.to_le_bytes());
+    packet.extend_from_slice(&PROTOCOL_VERSION.to_be_bytes());
+    packet
+}
+
+/// An ArtPoll asking every node to reply to the sender only.
+pub fn poll_packet() -> Vec<u8> {
+    let mut packet = header(OP_POLL);
+    packet.extend_from_slice(&[0x00, 0x00]); // flags, diagnostics priority
+    packet
+}
+
+/// An ArtDmx packet. Art-Net wants an even number of slots, so odd
+/// lengths are padded with a zero.
+pub fn dmx_packet(sequence: u8, port_address: PortAddress, data: &[u8]) -> Vec<u8> {
+    let data = &data[..data.len().min(MAX_DMX_LENGTH)];
+    let length = data.len() + data.len() % 2;
+
+    let mut packet = header(OP_DMX);
+    packet.push(sequence);
+    packet.push(0); // physical port
+    packet.extend_from_slice(&port_address.value().to_le_bytes());
+    packet.extend_from_slice(&(length as u16).to_be_bytes());
+    packet.extend_from_slice(data);
+    packet.resize(DMX_HEADER_LEN + length, 0);
+    packet
+}
+
+/// A stable stand-in CID for an Art-Net node, so it can be merged and
+/// listed next to sACN sources.
+pub fn node_id(ip: IpAddr) -> Uuid {
+    let mut bytes = [0u8; 16];
+    bytes[..12].copy_from_slice(&NODE_ID_PREFIX);
+    match ip {
+        IpAddr::V4(ip) => bytes[12..].copy_from_slice(&ip.octets()),
+        IpAddr::V6(ip) => bytes[12..].copy_from_slice(&ip.octets()[12..]),
+    }
+    Uuid::from_uuid_bytes(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An ArtDmx packet for port address 1:2:3 with 4 slots.
+    fn dmx_fixture() -> Vec<u8> {
+        [
+            &b"Art-Net\0"[..],
+            &[0x00, 0x50, 0x00, 0x0e, 0x2a, 0x00, 0x23, 0x01, 0x00, 0x04],
+            &[0x00, 0xff, 0x80, 0x40],
+        ]
+        .concat()
+    }
+
+    #[test]
+    fn parses_dmx() {
+        let buf = dmx_fixture();
+        let Ok(ArtPacket::Dmx(dmx)) = parse(&buf) else {
+            panic!("not an ArtDmx packet");
+        };
+        assert_eq!(dmx.sequence, 42);
+        assert_eq!(dmx.port_address.value(), 0x0123);
+        assert_eq!(dmx.port_address.to_string(), "1:2:3");
+        assert_eq!(dmx.data, &[0x00, 0xff, 0x80, 0x40]);
+    }
+
+    #[test]
+    fn ignores_padding() {
+        let mut buf = dmx_fixture();
+        buf.extend_from_slice(&[0; 6]);
+        let Ok(ArtPacket::Dmx(dmx)) = parse(&buf) else {
+            panic!("not an ArtDmx packet");
+        };
+        assert_eq!(dmx.data.len(), 4);
+    }
+
+    #[test]
+    fn rejects_short_data() {
+        let buf = dmx_fixture();
+        assert_eq!(
+            parse(&buf[..20]),
+            Err(ParseError::Truncated {
+                needed: 22,
+                available: 20
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_other_packets() {
+        let mut buf = dmx_fixture();
+        buf[0] = b'a';
+        assert_eq!(parse(&buf), Err(ParseError::Identifier));
+
+        let mut buf = dmx_fixture();
+        buf[9] = 0x20;
+        assert_eq!(parse(&buf), Err(ParseError::OpCode(0x2000)));
+
+        let mut buf = poll_reply_fixture();
+        buf[173] = 5;
+        assert_eq!(parse(&buf), Err(ParseError::PortCount(5)));
+
+        let mut buf = dmx_fixture();
+        buf[11] = 13;
+        assert_eq!(parse(&buf), Err(ParseError::ProtocolVersion(13)));
+
+        let mut buf = dmx_fixture();
+        buf[16] = 0x02;
+        buf[17] = 0x01;
+        assert_eq!(parse(&buf), Err(ParseError::DmxLength(513)));
+    }
+
+    /// An ArtPollReply from "Node 1" with an output on 0:1:2 and an input
+    /// on 0:1:5.
+    fn poll_reply_fixture() -> Vec<u8> {
+        let mut buf = vec![0u8; 239];
+        buf[..8].copy_from_slice(b"Art-Net\0");
+        buf[8..10].copy_from_slice(&[0x00, 0x21]);
+        buf[10..14].copy_from_slice(&[10, 0, 0, 9]);
+        buf[16..18].copy_from_slice(&[0x01, 0x02]);
+        buf[19] = 0x01;
+        buf[26..32].copy_from_slice(b"Node 1");
+        buf[44..55].copy_from_slice(b"Long name 1");
+        buf[173] = 2;
+        buf[174..176].copy_from_slice(&[0x80, 0x40]);
+        buf[186..188].copy_from_slice(&[0x00, 0x05]);
+        buf[190..192].copy_from_slice(&[0x02, 0x00]);
+        buf[211] = 1;
+        buf
+    }
+
+    #[test]
+    fn parses_poll_reply() {
+        let buf = poll_reply_fixture();
+        let Ok(ArtPacket::PollReply(reply)) = parse(&buf) else {
+            panic!("not a poll reply");
+        };
+        assert_eq!(reply.ip, Ipv4Addr::new(10, 0, 0, 9));
+        assert_eq!(reply.firmware, 0x0102);
+        assert_eq!(reply.short_name, "Node 1");
+        assert_eq!(reply.long_name, "Long name 1");
+        assert_eq!(
+            reply.ports(),
+            vec![
+                NodePort {
+                    bind_index: 1,
+                    input: None,
+                    output: Some(PortAddress::from_parts(0, 1, 2)),
+                },
+                NodePort {
+                    bind_index: 1,
+                    input: Some(PortAddress::from_parts(0, 1, 5)),
+                    output: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn dmx_packet_round_trips() {
+        let port_address = PortAddress::from_parts(1, 2, 3);
+        let buf = dmx_packet(7, port_address, &[1, 2, 3]);
+        let Ok(ArtPacket::Dmx(dmx)) = parse(&buf) else {
+            panic!("not an ArtDmx packet");
+        };
+        assert_eq!(dmx.sequence, 7);
+        assert_eq!(dmx.port_address, port_address);
+        assert_eq!(dmx.data, &[1, 2, 3, 0]);
+    }
+
+    #[test]
+    fn node_id_is_stable_per_ip() {
+        let a: IpAddr = "10.0.0.5".parse().unwrap();
+        let b: IpAddr = "10.0.0.6".parse().unwrap();
+        assert_eq!(node_id(a), node_id(a));
+        assert_ne!(node_id(a), node_id(b));
+    }
+}
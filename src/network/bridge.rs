@@ -0,0 +1,106 @@
+use super::{SacnNetwork, MAX_SEND_RATE};
+use crate::core::{BridgeTarget, LogLevel, TransmitUniverse};
+use anyhow::Result;
+use log::info;
+use std::collections::BTreeSet;
+use std::time::Duration;
+use tokio::time::sleep;
+
+impl SacnNetwork {
+    /// Re-sends the merged levels of every bridge mapping's inputs to its
+    /// outputs at the bridge rate, terminating sACN output whose input went
+    /// away.
+    pub async fn start_bridge(&self) -> Result<()> {
+        info!("Starting protocol bridge");
+        let mut active: BTreeSet<String> = BTreeSet::new(); // targets being sent, for the log
+        let mut last_errors: Vec<Option<String>> = Vec::new();
+
+        loop {
+            let (frames, rate, session) = {
+                let mut state = self.app_state.write().await;
+                (
+                    state.bridge_frames(),
+                    state.bridge_rate.clamp(1, MAX_SEND_RATE),
+                    state.transmit_session.clone(),
+                )
+            };
+            let identity = self.transmit_identity().await;
+
+            let mut results = Vec::with_capacity(frames.len());
+            let mut stopped = Vec::new();
+            {
+                let mut transmitter = self.transmitter();
+                let mut sacn_targets = BTreeSet::new();
+                for frame in &frames {
+                    let result = match frame.target {
+                        BridgeTarget::Sacn { universe, priority } => {
+                            sacn_targets.insert(universe);
+                            let mut output = TransmitUniverse::new(universe);
+                            output.priority = priority;
+                            output.levels = frame.levels;
+                            transmitter.send_bridged(&identity, &output)
+                        }
+                        BridgeTarget::ArtNet {
+                            port_address,
+                            output,
+                        } => {
+                            transmitter.send_artdmx(&identity, output, port_address, &frame.levels)
+                        }
+                    };
+                    results.push(result.map_err(|e| e.to_string()));
+                }
+
+                for universe in transmitter.bridged_universes() {
+                    if !sacn_targets.contains(&universe) {
+                        // Universes the DMX sender also sends are handed back to it
+                        let owned = session.universe(universe).filter(|u| u.enabled);
+                        let result = transmitter.stop_bridged(&identity, universe, owned);
+                        stopped.push((universe, result.map_err(|e| e.to_string())));
+                    }
+                }
+            }
+
+            let current: BTreeSet<String> = frames
+                .iter()
+                .map(|frame| frame.target.to_string())
+                .collect();
+            let mut state = self.app_state.write().await;
+            for (frame, result) in frames.iter().zip(results) {
+                state.record_bridge_result(frame.mapping, result);
+            }
+            for target in current.difference(&active) {
+                state.add_log(LogLevel::Tx, format!("Bridging to {}", target));
+            }
+            for target in active.difference(&current) {
+                state.add_log(LogLevel::Tx, format!("Stopped bridging to {}", target));
+            }
+            for (universe, result) in stopped {
+                if let Err(e) = result {
+                    state.add_log(
+                        LogLevel::Error,
+                        format!("Failed to terminate bridged universe {}: {}", universe, e),
+                    );
+                }
+            }
+
+            // Log each mapping's error once, not on every frame
+            let errors: Vec<Option<String>> = state
+                .bridge_status
+                .iter()
+                .map(|status| status.error.clone())
+                .collect();
+            for (index, error) in errors.iter().enumerate() {
+                if let Some(e) = error {
+                    if last_errors.get(index) != Some(error) {
+                        state.add_log(LogLevel::Error, format!("Bridge: {}", e));
+                    }
+                }
+            }
+            last_errors = errors;
+            active = current;
+            drop(state);
+
+            sleep(Duration::from_secs_f64(1.0 / rate as f64)).await;
+        }
+    }
+}
@@ -0,0 +1,329 @@
+use super::e131::{Packet, PacketData, DATA_UNIVERSE_OFFSET};
+use super::pcap;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use sacn::packet::E131_DISCOVERY_UNIVERSE;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufWriter, Read, Write};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+
+/// First bytes of every capture file. Records follow, integers little-endian:
+/// timestamp (i64, microseconds since the Unix epoch), address family (u8,
+/// 4 or 6), source IP (4 or 16 bytes), source port (u16), universe (u16: the
+/// data universe, sync address or discovery universe), payload length (u16)
+/// and the UDP payload as received.
+pub const CAPTURE_MAGIC: &[u8; 8] = b"SACNCAP1";
+
+/// Extension used for capture files.
+pub const CAPTURE_EXTENSION: &str = "sacncap";
+
+/// One received packet.
+#[derive(Debug, Clone)]
+pub struct CaptureRecord {
+    pub timestamp: DateTime<Utc>,
+    pub source: SocketAddr,
+    pub universe: u16,
+    pub payload: Vec<u8>,
+}
+
+/// The packets read from a capture file.
+#[derive(Debug, Default)]
+pub struct LoadedCapture {
+    pub records: Vec<CaptureRecord>,
+    /// sACN port datagrams the E1.31 parser rejected, counted by reason.
+    pub rejected: BTreeMap<String, usize>,
+}
+
+/// What the recorder is doing, for display.
+#[derive(Debug, Clone)]
+pub struct RecordingStatus {
+    pub path: PathBuf,
+    pub started: DateTime<Utc>,
+    pub packets: u64,
+    pub bytes: u64,
+}
+
+/// Appends records to a capture file.
+pub struct CaptureWriter {
+    writer: BufWriter<File>,
+    status: RecordingStatus,
+}
+
+impl CaptureWriter {
+    pub fn create(path: &Path) -> Result<Self> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| anyhow::anyhow!("Failed to create {}: {}", dir.display(), e))?;
+        }
+        let file = File::create(path)
+            .map_err(|e| anyhow::anyhow!("Failed to create {}: {}", path.display(), e))?;
+
+        let mut writer = BufWriter::new(file);
+        writer.write_all(CAPTURE_MAGIC)?;
+
+        Ok(Self {
+            writer,
+            status: RecordingStatus {
+                path: path.to_path_buf(),
+                started: Utc::now(),
+                packets: 0,
+                bytes: CAPTURE_MAGIC.len() as u64,
+            },
+        })
+    }
+
+    pub fn write(&mut self, record: &CaptureRecord) -> Result<()> {
+        let length = u16::try_from(record.payload.len())
+            .map_err(|_| anyhow::anyhow!("Packet too large to record"))?;
+
+        let mut header = Vec::with_capacity(31);
+        header.extend_from_slice(&record.timestamp.timestamp_micros().to_le_bytes());
+        match record.source.ip() {
+            IpAddr::V4(ip) => {
+                header.push(4);
+                header.extend_from_slice(&ip.octets());
+            }
+            IpAddr::V6(ip) => {
+                header.push(6);
+                header.extend_from_slice(&ip.octets());
+            }
+        }
+        header.extend_from_slice(&record.source.port().to_le_bytes());
+        header.extend_from_slice(&record.universe.to_le_bytes());
+        header.extend_from_slice(&length.to_le_bytes());
+
+        self.writer
+            .write_all(&header)
+            .and_then(|()| self.writer.write_all(&record.payload))
+            .map_err(|e| {
+                anyhow::anyhow!("Failed to write {}: {}", self.status.path.display(), e)
+            })?;
+
+        self.status.packets += 1;
+        self.status.bytes += (header.len() + record.payload.len()) as u64;
+        Ok(())
+    }
+
+    pub fn status(&self) -> &RecordingStatus {
+        &self.status
+    }
+
+    /// Flushes the file and returns the final status.
+    pub fn finish(mut self) -> Result<RecordingStatus> {
+        self.writer.flush().map_err(|e| {
+            anyhow::anyhow!("Failed to write {}: {}", self.status.path.display(), e)
+        })?;
+        Ok(self.status)
+    }
+}
+
+/// Reads every sACN packet from a capture file: our own format, or a pcap or
+/// pcapng file from Wireshark or tcpdump. Records are returned in file order.
+pub fn load_capture(path: &Path) -> Result<LoadedCapture> {
+    let mut contents = Vec::new();
+    File::open(path)
+        .and_then(|mut file| file.read_to_end(&mut contents))
+        .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?;
+
+    if let Some(records) = contents.strip_prefix(CAPTURE_MAGIC.as_slice()) {
+        Ok(LoadedCapture {
+            records: parse_capture(records)?,
+            ..Default::default()
+        })
+    } else if pcap::is_pcap(&contents) {
+        pcap::parse_pcap(&contents)
+    } else if pcap::is_pcapng(&contents) {
+        pcap::parse_pcapng(&contents)
+    } else {
+        Err(anyhow::anyhow!(
+            "{} is not an sACN capture, pcap or pcapng file",
+            path.display()
+        ))
+    }
+}
+
+/// Parses the records after the magic. A record cut short at the end of the
+/// file, e.g. after a crash mid-recording, is dropped.
+fn parse_capture(mut rest: &[u8]) -> Result<Vec<CaptureRecord>> {
+    let mut records = Vec::new();
+    while let Some((record, remaining)) = parse_record(rest)? {
+        records.push(record);
+        rest = remaining;
+    }
+    Ok(records)
+}
+
+fn take<'a>(buf: &mut &'a [u8], len: usize) -> Option<&'a [u8]> {
+    if buf.len() < len {
+        return None;
+    }
+    let (head, tail) = buf.split_at(len);
+    *buf = tail;
+    Some(head)
+}
+
+fn take_u16(buf: &mut &[u8]) -> Option<u16> {
+    take(buf, 2).map(|b| u16::from_le_bytes([b[0], b[1]]))
+}
+
+/// Parses the next record, None at the end of the file.
+fn parse_record(mut buf: &[u8]) -> Result<Option<(CaptureRecord, &[u8])>> {
+    let Some(micros) = take(&mut buf, 8) else {
+        return Ok(None);
+    };
+    let micros = i64::from_le_bytes(micros.try_into()?);
+    let timestamp = DateTime::from_timestamp_micros(micros)
+        .ok_or_else(|| anyhow::anyhow!("Invalid timestamp in capture: {}", micros))?;
+
+    let ip = match take(&mut buf, 1) {
+        None => return Ok(None),
+        Some([4]) => match take(&mut buf, 4) {
+            None => return Ok(None),
+            Some(b) => IpAddr::V4(Ipv4Addr::new(b[0], b[1], b[2], b[3])),
+        },
+        Some([6]) => match take(&mut buf, 16) {
+            None => return Ok(None),
+            Some(b) => IpAddr::V6(Ipv6Addr::from(<[u8; 16]>::try_from(b)?)),
+        },
+        Some(family) => {
+            return Err(anyhow::anyhow!(
+                "Invalid address family in capture: {}",
+                family[0]
+            ))
+        }
+    };
+
+    let (Some(port), Some(universe), Some(length)) =
+        (take_u16(&mut buf), take_u16(&mut buf), take_u16(&mut buf))
+    else {
+        return Ok(None);
+    };
+    let Some(payload) = take(&mut buf, length as usize) else {
+        return Ok(None);
+    };
+
+    let record = CaptureRecord {
+        timestamp,
+        source: SocketAddr::new(ip, port),
+        universe,
+        payload: payload.to_vec(),
+    };
+    Ok(Some((record, buf)))
+}
+
+/// The universe a packet is filed under: the data universe, the sync
+/// address or the discovery universe.
+pub fn packet_universe(packet: &Packet) -> u16 {
+    match &packet.data {
+        PacketData::Data(data) => data.universe,
+        PacketData::Sync(sync) => sync.sync_address,
+        PacketData::Discovery(_) => E131_DISCOVERY_UNIVERSE,
+    }
+}
+
+/// The universe a datagram the parser rejected is filed under: whatever is
+/// where a data packet's universe would be, or 0 if it's too short for one.
+pub fn raw_universe(buf: &[u8]) -> u16 {
+    buf.get(DATA_UNIVERSE_OFFSET..DATA_UNIVERSE_OFFSET + 2)
+        .map_or(0, |b| u16::from_be_bytes([b[0], b[1]]))
+}
+
+/// A new file name in the app's capture directory, named after the current time.
+pub fn new_capture_path() -> Result<PathBuf> {
+    let dirs = directories::ProjectDirs::from("com", "sacn-viewer", "sACN Viewer")
+        .ok_or_else(|| anyhow::anyhow!("No home directory to save captures in"))?;
+    let name = format!(
+        "sacn-{}.{}",
+        chrono::Local::now().format("%Y%m%d-%H%M%S"),
+        CAPTURE_EXTENSION
+    );
+    Ok(dirs.data_dir().join("captures").join(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::e131::fixtures::{data_fixture, SYNC_FIXTURE};
+
+    /// A path in the temp directory no other test uses.
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "sacn-viewer-{}-{}.{}",
+            name,
+            std::process::id(),
+            CAPTURE_EXTENSION
+        ))
+    }
+
+    fn records() -> Vec<CaptureRecord> {
+        let start = DateTime::from_timestamp_micros(1_700_000_000_123_456).unwrap();
+        vec![
+            CaptureRecord {
+                timestamp: start,
+                source: "10.0.0.5:5568".parse().unwrap(),
+                universe: 1,
+                payload: data_fixture(),
+            },
+            CaptureRecord {
+                timestamp: start + chrono::Duration::microseconds(22_727),
+                source: "[fe80::1]:49152".parse().unwrap(),
+                universe: 7,
+                payload: SYNC_FIXTURE.to_vec(),
+            },
+            CaptureRecord {
+                timestamp: start + chrono::Duration::seconds(90),
+                source: "192.168.1.20:5568".parse().unwrap(),
+                universe: 63999,
+                payload: vec![0xaa; 638],
+            },
+        ]
+    }
+
+    fn assert_same(loaded: &[CaptureRecord], written: &[CaptureRecord]) {
+        assert_eq!(loaded.len(), written.len());
+        for (loaded, written) in loaded.iter().zip(written) {
+            assert_eq!(loaded.timestamp, written.timestamp);
+            assert_eq!(loaded.source, written.source);
+            assert_eq!(loaded.universe, written.universe);
+            assert_eq!(loaded.payload, written.payload);
+        }
+    }
+
+    #[test]
+    fn round_trips_records() {
+        let path = temp_path("round-trip");
+        let written = records();
+        let mut writer = CaptureWriter::create(&path).unwrap();
+        for record in &written {
+            writer.write(record).unwrap();
+        }
+        let status = writer.finish().unwrap();
+        assert_eq!(status.packets, 3);
+        assert_eq!(status.bytes, std::fs::metadata(&path).unwrap().len());
+
+        let loaded = load_capture(&path);
+        std::fs::remove_file(&path).unwrap();
+        let loaded = loaded.unwrap();
+        assert_same(&loaded.records, &written);
+        assert!(loaded.rejected.is_empty());
+    }
+
+    #[test]
+    fn drops_a_record_cut_short() {
+        let path = temp_path("cut-short");
+        let written = records();
+        let mut writer = CaptureWriter::create(&path).unwrap();
+        for record in &written {
+            writer.write(record).unwrap();
+        }
+        writer.finish().unwrap();
+
+        let contents = std::fs::read(&path).unwrap();
+        std::fs::write(&path, &contents[..contents.len() - 100]).unwrap();
+        let loaded = load_capture(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_same(&loaded.unwrap().records, &written[..2]);
+    }
+}
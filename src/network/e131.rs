@@ -0,0 +1,911 @@
+use std::borrow::Cow;
+use std::fmt;
+use uuid::Uuid;
+
+// Shared with the compliance checker, which walks the same fields
+pub(crate) const ACN_PACKET_IDENTIFIER: &[u8; 12] = b"ASC-E1.17\0\0\0";
+pub(crate) const VECTOR_ROOT_E131_DATA: u32 = 0x0000_0004;
+pub(crate) const VECTOR_ROOT_E131_EXTENDED: u32 = 0x0000_0008;
+pub(crate) const VECTOR_E131_DATA_PACKET: u32 = 0x0000_0002;
+pub(crate) const VECTOR_E131_EXTENDED_SYNCHRONIZATION: u32 = 0x0000_0001;
+pub(crate) const VECTOR_E131_EXTENDED_DISCOVERY: u32 = 0x0000_0002;
+pub(crate) const VECTOR_UNIVERSE_DISCOVERY_UNIVERSE_LIST: u32 = 0x0000_0001;
+pub(crate) const VECTOR_DMP_SET_PROPERTY: u8 = 0x02;
+pub(crate) const DMP_ADDRESS_AND_DATA_TYPE: u8 = 0xa1;
+
+const PREVIEW_DATA: u8 = 0x80;
+pub(crate) const STREAM_TERMINATED: u8 = 0x40;
+const FORCE_SYNCHRONIZATION: u8 = 0x20;
+
+/// Where each layer's PDU starts, and where its fixed fields end.
+pub(crate) const ROOT_PDU_OFFSET: usize = 16;
+pub(crate) const ROOT_LAYER_END: usize = 38;
+pub(crate) const FRAMING_PDU_OFFSET: usize = 38;
+const DATA_FRAMING_LAYER_END: usize = 115;
+pub(crate) const DMP_PDU_OFFSET: usize = 115;
+pub(crate) const PROPERTY_VALUES_OFFSET: usize = 125;
+pub(crate) const SYNC_PACKET_LEN: usize = 49;
+const DISCOVERY_FRAMING_LAYER_END: usize = 112;
+pub(crate) const DISCOVERY_PDU_OFFSET: usize = 112;
+pub(crate) const UNIVERSE_LIST_OFFSET: usize = 120;
+
+/// Where the fields read by more than one module are.
+pub(crate) const CID_OFFSET: usize = 22;
+pub(crate) const SOURCE_NAME_FIELD: std::ops::Range<usize> = 44..108;
+pub(crate) const DATA_UNIVERSE_OFFSET: usize = 113;
+
+pub(crate) const MAX_PRIORITY: u8 = 200;
+pub(crate) const MAX_UNIVERSE: u16 = 63999;
+pub(crate) const MAX_PROPERTY_VALUES: usize = 513; // start code and 512 slots
+pub(crate) const MAX_DISCOVERY_UNIVERSES: usize = 512;
+
+/// The PDU layers of an E1.31 packet, named in parse errors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layer {
+    Root,
+    Framing,
+    Dmp,
+    UniverseDiscovery,
+}
+
+impl fmt::Display for Layer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Layer::Root => write!(f, "root"),
+            Layer::Framing => write!(f, "framing"),
+            Layer::Dmp => write!(f, "DMP"),
+            Layer::UniverseDiscovery => write!(f, "universe discovery"),
+        }
+    }
+}
+
+/// Why a buffer isn't a valid E1.31 packet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    Truncated {
+        layer: Layer,
+        needed: usize,
+        available: usize,
+    },
+    Preamble(u16),
+    Postamble(u16),
+    PacketIdentifier,
+    Flags {
+        layer: Layer,
+        flags: u8,
+    },
+    Length {
+        layer: Layer,
+        length: usize,
+        available: usize,
+    },
+    RootVector(u32),
+    FramingVector(u32),
+    SourceNameUnterminated,
+    SourceNameEncoding(std::str::Utf8Error),
+    Priority(u8),
+    SyncAddress(u16),
+    Universe(u16),
+    DmpVector(u8),
+    AddressType(u8),
+    FirstAddress(u16),
+    AddressIncrement(u16),
+    PropertyCount {
+        count: u16,
+        available: usize,
+    },
+    DiscoveryVector(u32),
+    Page {
+        page: u8,
+        last_page: u8,
+    },
+    UniverseList(usize),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Truncated {
+                layer,
+                needed,
+                available,
+            } => write!(
+                f,
+                "Packet too short for the {} layer: {} bytes, need {}",
+                layer, available, needed
+            ),
+            ParseError::Preamble(size) => write!(f, "Invalid preamble size {:#06x}", size),
+            ParseError::Postamble(size) => write!(f, "Invalid post-amble size {:#06x}", size),
+            ParseError::PacketIdentifier => write!(f, "Not an ACN packet identifier"),
+            ParseError::Flags { layer, flags } => {
+                write!(f, "Invalid {} layer flags {:#x}", layer, flags)
+            }
+            ParseError::Length {
+                layer,
+                length,
+                available,
+            } => write!(
+                f,
+                "Length of the {} layer is {} but {} bytes follow",
+                layer, length, available
+            ),
+            ParseError::RootVector(vector) => write!(f, "Unknown root vector {:#010x}", vector),
+            ParseError::FramingVector(vector) => {
+                write!(f, "Unknown framing vector {:#010x}", vector)
+            }
+            ParseError::SourceNameUnterminated => write!(f, "Source name is not null-terminated"),
+            ParseError::SourceNameEncoding(e) => write!(f, "Source name is not UTF-8: {}", e),
+            ParseError::Priority(priority) => write!(f, "Priority {} is above 200", priority),
+            ParseError::SyncAddress(address) => {
+                write!(f, "Synchronization address {} is out of range", address)
+            }
+            ParseError::Universe(universe) => write!(f, "Universe {} is outside 1-63999", universe),
+            ParseError::DmpVector(vector) => write!(f, "Unknown DMP vector {:#04x}", vector),
+            ParseError::AddressType(kind) => {
+                write!(f, "Invalid address and data type {:#04x}", kind)
+            }
+            ParseError::FirstAddress(address) => {
+                write!(f, "First property address is {}, not 0", address)
+            }
+            ParseError::AddressIncrement(increment) => {
+                write!(f, "Address increment is {}, not 1", increment)
+            }
+            ParseError::PropertyCount { count, available } => write!(
+                f,
+                "Property value count is {} but {} values follow",
+                count, available
+            ),
+            ParseError::DiscoveryVector(vector) => {
+                write!(f, "Unknown universe discovery vector {:#010x}", vector)
+            }
+            ParseError::Page { page, last_page } => {
+                write!(f, "Page {} is after the last page {}", page, last_page)
+            }
+            ParseError::UniverseList(len) => {
+                write!(f, "Universe list of {} bytes is not a valid list", len)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// An E1.31 packet borrowing its source name, levels and universe list from
+/// the buffer it was parsed from. Only a source name repaired by
+/// [`parse_lenient`] is copied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Packet<'a> {
+    pub cid: Uuid,
+    pub data: PacketData<'a>,
+    pub tolerated: Option<ParseError>, // first field error a lenient parse let through
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PacketData<'a> {
+    Data(DataPacket<'a>),
+    Sync(SyncPacket),
+    Discovery(DiscoveryPacket<'a>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataPacket<'a> {
+    pub source_name: Cow<'a, str>,
+    pub raw_source_name: &'a [u8], // all 64 bytes, padding included
+    pub priority: u8,
+    pub sync_address: u16,
+    pub sequence: u8,
+    pub options: u8,
+    pub universe: u16,
+    pub start_code: u8,
+    pub slots: &'a [u8], // after the start code
+}
+
+impl DataPacket<'_> {
+    pub fn preview_data(&self) -> bool {
+        self.options & PREVIEW_DATA != 0
+    }
+
+    pub fn stream_terminated(&self) -> bool {
+        self.options & STREAM_TERMINATED != 0
+    }
+
+    pub fn force_synchronization(&self) -> bool {
+        self.options & FORCE_SYNCHRONIZATION != 0
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SyncPacket {
+    pub sequence: u8,
+    pub sync_address: u16,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiscoveryPacket<'a> {
+    pub source_name: Cow<'a, str>,
+    pub raw_source_name: &'a [u8],
+    pub page: u8,
+    pub last_page: u8,
+    universe_list: &'a [u8], // big-endian u16s
+}
+
+impl DiscoveryPacket<'_> {
+    /// The universes listed on this page.
+    pub fn universes(&self) -> impl Iterator<Item = u16> + '_ {
+        self.universe_list
+            .chunks_exact(2)
+            .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+    }
+}
+
+pub(crate) fn u16_at(buf: &[u8], offset: usize) -> u16 {
+    u16::from_be_bytes([buf[offset], buf[offset + 1]])
+}
+
+pub(crate) fn u32_at(buf: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes([
+        buf[offset],
+        buf[offset + 1],
+        buf[offset + 2],
+        buf[offset + 3],
+    ])
+}
+
+fn need(buf: &[u8], needed: usize, layer: Layer) -> Result<(), ParseError> {
+    if buf.len() < needed {
+        return Err(ParseError::Truncated {
+            layer,
+            needed,
+            available: buf.len(),
+        });
+    }
+    Ok(())
+}
+
+/// Checks the flags and length of a PDU. Each E1.31 packet holds a single
+/// PDU per layer, so it must run to the end of the buffer.
+fn check_flags_and_length(buf: &[u8], offset: usize, layer: Layer) -> Result<(), ParseError> {
+    let value = u16_at(buf, offset);
+    let flags = (value >> 12) as u8;
+    if flags != 0x7 {
+        return Err(ParseError::Flags { layer, flags });
+    }
+    let length = (value & 0x0fff) as usize;
+    let available = buf.len() - offset;
+    if length != available {
+        return Err(ParseError::Length {
+            layer,
+            length,
+            available,
+        });
+    }
+    Ok(())
+}
+
+/// Whether field errors fail the parse, and the first one let through.
+struct Strictness {
+    lenient: bool,
+    tolerated: Option<ParseError>,
+}
+
+impl Strictness {
+    /// Fails a strict parse, a lenient one carries on and remembers the error.
+    fn tolerate(&mut self, error: ParseError) -> Result<(), ParseError> {
+        if !self.lenient {
+            return Err(error);
+        }
+        self.tolerated.get_or_insert(error);
+        Ok(())
+    }
+}
+
+/// Splits the 64 byte source name field at its terminating null. A lenient
+/// parse takes a name without one as running to the end of the field and
+/// replaces invalid UTF-8.
+fn source_name<'a>(
+    field: &'a [u8],
+    strictness: &mut Strictness,
+) -> Result<Cow<'a, str>, ParseError> {
+    let end = match field.iter().position(|&b| b == 0) {
+        Some(end) => end,
+        None => {
+            strictness.tolerate(ParseError::SourceNameUnterminated)?;
+            field.len()
+        }
+    };
+    match std::str::from_utf8(&field[..end]) {
+        Ok(name) => Ok(Cow::Borrowed(name)),
+        Err(e) => {
+            strictness.tolerate(ParseError::SourceNameEncoding(e))?;
+            Ok(String::from_utf8_lossy(&field[..end]))
+        }
+    }
+}
+
+/// Parses a UDP payload from the sACN port as an E1.31 data, sync or
+/// universe discovery packet.
+pub fn parse(buf: &[u8]) -> Result<Packet<'_>, ParseError> {
+    parse_packet(buf, false)
+}
+
+/// Parses a packet for display, accepting the field errors senders commonly
+/// make: a source name that isn't null-terminated or valid UTF-8, and a
+/// priority above 200, which is taken as 200. Packets whose structure is
+/// broken are still rejected. The first error let through is kept in
+/// [`Packet::tolerated`], and the compliance checker reports all of them.
+pub fn parse_lenient(buf: &[u8]) -> Result<Packet<'_>, ParseError> {
+    parse_packet(buf, true)
+}
+
+fn parse_packet(buf: &[u8], lenient: bool) -> Result<Packet<'_>, ParseError> {
+    let mut strictness = Strictness {
+        lenient,
+        tolerated: None,
+    };
+    need(buf, ROOT_LAYER_END, Layer::Root)?;
+
+    let preamble = u16_at(buf, 0);
+    if preamble != 0x0010 {
+        return Err(ParseError::Preamble(preamble));
+    }
+    let postamble = u16_at(buf, 2);
+    if postamble != 0x0000 {
+        return Err(ParseError::Postamble(postamble));
+    }
+    if &buf[4..16] != ACN_PACKET_IDENTIFIER {
+        return Err(ParseError::PacketIdentifier);
+    }
+    check_flags_and_length(buf, ROOT_PDU_OFFSET, Layer::Root)?;
+
+    let mut cid = [0u8; 16];
+    cid.copy_from_slice(&buf[CID_OFFSET..ROOT_LAYER_END]);
+    let cid = Uuid::from_uuid_bytes(cid);
+
+    let data = match u32_at(buf, 18) {
+        VECTOR_ROOT_E131_DATA => PacketData::Data(parse_data(buf, &mut strictness)?),
+        VECTOR_ROOT_E131_EXTENDED => {
+            need(buf, FRAMING_PDU_OFFSET + 6, Layer::Framing)?;
+            match u32_at(buf, 40) {
+                VECTOR_E131_EXTENDED_SYNCHRONIZATION => PacketData::Sync(parse_sync(buf)?),
+                VECTOR_E131_EXTENDED_DISCOVERY => {
+                    PacketData::Discovery(parse_discovery(buf, &mut strictness)?)
+                }
+                vector => return Err(ParseError::FramingVector(vector)),
+            }
+        }
+        vector => return Err(ParseError::RootVector(vector)),
+    };
+    Ok(Packet {
+        cid,
+        data,
+        tolerated: strictness.tolerated,
+    })
+}
+
+fn parse_data<'a>(
+    buf: &'a [u8],
+    strictness: &mut Strictness,
+) -> Result<DataPacket<'a>, ParseError> {
+    need(buf, DATA_FRAMING_LAYER_END, Layer::Framing)?;
+    check_flags_and_length(buf, FRAMING_PDU_OFFSET, Layer::Framing)?;
+    let vector = u32_at(buf, 40);
+    if vector != VECTOR_E131_DATA_PACKET {
+        return Err(ParseError::FramingVector(vector));
+    }
+
+    let raw_source_name = &buf[SOURCE_NAME_FIELD];
+    let source_name = source_name(raw_source_name, strictness)?;
+    let mut priority = buf[108];
+    if priority > MAX_PRIORITY {
+        strictness.tolerate(ParseError::Priority(priority))?;
+        priority = MAX_PRIORITY;
+    }
+    let sync_address = u16_at(buf, 109);
+    if sync_address > MAX_UNIVERSE {
+        return Err(ParseError::SyncAddress(sync_address));
+    }
+    let universe = u16_at(buf, DATA_UNIVERSE_OFFSET);
+    if !(1..=MAX_UNIVERSE).contains(&universe) {
+        return Err(ParseError::Universe(universe));
+    }
+
+    need(buf, PROPERTY_VALUES_OFFSET, Layer::Dmp)?;
+    check_flags_and_length(buf, DMP_PDU_OFFSET, Layer::Dmp)?;
+    if buf[117] != VECTOR_DMP_SET_PROPERTY {
+        return Err(ParseError::DmpVector(buf[117]));
+    }
+    if buf[118] != DMP_ADDRESS_AND_DATA_TYPE {
+        return Err(ParseError::AddressType(buf[118]));
+    }
+    let first_address = u16_at(buf, 119);
+    if first_address != 0 {
+        return Err(ParseError::FirstAddress(first_address));
+    }
+    let increment = u16_at(buf, 121);
+    if increment != 1 {
+        return Err(ParseError::AddressIncrement(increment));
+    }
+    let count = u16_at(buf, 123);
+    let values = &buf[PROPERTY_VALUES_OFFSET..];
+    if count as usize != values.len() || !(1..=MAX_PROPERTY_VALUES).contains(&values.len()) {
+        return Err(ParseError::PropertyCount {
+            count,
+            available: values.len(),
+        });
+    }
+
+    Ok(DataPacket {
+        source_name,
+        raw_source_name,
+        priority,
+        sync_address,
+        sequence: buf[111],
+        options: buf[112],
+        universe,
+        start_code: values[0],
+        slots: &values[1..],
+    })
+}
+
+fn parse_sync(buf: &[u8]) -> Result<SyncPacket, ParseError> {
+    need(buf, SYNC_PACKET_LEN, Layer::Framing)?;
+    check_flags_and_length(buf, FRAMING_PDU_OFFSET, Layer::Framing)?;
+
+    let sync_address = u16_at(buf, 45);
+    if !(1..=MAX_UNIVERSE).contains(&sync_address) {
+        return Err(ParseError::SyncAddress(sync_address));
+    }
+    Ok(SyncPacket {
+        sequence: buf[44],
+        sync_address,
+    })
+}
+
+fn parse_discovery<'a>(
+    buf: &'a [u8],
+    strictness: &mut Strictness,
+) -> Result<DiscoveryPacket<'a>, ParseError> {
+    need(buf, DISCOVERY_FRAMING_LAYER_END, Layer::Framing)?;
+    check_flags_and_length(buf, FRAMING_PDU_OFFSET, Layer::Framing)?;
+    let raw_source_name = &buf[SOURCE_NAME_FIELD];
+    let source_name = source_name(raw_source_name, strictness)?;
+
+    need(buf, UNIVERSE_LIST_OFFSET, Layer::UniverseDiscovery)?;
+    check_flags_and_length(buf, DISCOVERY_PDU_OFFSET, Layer::UniverseDiscovery)?;
+    let vector = u32_at(buf, 114);
+    if vector != VECTOR_UNIVERSE_DISCOVERY_UNIVERSE_LIST {
+        return Err(ParseError::DiscoveryVector(vector));
+    }
+    let (page, last_page) = (buf[118], buf[119]);
+    if page > last_page {
+        return Err(ParseError::Page { page, last_page });
+    }
+    let universe_list = &buf[UNIVERSE_LIST_OFFSET..];
+    if !universe_list.len().is_multiple_of(2) || universe_list.len() / 2 > MAX_DISCOVERY_UNIVERSES {
+        return Err(ParseError::UniverseList(universe_list.len()));
+    }
+
+    Ok(DiscoveryPacket {
+        source_name,
+        raw_source_name,
+        page,
+        last_page,
+        universe_list,
+    })
+}
+
+/// Byte fixtures of well-formed packets, shared with the tests of the modules
+/// that handle received packets.
+#[cfg(test)]
+pub mod fixtures {
+    pub const CID: [u8; 16] = [
+        0x5a, 0x1c, 0x3e, 0x70, 0x8b, 0x24, 0x4d, 0x11, 0x9f, 0x02, 0xc6, 0x13, 0x88, 0x41, 0xe0,
+        0x7d,
+    ];
+
+    /// Pads a source name to its 64 byte field.
+    pub fn name_field(name: &str) -> Vec<u8> {
+        let mut field = name.as_bytes().to_vec();
+        field.resize(64, 0);
+        field
+    }
+
+    /// A data packet on universe 1 with a null start code and 4 slots.
+    pub fn data_fixture() -> Vec<u8> {
+        [
+            &[0x00, 0x10, 0x00, 0x00][..],
+            b"ASC-E1.17\0\0\0",
+            &[0x70, 0x72, 0x00, 0x00, 0x00, 0x04],
+            &CID,
+            &[0x70, 0x5c, 0x00, 0x00, 0x00, 0x02],
+            &name_field("Fixture"),
+            &[0x64, 0x00, 0x00, 0x2a, 0x00, 0x00, 0x01],
+            &[0x70, 0x0f, 0x02, 0xa1, 0x00, 0x00, 0x00, 0x01, 0x00, 0x05],
+            &[0x00, 0xff, 0x80, 0x40, 0x00],
+        ]
+        .concat()
+    }
+
+    /// A sync packet for sync address 7.
+    pub const SYNC_FIXTURE: [u8; 49] = [
+        0x00, 0x10, 0x00, 0x00, b'A', b'S', b'C', b'-', b'E', b'1', b'.', b'1', b'7', 0x00, 0x00,
+        0x00, 0x70, 0x21, 0x00, 0x00, 0x00, 0x08, 0x5a, 0x1c, 0x3e, 0x70, 0x8b, 0x24, 0x4d, 0x11,
+        0x9f, 0x02, 0xc6, 0x13, 0x88, 0x41, 0xe0, 0x7d, 0x70, 0x0b, 0x00, 0x00, 0x00, 0x01, 0x07,
+        0x00, 0x07, 0x00, 0x00,
+    ];
+
+    /// A discovery packet listing universes 1, 2 and 5 on page 0 of 0.
+    pub fn discovery_fixture() -> Vec<u8> {
+        [
+            &[0x00, 0x10, 0x00, 0x00][..],
+            b"ASC-E1.17\0\0\0",
+            &[0x70, 0x6e, 0x00, 0x00, 0x00, 0x08],
+            &CID,
+            &[0x70, 0x58, 0x00, 0x00, 0x00, 0x02],
+            &name_field("Discovery"),
+            &[0x00, 0x00, 0x00, 0x00],
+            &[0x70, 0x0e, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00],
+            &[0x00, 0x01, 0x00, 0x02, 0x00, 0x05],
+        ]
+        .concat()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fixtures::*;
+    use super::*;
+
+    fn data_packet(buf: &[u8]) -> DataPacket<'_> {
+        match parse(buf).unwrap().data {
+            PacketData::Data(data) => data,
+            other => panic!("Expected a data packet, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parses_data_packet() {
+        let buf = data_fixture();
+        let packet = parse(&buf).unwrap();
+        assert_eq!(packet.cid, Uuid::from_uuid_bytes(CID));
+
+        let data = data_packet(&buf);
+        assert_eq!(data.source_name, "Fixture");
+        assert_eq!(data.raw_source_name.len(), 64);
+        assert_eq!(data.priority, 100);
+        assert_eq!(data.sync_address, 0);
+        assert_eq!(data.sequence, 0x2a);
+        assert_eq!(data.universe, 1);
+        assert_eq!(data.start_code, 0x00);
+        assert_eq!(data.slots, &[0xff, 0x80, 0x40, 0x00]);
+        assert!(!data.preview_data());
+        assert!(!data.stream_terminated());
+        assert!(!data.force_synchronization());
+    }
+
+    #[test]
+    fn data_packet_borrows_from_buffer() {
+        let buf = data_fixture();
+        let data = data_packet(&buf);
+        assert_eq!(data.slots.as_ptr(), buf[126..].as_ptr());
+        assert_eq!(data.source_name.as_ptr(), buf[44..].as_ptr());
+    }
+
+    #[test]
+    fn parses_option_flags() {
+        let mut buf = data_fixture();
+        buf[112] = 0xe0;
+        let data = data_packet(&buf);
+        assert!(data.preview_data());
+        assert!(data.stream_terminated());
+        assert!(data.force_synchronization());
+    }
+
+    #[test]
+    fn parses_sync_packet() {
+        let packet = parse(&SYNC_FIXTURE).unwrap();
+        assert_eq!(packet.cid, Uuid::from_uuid_bytes(CID));
+        assert_eq!(
+            packet.data,
+            PacketData::Sync(SyncPacket {
+                sequence: 7,
+                sync_address: 7,
+            })
+        );
+    }
+
+    #[test]
+    fn parses_discovery_packet() {
+        let buf = discovery_fixture();
+        let PacketData::Discovery(discovery) = parse(&buf).unwrap().data else {
+            panic!("Expected a discovery packet");
+        };
+        assert_eq!(discovery.source_name, "Discovery");
+        assert_eq!(discovery.page, 0);
+        assert_eq!(discovery.last_page, 0);
+        assert_eq!(discovery.universes().collect::<Vec<_>>(), vec![1, 2, 5]);
+    }
+
+    #[test]
+    fn rejects_truncated_packets() {
+        let buf = data_fixture();
+        assert_eq!(
+            parse(&buf[..20]),
+            Err(ParseError::Truncated {
+                layer: Layer::Root,
+                needed: 38,
+                available: 20,
+            })
+        );
+        assert!(matches!(
+            parse(&buf[..100]),
+            Err(ParseError::Length {
+                layer: Layer::Root,
+                ..
+            })
+        ));
+
+        let mut buf = SYNC_FIXTURE[..40].to_vec();
+        buf[17] = 24;
+        assert_eq!(
+            parse(&buf),
+            Err(ParseError::Truncated {
+                layer: Layer::Framing,
+                needed: 44,
+                available: 40,
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_bad_root_layer() {
+        let mut buf = data_fixture();
+        buf[1] = 0x20;
+        assert_eq!(parse(&buf), Err(ParseError::Preamble(0x0020)));
+
+        let mut buf = data_fixture();
+        buf[3] = 0x01;
+        assert_eq!(parse(&buf), Err(ParseError::Postamble(0x0001)));
+
+        let mut buf = data_fixture();
+        buf[4] = b'X';
+        assert_eq!(parse(&buf), Err(ParseError::PacketIdentifier));
+
+        let mut buf = data_fixture();
+        buf[16] = 0x30;
+        assert_eq!(
+            parse(&buf),
+            Err(ParseError::Flags {
+                layer: Layer::Root,
+                flags: 0x3,
+            })
+        );
+
+        let mut buf = data_fixture();
+        buf[21] = 0x05;
+        assert_eq!(parse(&buf), Err(ParseError::RootVector(5)));
+    }
+
+    #[test]
+    fn rejects_length_mismatch() {
+        let mut buf = data_fixture();
+        buf.push(0);
+        assert_eq!(
+            parse(&buf),
+            Err(ParseError::Length {
+                layer: Layer::Root,
+                length: 114,
+                available: 115,
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_bad_source_names() {
+        let mut buf = data_fixture();
+        buf[44..108].fill(b'a');
+        assert_eq!(parse(&buf), Err(ParseError::SourceNameUnterminated));
+
+        let mut buf = data_fixture();
+        buf[44] = 0xff;
+        assert!(matches!(
+            parse(&buf),
+            Err(ParseError::SourceNameEncoding(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_out_of_range_fields() {
+        let mut buf = data_fixture();
+        buf[108] = 201;
+        assert_eq!(parse(&buf), Err(ParseError::Priority(201)));
+
+        let mut buf = data_fixture();
+        buf[109..111].copy_from_slice(&64000u16.to_be_bytes());
+        assert_eq!(parse(&buf), Err(ParseError::SyncAddress(64000)));
+
+        let mut buf = data_fixture();
+        buf[113..115].copy_from_slice(&[0, 0]);
+        assert_eq!(parse(&buf), Err(ParseError::Universe(0)));
+
+        let mut buf = SYNC_FIXTURE;
+        buf[45..47].copy_from_slice(&[0, 0]);
+        assert_eq!(parse(&buf), Err(ParseError::SyncAddress(0)));
+    }
+
+    #[test]
+    fn lenient_parse_repairs_source_names() {
+        let mut buf = data_fixture();
+        buf[44..108].fill(b'a');
+        let PacketData::Data(data) = parse_lenient(&buf).unwrap().data else {
+            panic!("Expected a data packet");
+        };
+        assert_eq!(data.source_name, "a".repeat(64), "unterminated");
+
+        let mut buf = data_fixture();
+        buf[44] = 0xff;
+        let PacketData::Data(data) = parse_lenient(&buf).unwrap().data else {
+            panic!("Expected a data packet");
+        };
+        assert_eq!(data.source_name, "\u{fffd}ixture", "invalid UTF-8");
+
+        let mut buf = discovery_fixture();
+        buf[44..108].fill(b'd');
+        let PacketData::Discovery(discovery) = parse_lenient(&buf).unwrap().data else {
+            panic!("Expected a discovery packet");
+        };
+        assert_eq!(discovery.source_name, "d".repeat(64));
+    }
+
+    #[test]
+    fn lenient_parse_clamps_priority() {
+        let mut buf = data_fixture();
+        buf[108] = 255;
+        let PacketData::Data(data) = parse_lenient(&buf).unwrap().data else {
+            panic!("Expected a data packet");
+        };
+        assert_eq!(data.priority, 200);
+        assert_eq!(data.slots, &[0xff, 0x80, 0x40, 0x00]);
+    }
+
+    #[test]
+    fn lenient_parse_keeps_the_first_error() {
+        let mut buf = data_fixture();
+        buf[44..108].fill(b'a');
+        buf[108] = 255;
+        let packet = parse_lenient(&buf).unwrap();
+        assert_eq!(packet.tolerated, parse(&buf).err());
+
+        let buf = data_fixture();
+        assert_eq!(parse_lenient(&buf).unwrap().tolerated, None);
+    }
+
+    #[test]
+    fn lenient_parse_matches_strict_parse_otherwise() {
+        let buf = data_fixture();
+        assert_eq!(parse_lenient(&buf), parse(&buf));
+        assert!(matches!(
+            parse_lenient(&buf).unwrap().data,
+            PacketData::Data(DataPacket {
+                source_name: Cow::Borrowed("Fixture"),
+                ..
+            })
+        ));
+
+        let mut buf = data_fixture();
+        buf[1] = 0x20;
+        assert_eq!(parse_lenient(&buf), Err(ParseError::Preamble(0x0020)));
+
+        let mut buf = data_fixture();
+        buf[113..115].copy_from_slice(&[0, 0]);
+        assert_eq!(parse_lenient(&buf), Err(ParseError::Universe(0)));
+    }
+
+    #[test]
+    fn rejects_bad_dmp_layer() {
+        let mut buf = data_fixture();
+        buf[117] = 0x01;
+        assert_eq!(parse(&buf), Err(ParseError::DmpVector(0x01)));
+
+        let mut buf = data_fixture();
+        buf[118] = 0xa2;
+        assert_eq!(parse(&buf), Err(ParseError::AddressType(0xa2)));
+
+        let mut buf = data_fixture();
+        buf[120] = 0x01;
+        assert_eq!(parse(&buf), Err(ParseError::FirstAddress(1)));
+
+        let mut buf = data_fixture();
+        buf[122] = 0x02;
+        assert_eq!(parse(&buf), Err(ParseError::AddressIncrement(2)));
+
+        let mut buf = data_fixture();
+        buf[124] = 0x06;
+        assert_eq!(
+            parse(&buf),
+            Err(ParseError::PropertyCount {
+                count: 6,
+                available: 5,
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_bad_discovery_layer() {
+        let mut buf = discovery_fixture();
+        buf[117] = 0x02;
+        assert_eq!(parse(&buf), Err(ParseError::DiscoveryVector(2)));
+
+        let mut buf = discovery_fixture();
+        buf[118] = 1;
+        assert_eq!(
+            parse(&buf),
+            Err(ParseError::Page {
+                page: 1,
+                last_page: 0,
+            })
+        );
+
+        // Drop the last byte and shorten every layer to match
+        let mut buf = discovery_fixture();
+        buf.pop();
+        for offset in [16, 38, 112] {
+            buf[offset + 1] -= 1;
+        }
+        assert_eq!(parse(&buf), Err(ParseError::UniverseList(5)));
+    }
+
+    #[test]
+    fn rejects_unknown_framing_vector() {
+        let mut buf = SYNC_FIXTURE;
+        buf[43] = 0x03;
+        assert_eq!(parse(&buf), Err(ParseError::FramingVector(3)));
+
+        let mut buf = data_fixture();
+        buf[43] = 0x01;
+        assert_eq!(parse(&buf), Err(ParseError::FramingVector(1)));
+    }
+
+    #[test]
+    fn parses_packets_packed_by_sacn_crate() {
+        use sacn::packet::{
+            AcnRootLayerProtocol, DataPacketDmpLayer, DataPacketFramingLayer, E131RootLayer,
+            E131RootLayerData,
+        };
+        use std::borrow::Cow;
+
+        let mut values = vec![0u8; 513];
+        values[1] = 255;
+        values[512] = 1;
+        let buf = AcnRootLayerProtocol {
+            pdu: E131RootLayer {
+                cid: Uuid::from_uuid_bytes(CID),
+                data: E131RootLayerData::DataPacket(DataPacketFramingLayer {
+                    source_name: Cow::from("sACN Viewer"),
+                    priority: 150,
+                    synchronization_address: 9,
+                    sequence_number: 200,
+                    preview_data: true,
+                    stream_terminated: false,
+                    force_synchronization: false,
+                    universe: 63999,
+                    data: DataPacketDmpLayer {
+                        property_values: Cow::from(values),
+                    },
+                }),
+            },
+        }
+        .pack_alloc()
+        .unwrap();
+
+        let data = data_packet(&buf);
+        assert_eq!(data.source_name, "sACN Viewer");
+        assert_eq!(data.priority, 150);
+        assert_eq!(data.sync_address, 9);
+        assert_eq!(data.sequence, 200);
+        assert!(data.preview_data());
+        assert_eq!(data.universe, 63999);
+        assert_eq!(data.slots.len(), 512);
+        assert_eq!((data.slots[0], data.slots[511]), (255, 1));
+    }
+}
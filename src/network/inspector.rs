@@ -0,0 +1,359 @@
+use super::e131::{self, Packet, PacketData, ParseError};
+use chrono::{DateTime, Utc};
+use std::collections::VecDeque;
+use std::net::SocketAddr;
+use std::ops::Range;
+use uuid::Uuid;
+
+/// How many of the most recent packets the inspector keeps.
+const INSPECTOR_CAPACITY: usize = 500;
+
+/// A raw packet kept for the inspector.
+#[derive(Debug, Clone)]
+pub struct InspectedPacket {
+    pub id: u64,
+    pub timestamp: DateTime<Utc>,
+    pub source: SocketAddr,
+    pub payload: Vec<u8>,
+    pub summary: String,
+    pub malformed: bool,
+}
+
+/// The most recent packets received on the sACN port, malformed ones
+/// included, newest last.
+#[derive(Debug)]
+pub struct PacketInspector {
+    packets: VecDeque<InspectedPacket>,
+    next_id: u64,
+    pub paused: bool, // stop taking packets so the list holds still
+}
+
+impl PacketInspector {
+    pub fn new() -> Self {
+        Self {
+            packets: VecDeque::with_capacity(INSPECTOR_CAPACITY),
+            next_id: 0,
+            paused: false,
+        }
+    }
+
+    /// Keeps a packet with the result of its lenient parse. Field errors the
+    /// parse let through still mark it as malformed.
+    pub fn push(
+        &mut self,
+        source: SocketAddr,
+        payload: &[u8],
+        parsed: &Result<Packet<'_>, ParseError>,
+    ) {
+        if self.paused {
+            return;
+        }
+        if self.packets.len() == INSPECTOR_CAPACITY {
+            self.packets.pop_front();
+        }
+        self.packets.push_back(InspectedPacket {
+            id: self.next_id,
+            timestamp: Utc::now(),
+            source,
+            payload: payload.to_vec(),
+            summary: summarize(parsed),
+            malformed: parse_error(parsed).is_some(),
+        });
+        self.next_id += 1;
+    }
+
+    pub fn packets(&self) -> &VecDeque<InspectedPacket> {
+        &self.packets
+    }
+
+    pub fn get(&self, id: u64) -> Option<&InspectedPacket> {
+        self.packets.iter().find(|packet| packet.id == id)
+    }
+
+    pub fn clear(&mut self) {
+        self.packets.clear();
+    }
+}
+
+/// What a strict parse would reject the packet for.
+fn parse_error<'a>(parsed: &'a Result<Packet<'_>, ParseError>) -> Option<&'a ParseError> {
+    match parsed {
+        Ok(packet) => packet.tolerated.as_ref(),
+        Err(e) => Some(e),
+    }
+}
+
+/// A one line description of a packet for the packet list.
+fn summarize(parsed: &Result<Packet<'_>, ParseError>) -> String {
+    match parsed {
+        Ok(Packet {
+            data,
+            tolerated: None,
+            ..
+        }) => match data {
+            PacketData::Data(data) => format!(
+                "Data U{} \"{}\" start code {:#04X}, {} slots, seq {}",
+                data.universe,
+                data.source_name,
+                data.start_code,
+                data.slots.len(),
+                data.sequence
+            ),
+            PacketData::Sync(sync) => {
+                format!("Sync U{}, seq {}", sync.sync_address, sync.sequence)
+            }
+            PacketData::Discovery(discovery) => format!(
+                "Discovery \"{}\" page {}/{}, {} universes",
+                discovery.source_name,
+                discovery.page,
+                discovery.last_page,
+                discovery.universes().count()
+            ),
+        },
+        Ok(Packet {
+            tolerated: Some(e), ..
+        })
+        | Err(e) => format!("Malformed: {}", e),
+    }
+}
+
+/// One decoded field and the bytes it came from.
+#[derive(Debug, Clone)]
+pub struct Field {
+    pub name: &'static str,
+    pub range: Range<usize>,
+    pub value: String,
+}
+
+/// One PDU layer of a dissected packet.
+#[derive(Debug, Clone)]
+pub struct PduNode {
+    pub name: &'static str,
+    pub range: Range<usize>,
+    pub fields: Vec<Field>,
+}
+
+/// A packet broken down field by field, as far as its bytes allow.
+#[derive(Debug, Clone)]
+pub struct Dissection {
+    pub layers: Vec<PduNode>,
+    pub error: Option<String>, // what makes the packet malformed
+}
+
+/// Builds the fields of one layer, stopping at the end of the packet.
+struct LayerBuilder<'a> {
+    buf: &'a [u8],
+    node: PduNode,
+}
+
+impl<'a> LayerBuilder<'a> {
+    fn new(buf: &'a [u8], name: &'static str, range: Range<usize>) -> Self {
+        let range = range.start..range.end.min(buf.len());
+        Self {
+            buf,
+            node: PduNode {
+                name,
+                range,
+                fields: Vec::new(),
+            },
+        }
+    }
+
+    fn field(
+        &mut self,
+        name: &'static str,
+        range: Range<usize>,
+        describe: impl FnOnce(&[u8]) -> String,
+    ) -> &mut Self {
+        if let Some(bytes) = self.buf.get(range.clone()) {
+            self.node.fields.push(Field {
+                name,
+                range,
+                value: describe(bytes),
+            });
+        }
+        self
+    }
+
+    fn u8(&mut self, name: &'static str, offset: usize) -> &mut Self {
+        self.field(name, offset..offset + 1, |b| b[0].to_string())
+    }
+
+    fn u16(&mut self, name: &'static str, offset: usize) -> &mut Self {
+        self.field(name, offset..offset + 2, |b| be_u16(b).to_string())
+    }
+
+    fn hex(&mut self, name: &'static str, range: Range<usize>) -> &mut Self {
+        self.field(name, range, |b| {
+            let hex: String = b.iter().map(|byte| format!("{:02X}", byte)).collect();
+            format!("0x{}", hex)
+        })
+    }
+
+    fn flags_and_length(&mut self, offset: usize) -> &mut Self {
+        self.field("Flags & Length", offset..offset + 2, |b| {
+            let value = be_u16(b);
+            format!("flags {:#x}, length {}", value >> 12, value & 0x0fff)
+        })
+    }
+
+    fn vector(&mut self, range: Range<usize>, name: impl FnOnce(u32) -> &'static str) -> &mut Self {
+        self.field("Vector", range, |b| {
+            let vector = b.iter().fold(0u32, |acc, &byte| (acc << 8) | byte as u32);
+            let width = 2 + b.len() * 2;
+            format!("{:#0width$x} ({})", vector, name(vector), width = width)
+        })
+    }
+
+    fn source_name(&mut self, offset: usize) -> &mut Self {
+        self.field("Source Name", offset..offset + 64, |b| {
+            let end = b.iter().position(|&byte| byte == 0).unwrap_or(b.len());
+            format!("\"{}\"", String::from_utf8_lossy(&b[..end]))
+        })
+    }
+
+    /// The layer, unless the packet ends before any of its fields.
+    fn finish(self) -> Option<PduNode> {
+        (!self.node.fields.is_empty()).then_some(self.node)
+    }
+}
+
+fn be_u16(bytes: &[u8]) -> u16 {
+    u16::from_be_bytes([bytes[0], bytes[1]])
+}
+
+fn be_u32(bytes: &[u8]) -> Option<u32> {
+    Some(u32::from_be_bytes(bytes.get(..4)?.try_into().ok()?))
+}
+
+/// Breaks a packet down into its root, framing and DMP or universe
+/// discovery layers, like a Wireshark dissector.
+pub fn dissect(buf: &[u8]) -> Dissection {
+    let mut layers = Vec::new();
+
+    let mut root = LayerBuilder::new(buf, "Root Layer", 0..38);
+    root.hex("Preamble Size", 0..2)
+        .hex("Post-amble Size", 2..4)
+        .field("ACN Packet Identifier", 4..16, |b| {
+            format!("\"{}\"", String::from_utf8_lossy(b).trim_end_matches('\0'))
+        })
+        .flags_and_length(16)
+        .vector(18..22, |vector| match vector {
+            0x04 => "VECTOR_ROOT_E131_DATA",
+            0x08 => "VECTOR_ROOT_E131_EXTENDED",
+            _ => "unknown",
+        })
+        .field("CID", 22..38, |b| {
+            Uuid::from_bytes(b).map_or_else(|e| e.to_string(), |cid| cid.to_string())
+        });
+    layers.extend(root.finish());
+
+    let root_vector = buf.get(18..22).and_then(be_u32);
+    let framing_vector = buf.get(40..44).and_then(be_u32);
+    match (root_vector, framing_vector) {
+        (Some(0x04), _) => {
+            let mut framing = LayerBuilder::new(buf, "Framing Layer", 38..115);
+            framing
+                .flags_and_length(38)
+                .vector(40..44, |vector| match vector {
+                    0x02 => "VECTOR_E131_DATA_PACKET",
+                    _ => "unknown",
+                })
+                .source_name(44)
+                .u8("Priority", 108)
+                .u16("Synchronization Address", 109)
+                .u8("Sequence Number", 111)
+                .field("Options", 112..113, |b| {
+                    let mut flags = Vec::new();
+                    if b[0] & 0x80 != 0 {
+                        flags.push("Preview_Data");
+                    }
+                    if b[0] & 0x40 != 0 {
+                        flags.push("Stream_Terminated");
+                    }
+                    if b[0] & 0x20 != 0 {
+                        flags.push("Force_Synchronization");
+                    }
+                    if flags.is_empty() {
+                        format!("{:#04x}", b[0])
+                    } else {
+                        format!("{:#04x} ({})", b[0], flags.join(", "))
+                    }
+                })
+                .u16("Universe", 113);
+            layers.extend(framing.finish());
+
+            let mut dmp = LayerBuilder::new(buf, "DMP Layer", 115..buf.len());
+            dmp.flags_and_length(115)
+                .vector(117..118, |vector| match vector {
+                    0x02 => "VECTOR_DMP_SET_PROPERTY",
+                    _ => "unknown",
+                })
+                .hex("Address Type & Data Type", 118..119)
+                .u16("First Property Address", 119)
+                .u16("Address Increment", 121)
+                .u16("Property Value Count", 123)
+                .field("START Code", 125..126, |b| match b[0] {
+                    0x00 => "0x00 (DMX levels)".to_string(),
+                    0xdd => "0xDD (per-address priority)".to_string(),
+                    code => format!("{:#04X}", code),
+                });
+            if buf.len() > 126 {
+                dmp.field("Property Values", 126..buf.len(), |b| {
+                    let active = b.iter().filter(|&&value| value > 0).count();
+                    format!("{} slots, {} above 0", b.len(), active)
+                });
+            }
+            layers.extend(dmp.finish());
+        }
+        (Some(0x08), Some(0x01)) => {
+            let mut framing = LayerBuilder::new(buf, "Framing Layer", 38..49);
+            framing
+                .flags_and_length(38)
+                .vector(40..44, |_| "VECTOR_E131_EXTENDED_SYNCHRONIZATION")
+                .u8("Sequence Number", 44)
+                .u16("Synchronization Address", 45)
+                .hex("Reserved", 47..49);
+            layers.extend(framing.finish());
+        }
+        (Some(0x08), Some(0x02)) => {
+            let mut framing = LayerBuilder::new(buf, "Framing Layer", 38..112);
+            framing
+                .flags_and_length(38)
+                .vector(40..44, |_| "VECTOR_E131_EXTENDED_DISCOVERY")
+                .source_name(44)
+                .hex("Reserved", 108..112);
+            layers.extend(framing.finish());
+
+            let mut discovery = LayerBuilder::new(buf, "Universe Discovery Layer", 112..buf.len());
+            discovery
+                .flags_and_length(112)
+                .vector(114..118, |vector| match vector {
+                    0x01 => "VECTOR_UNIVERSE_DISCOVERY_UNIVERSE_LIST",
+                    _ => "unknown",
+                })
+                .u8("Page", 118)
+                .u8("Last Page", 119);
+            if buf.len() > 120 {
+                discovery.field("Universes", 120..buf.len(), |b| {
+                    b.chunks_exact(2)
+                        .map(|pair| be_u16(pair).to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                });
+            }
+            layers.extend(discovery.finish());
+        }
+        (Some(0x08), _) => {
+            let mut framing = LayerBuilder::new(buf, "Framing Layer", 38..buf.len());
+            framing.flags_and_length(38).vector(40..44, |_| "unknown");
+            layers.extend(framing.finish());
+        }
+        _ => {}
+    }
+
+    Dissection {
+        layers,
+        error: e131::parse(buf).err().map(|e| e.to_string()),
+    }
+}
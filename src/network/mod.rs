@@ -1,27 +1,171 @@
-use crate::core::{AppState, LogLevel};
+use crate::core::{
+    AppState, ArtNetNode, ArtNetOutput, ListenerStatus, LogLevel, PortAddress, Protocol,
+    SourceData, TransmitUniverse, ARTNET_PRIORITY,
+};
 use anyhow::Result;
+use chrono::{DateTime, Utc};
 use log::{debug, info};
-use sacn::packet::ACN_SDT_MULTICAST_PORT;
-use sacn::receive::{DMXData, SacnReceiver};
-use sacn::source::SacnSource;
+use sacn::packet::{ACN_SDT_MULTICAST_PORT, E131_DISCOVERY_UNIVERSE};
+use socket2::{Domain, Socket, Type};
+use std::collections::{BTreeMap, BTreeSet};
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
-use std::sync::Arc;
-use std::time::Duration;
-use tokio::sync::RwLock;
-use tokio::time::sleep;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+use tokio::net::UdpSocket;
+use tokio::sync::{watch, RwLock};
+use tokio::time::{interval, sleep};
+use uuid::Uuid;
+
+mod artnet;
+mod bridge;
+mod capture;
+pub(crate) mod e131;
+mod inspector;
+mod pcap;
+mod replay;
+mod transmitter;
+
+use artnet::{ArtDmx, ArtPacket, ArtPollReply, ARTNET_PORT};
+pub use capture::RecordingStatus;
+use capture::{CaptureRecord, CaptureWriter};
+#[cfg(test)]
+pub use e131::fixtures as e131_fixtures;
+use e131::{DataPacket, Packet, PacketData};
+pub use inspector::{dissect, Dissection, PacketInspector};
+pub use replay::{Replay, MAX_REPLAY_SPEED, MIN_REPLAY_SPEED};
+pub use transmitter::MAX_SEND_RATE;
+use transmitter::{SourceIdentity, Transmitter};
+
+/// Largest sACN packet we expect to receive (a full universe discovery packet).
+const MAX_PACKET_SIZE: usize = 1144;
+
+/// How often sources are checked for loss and subscriptions are updated.
+const HOUSEKEEPING_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How often Art-Net nodes are polled, as the Art-Net 4 spec asks of controllers.
+const ARTPOLL_INTERVAL: Duration = Duration::from_secs(3);
+
+/// How long to wait before retrying a listener that failed to bind.
+const LISTENER_RETRY_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How often the replay engine checks for control changes while idle.
+const REPLAY_IDLE_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Start code for DMX level data.
+const DMX_START_CODE: u8 = 0x00;
+
+/// Start code for ETC per-address priority data.
+const PER_ADDRESS_PRIORITY_START_CODE: u8 = 0xDD;
+
+/// Returns the E1.31 multicast group for a universe (239.255.hi.lo).
+pub fn universe_multicast_addr(universe: u16) -> Ipv4Addr {
+    Ipv4Addr::new(239, 255, (universe >> 8) as u8, (universe & 0xff) as u8)
+}
+
+/// Commands sent to the listener task through [`SacnNetwork`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ListenerCommand {
+    Listen,
+    Rebind,
+    Stop,
+}
 
 pub struct SacnNetwork {
     app_state: Arc<RwLock<AppState>>,
+    listener_control: watch::Sender<ListenerCommand>,
+    artnet_control: watch::Sender<ListenerCommand>,
+    transmitter: Mutex<Transmitter>,
+    recorder: Mutex<Option<CaptureWriter>>, // set while recording received traffic
+    replay: Mutex<Option<Replay>>,
+    inspector: Mutex<PacketInspector>,
 }
 
 impl SacnNetwork {
     pub fn new(app_state: Arc<RwLock<AppState>>) -> Self {
-        Self { app_state }
+        let (listener_control, _) = watch::channel(ListenerCommand::Listen);
+        let (artnet_control, _) = watch::channel(ListenerCommand::Listen);
+        Self {
+            app_state,
+            listener_control,
+            artnet_control,
+            transmitter: Mutex::new(Transmitter::new()),
+            recorder: Mutex::new(None),
+            replay: Mutex::new(None),
+            inspector: Mutex::new(PacketInspector::new()),
+        }
     }
 
+    /// Creates a UDP socket bound to a receive port that other receivers on
+    /// this host can share.
+    fn create_receive_socket(port: u16) -> std::io::Result<UdpSocket> {
+        let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(socket2::Protocol::UDP))?;
+        socket.set_reuse_address(true)?;
+        #[cfg(unix)]
+        socket.set_reuse_port(true)?;
+        socket.set_nonblocking(true)?;
+
+        // Multicast traffic is only delivered to sockets bound to the wildcard
+        // address on most platforms, the adapter is selected when joining groups.
+        let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), port);
+        socket.bind(&addr.into())?;
+
+        UdpSocket::from_std(socket.into())
+    }
+
+    /// Asks the running listener to rebind, e.g. after the adapter changed.
+    pub fn restart_listener(&self) {
+        self.listener_control.send_replace(ListenerCommand::Rebind);
+    }
+
+    pub fn stop_listener(&self) {
+        self.listener_control.send_replace(ListenerCommand::Stop);
+    }
+
+    /// Runs the receive loop until the app exits, rebinding or stopping it
+    /// whenever a command arrives through the listener control handle.
     pub async fn start_listener(&self) -> Result<()> {
         info!("Starting sACN network listener");
+        let mut control = self.listener_control.subscribe();
+
+        loop {
+            let command = *control.borrow_and_update();
+            if command == ListenerCommand::Stop {
+                self.set_listener_status(ListenerStatus::Stopped).await;
+                control.changed().await?;
+                continue;
+            }
+
+            if command == ListenerCommand::Rebind {
+                self.set_listener_status(ListenerStatus::Rebinding).await;
+            }
+
+            match self.run_listener(&mut control).await {
+                // A new command arrived, go round again to act on it
+                Ok(()) => {}
+                Err(e) => {
+                    self.set_listener_status(ListenerStatus::Failed(e.to_string()))
+                        .await;
+                    tokio::select! {
+                        result = control.changed() => result?,
+                        _ = sleep(LISTENER_RETRY_INTERVAL) => {}
+                    }
+                }
+            }
+        }
+    }
 
+    async fn set_listener_status(&self, status: ListenerStatus) {
+        let mut state = self.app_state.write().await;
+        if state.listener_status != status {
+            state.add_log(LogLevel::Info, format!("sACN listener {}", status));
+            state.listener_status = status;
+        }
+    }
+
+    /// Binds a receiver to the selected adapter and processes packets until
+    /// a listener command arrives.
+    async fn run_listener(&self, control: &mut watch::Receiver<ListenerCommand>) -> Result<()> {
         // Get the selected adapter IP
         let bind_ip = {
             let state = self.app_state.read().await;
@@ -29,17 +173,20 @@ impl SacnNetwork {
                 .get_selected_adapter_ip()
                 .unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED))
         };
+        let interface = match bind_ip {
+            IpAddr::V4(ip) => ip,
+            IpAddr::V6(_) => Ipv4Addr::UNSPECIFIED,
+        };
 
         let addr = SocketAddr::new(bind_ip, ACN_SDT_MULTICAST_PORT);
 
-        // Create the sACN receiver with universe discovery enabled
-        let mut receiver = match SacnReceiver::with_ip(addr, None) {
-            Ok(receiver) => {
+        let socket = match Self::create_receive_socket(ACN_SDT_MULTICAST_PORT) {
+            Ok(socket) => {
                 {
                     let mut state = self.app_state.write().await;
                     state.add_log(LogLevel::Info, format!("sACN receiver created on {}", addr));
                 }
-                receiver
+                socket
             }
             Err(e) => {
                 {
@@ -53,17 +200,15 @@ impl SacnNetwork {
             }
         };
 
-        // Enable source discovery announcements
-        receiver.set_announce_source_discovery(true);
-
-        // Listen for all universes (we'll register them as they're discovered)
-        // Start with a common set of universes
-        let common_universes: Vec<u16> = (1..=512).collect();
-        if let Err(e) = receiver.listen_universes(&common_universes) {
+        // Universe discovery tells us which universes are live, the data
+        // universes themselves are joined on demand in the receive loop
+        if let Err(e) =
+            socket.join_multicast_v4(universe_multicast_addr(E131_DISCOVERY_UNIVERSE), interface)
+        {
             let mut state = self.app_state.write().await;
             state.add_log(
                 LogLevel::Warning,
-                format!("Failed to register some universes: {}", e),
+                format!("Failed to join universe discovery: {}", e),
             );
         }
 
@@ -77,148 +222,908 @@ impl SacnNetwork {
                 ),
             );
         }
+        self.set_listener_status(ListenerStatus::Listening(bind_ip))
+            .await;
 
         // Main receive loop
+        let mut buf = [0u8; MAX_PACKET_SIZE];
+        let mut housekeeping = interval(HOUSEKEEPING_INTERVAL);
+        let mut joined = BTreeSet::new();
         loop {
-            let timeout = Some(Duration::from_millis(100));
-            match receiver.recv(timeout) {
-                Ok(packets) => {
-                    // Process received packets
-                    for packet in packets {
-                        self.handle_packet(packet).await;
+            tokio::select! {
+                result = socket.recv_from(&mut buf) => match result {
+                    Ok((len, src)) => self.handle_packet(&buf[..len], src).await,
+                    Err(e) => {
+                        debug!("sACN receive error: {:?}", e);
+                        sleep(Duration::from_millis(100)).await;
                     }
+                },
+                _ = housekeeping.tick() => {
+                    let wanted = {
+                        let mut state = self.app_state.write().await;
+                        state.check_source_loss();
+                        state.check_discovery_timeout();
+                        state.check_sync_timeout();
+                        state.check_compliance_timeout();
+                        state.prune_history();
+                        state.wanted_universes()
+                    };
+                    self.sync_subscriptions(&socket, interface, &mut joined, &wanted)
+                        .await;
                 }
-                Err(e) => {
-                    match e.kind() {
-                        sacn::error::errors::ErrorKind::SourceDiscovered(source_name) => {
-                            let mut state = self.app_state.write().await;
-                            state.add_log(
-                                LogLevel::Info,
-                                format!("Source discovered: {}", source_name),
-                            );
-                            state.update_device(ip, universe, source_name, priority);
-                        }
-                        _ => {
-                            // Handle other errors including timeouts
-                            debug!("sACN receive error: {:?}", e);
-                            sleep(Duration::from_millis(100)).await;
+                _ = control.changed() => {
+                    // Dropping the socket leaves every multicast group
+                    self.app_state.write().await.joined_universes.clear();
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    /// Joins and leaves multicast groups so the socket is subscribed to
+    /// exactly the wanted universes.
+    async fn sync_subscriptions(
+        &self,
+        socket: &UdpSocket,
+        interface: Ipv4Addr,
+        joined: &mut BTreeSet<u16>,
+        wanted: &BTreeSet<u16>,
+    ) {
+        if joined == wanted {
+            return;
+        }
+
+        let mut state = self.app_state.write().await;
+
+        for &universe in joined.difference(wanted) {
+            match socket.leave_multicast_v4(universe_multicast_addr(universe), interface) {
+                Ok(()) => state.add_log(LogLevel::Info, format!("Left universe {}", universe)),
+                Err(e) => state.add_log(
+                    LogLevel::Warning,
+                    format!("Failed to leave universe {}: {}", universe, e),
+                ),
+            }
+        }
+        joined.retain(|universe| wanted.contains(universe));
+
+        let to_join: Vec<u16> = wanted.difference(joined).copied().collect();
+        for universe in to_join {
+            match socket.join_multicast_v4(universe_multicast_addr(universe), interface) {
+                Ok(()) => {
+                    joined.insert(universe);
+                    state.add_log(LogLevel::Info, format!("Joined universe {}", universe));
+                }
+                Err(e) => state.add_log(
+                    LogLevel::Warning,
+                    format!("Failed to join universe {}: {}", universe, e),
+                ),
+            }
+        }
+
+        state.joined_universes = joined.clone();
+    }
+
+    async fn handle_packet(&self, buf: &[u8], src: SocketAddr) {
+        self.app_state.write().await.check_compliance(buf, src.ip());
+
+        // Levels are still shown for packets with field errors the compliance
+        // checker has just reported, only broken packets are dropped
+        let packet = e131::parse_lenient(buf);
+        self.inspector().push(src, buf, &packet);
+
+        // Malformed packets are recorded too, they're what a capture of a
+        // misbehaving sender is for
+        let universe = packet
+            .as_ref()
+            .map_or_else(|_| capture::raw_universe(buf), capture::packet_universe);
+        self.record_packet(buf, src, universe).await;
+
+        match packet {
+            Ok(packet) => self.dispatch_packet(packet, src, None).await,
+            Err(e) => debug!("Discarding malformed sACN packet from {}: {}", src, e),
+        }
+    }
+
+    /// Applies a parsed packet to the app state. `captured` is when a
+    /// replayed packet was captured, its data then ages on the replay's clock.
+    async fn dispatch_packet(
+        &self,
+        packet: Packet<'_>,
+        src: SocketAddr,
+        captured: Option<DateTime<Utc>>,
+    ) {
+        let cid = packet.cid;
+        match packet.data {
+            PacketData::Data(data) => self.handle_data_packet(cid, src.ip(), data, captured).await,
+            PacketData::Discovery(discovery) => {
+                let universes: Vec<u16> = discovery.universes().collect();
+                let mut state = self.app_state.write().await;
+                state.update_discovery(
+                    cid,
+                    discovery.source_name.to_string(),
+                    src.ip(),
+                    discovery.page,
+                    discovery.last_page,
+                    &universes,
+                );
+            }
+            PacketData::Sync(sync) => {
+                let mut state = self.app_state.write().await;
+                state.add_log(
+                    LogLevel::Rx,
+                    format!(
+                        "Received sync on universe {} from {}, seq {}",
+                        sync.sync_address,
+                        src.ip(),
+                        sync.sequence
+                    ),
+                );
+                state.apply_sync(cid, sync.sync_address, captured.unwrap_or_else(Utc::now));
+            }
+        }
+    }
+
+    async fn handle_data_packet(
+        &self,
+        cid: Uuid,
+        source_ip: IpAddr,
+        data: DataPacket<'_>,
+        captured: Option<DateTime<Utc>>,
+    ) {
+        let (start_code, slots) = (data.start_code, data.slots);
+
+        // Convert the slots after the start code to [u8; 512], padding with zeros if needed
+        let mut channels = [0u8; 512];
+        let copy_len = std::cmp::min(slots.len(), 512);
+        channels[..copy_len].copy_from_slice(&slots[..copy_len]);
+
+        let mut state = self.app_state.write().await;
+        let source_name = data.source_name.to_string();
+        state.record_stream_stats(
+            data.universe,
+            cid,
+            &source_name,
+            source_ip,
+            data.sequence,
+            captured,
+        );
+
+        // Duplicate and late packets would overwrite newer levels
+        if !state.accept_sequence(data.universe, cid, data.sequence) {
+            debug!(
+                "Discarding out of sequence packet {} from {} on universe {}",
+                data.sequence, source_name, data.universe
+            );
+            return;
+        }
+
+        if data.stream_terminated() {
+            state.terminate_source(data.universe, cid);
+            return;
+        }
+
+        match start_code {
+            DMX_START_CODE => {
+                // Log the received packet
+                state.add_log(
+                    LogLevel::Rx,
+                    format!(
+                        "Received DMX data on universe {} from {} ({}): {} channels, seq {}",
+                        data.universe,
+                        source_name,
+                        source_ip,
+                        slots.len(),
+                        data.sequence
+                    ),
+                );
+
+                let source = SourceData::new(
+                    cid,
+                    source_name.clone(),
+                    source_ip,
+                    Protocol::Sacn,
+                    data.priority,
+                    data.sequence,
+                    channels,
+                );
+                let source = match captured {
+                    Some(captured) => source.replayed_at(captured),
+                    None => source,
+                };
+                if data.sync_address != 0 {
+                    state.receive_synchronized(
+                        data.universe,
+                        source,
+                        data.sync_address,
+                        data.force_synchronization(),
+                    );
+                } else {
+                    state.receive_unsynchronized(data.universe, cid);
+                    state.update_universe(data.universe, source);
+                }
+                state.update_device(
+                    cid,
+                    source_ip,
+                    data.universe,
+                    source_name,
+                    data.priority,
+                    Protocol::Sacn,
+                );
+            }
+            PER_ADDRESS_PRIORITY_START_CODE => {
+                state.add_log(
+                    LogLevel::Rx,
+                    format!(
+                        "Received per-address priority on universe {} from {} ({})",
+                        data.universe, source_name, source_ip
+                    ),
+                );
+
+                if !state.update_address_priorities(
+                    data.universe,
+                    cid,
+                    channels,
+                    captured.unwrap_or_else(Utc::now),
+                ) {
+                    debug!(
+                        "Per-address priority from {} on universe {} arrived before any levels",
+                        source_name, data.universe
+                    );
+                }
+            }
+            other => debug!(
+                "Ignoring start code {:#04X} on universe {}",
+                other, data.universe
+            ),
+        }
+    }
+
+    pub fn restart_artnet_listener(&self) {
+        self.artnet_control.send_replace(ListenerCommand::Rebind);
+    }
+
+    pub fn stop_artnet_listener(&self) {
+        self.artnet_control.send_replace(ListenerCommand::Stop);
+    }
+
+    /// Runs the Art-Net receive loop next to the sACN one, with its own
+    /// start, stop and retry handling.
+    pub async fn start_artnet_listener(&self) -> Result<()> {
+        info!("Starting Art-Net listener");
+        let mut control = self.artnet_control.subscribe();
+
+        loop {
+            let command = *control.borrow_and_update();
+            if command == ListenerCommand::Stop {
+                self.set_artnet_status(ListenerStatus::Stopped).await;
+                control.changed().await?;
+                continue;
+            }
+
+            if command == ListenerCommand::Rebind {
+                self.set_artnet_status(ListenerStatus::Rebinding).await;
+            }
+
+            if let Err(e) = self.run_artnet_listener(&mut control).await {
+                self.set_artnet_status(ListenerStatus::Failed(e.to_string()))
+                    .await;
+                tokio::select! {
+                    result = control.changed() => result?,
+                    _ = sleep(LISTENER_RETRY_INTERVAL) => {}
+                }
+            }
+        }
+    }
+
+    async fn set_artnet_status(&self, status: ListenerStatus) {
+        let mut state = self.app_state.write().await;
+        if state.artnet_status != status {
+            state.add_log(LogLevel::Info, format!("Art-Net listener {}", status));
+            state.artnet_status = status;
+        }
+    }
+
+    /// Receives Art-Net broadcasts and unicasts on every adapter, and polls
+    /// for nodes, until a listener command arrives.
+    async fn run_artnet_listener(
+        &self,
+        control: &mut watch::Receiver<ListenerCommand>,
+    ) -> Result<()> {
+        let socket = Self::create_receive_socket(ARTNET_PORT)
+            .and_then(|socket| socket.set_broadcast(true).map(|()| socket))
+            .map_err(|e| anyhow::anyhow!("Failed to create Art-Net receiver: {}", e))?;
+        self.set_artnet_status(ListenerStatus::Listening(IpAddr::V4(Ipv4Addr::UNSPECIFIED)))
+            .await;
+
+        let mut buf = [0u8; artnet::MAX_PACKET_SIZE];
+        let mut housekeeping = interval(HOUSEKEEPING_INTERVAL);
+        let mut poll = interval(ARTPOLL_INTERVAL);
+        loop {
+            tokio::select! {
+                result = socket.recv_from(&mut buf) => match result {
+                    Ok((len, src)) => match artnet::parse(&buf[..len]) {
+                        Ok(ArtPacket::Dmx(dmx)) => self.handle_artdmx(src.ip(), dmx).await,
+                        Ok(ArtPacket::PollReply(reply)) => {
+                            self.handle_poll_reply(src.ip(), reply).await
                         }
+                        Err(e) => debug!("Ignoring Art-Net packet from {}: {}", src, e),
+                    },
+                    Err(e) => {
+                        debug!("Art-Net receive error: {:?}", e);
+                        sleep(Duration::from_millis(100)).await;
+                    }
+                },
+                // Art-Net sources time out even while the sACN listener is stopped
+                _ = housekeeping.tick() => {
+                    let mut state = self.app_state.write().await;
+                    state.check_source_loss();
+                    state.check_artnet_nodes();
+                }
+                _ = poll.tick() => {
+                    // Nodes reply to the port the poll came from, so it goes
+                    // out of the listening socket
+                    let broadcast = self.app_state.read().await.artnet_broadcast_addr();
+                    let target = SocketAddr::new(broadcast, ARTNET_PORT);
+                    if let Err(e) = socket.send_to(&artnet::poll_packet(), target).await {
+                        debug!("Failed to send ArtPoll to {}: {}", target, e);
                     }
                 }
+                _ = control.changed() => return Ok(()),
             }
         }
     }
 
-    async fn handle_packet(&self, packet: DMXData) {
+    async fn handle_poll_reply(&self, source_ip: IpAddr, reply: ArtPollReply<'_>) {
+        let node = ArtNetNode {
+            short_name: reply.short_name.trim().to_string(),
+            long_name: reply.long_name.trim().to_string(),
+            firmware: reply.firmware,
+            node_report: reply.node_report.trim().to_string(),
+            ports: reply.ports(),
+            last_reply: Utc::now(),
+        };
         let mut state = self.app_state.write().await;
+        state.update_artnet_node(artnet::node_id(source_ip), source_ip, node);
+    }
+
+    /// Merges ArtDmx levels into the universe their port address maps to.
+    async fn handle_artdmx(&self, source_ip: IpAddr, dmx: ArtDmx<'_>) {
+        let mut state = self.app_state.write().await;
+        // Our own broadcasts come straight back to the listener
+        if state.is_sending_artnet() && state.is_own_address(source_ip) {
+            debug!(
+                "Ignoring our own Art-Net DMX on port address {}",
+                dmx.port_address
+            );
+            return;
+        }
+        let Some(universe) = state.artnet_universe(dmx.port_address) else {
+            debug!(
+                "Art-Net port address {} from {} has no universe",
+                dmx.port_address, source_ip
+            );
+            return;
+        };
+
+        let mut channels = [0u8; 512];
+        channels[..dmx.data.len()].copy_from_slice(dmx.data);
 
-        // Log the received packet
         state.add_log(
             LogLevel::Rx,
             format!(
-                "Received DMX data on universe {}: {} channels",
-                packet.universe,
-                packet.values.len()
+                "Received Art-Net DMX on universe {} (port address {}) from {}: {} channels, seq {}",
+                universe,
+                dmx.port_address,
+                source_ip,
+                dmx.data.len(),
+                dmx.sequence
             ),
         );
 
-        // Convert Vec<u8> to [u8; 512], padding with zeros if needed
-        let mut channels = [0u8; 512];
-        let copy_len = std::cmp::min(packet.values.len(), 512);
-        if copy_len > 0 {
-            channels[..copy_len].copy_from_slice(&packet.values[..copy_len]);
-        }
-
-        // Update universe data
-        state.update_universe(
-            packet.universe,
+        let cid = artnet::node_id(source_ip);
+        let source_name = state
+            .devices
+            .get(&cid)
+            .filter(|device| device.node.is_some())
+            .map_or_else(
+                || format!("Art-Net {}", source_ip),
+                |device| device.name.clone(),
+            );
+        let source = SourceData::new(
+            cid,
+            source_name.clone(),
+            source_ip,
+            Protocol::ArtNet,
+            ARTNET_PRIORITY,
+            dmx.sequence,
             channels,
-            // We don't have direct access to source IP from DMXData,
-            // so we'll use a placeholder for now
-            IpAddr::V4(Ipv4Addr::UNSPECIFIED),
-            0, // sequence not available in DMXData
         );
+        state.receive_unsynchronized(universe, cid);
+        state.update_universe(universe, source);
+        state.update_device(
+            cid,
+            source_ip,
+            universe,
+            source_name,
+            ARTNET_PRIORITY,
+            Protocol::ArtNet,
+        );
+    }
 
-        // Update device information if we have source CID
-        if let Some(src_cid) = packet.src_cid {
-            let source_name = format!("Source-{}", src_cid);
-            state.update_device(
-                IpAddr::V4(Ipv4Addr::UNSPECIFIED), // placeholder
-                packet.universe,
-                source_name,
-                packet.priority,
-            );
-        }
+    fn recorder(&self) -> MutexGuard<'_, Option<CaptureWriter>> {
+        self.recorder.lock().unwrap_or_else(PoisonError::into_inner)
     }
 
-    pub async fn send_dmx(&self, universe: u16, dmx_data: &[u8; 512]) -> Result<()> {
-        // Get the selected adapter IP for binding
-        let bind_ip = {
-            let state = self.app_state.read().await;
-            state
-                .get_selected_adapter_ip()
-                .unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED))
+    /// Appends a received packet to the capture file if recording.
+    async fn record_packet(&self, buf: &[u8], source: SocketAddr, universe: u16) {
+        let error = {
+            let mut recorder = self.recorder();
+            let Some(writer) = recorder.as_mut() else {
+                return;
+            };
+            let record = CaptureRecord {
+                timestamp: Utc::now(),
+                source,
+                universe,
+                payload: buf.to_vec(),
+            };
+            match writer.write(&record) {
+                Ok(()) => return,
+                Err(e) => {
+                    recorder.take();
+                    e
+                }
+            }
         };
 
-        // Use a different port for sending to avoid conflicts
-        let bind_addr = SocketAddr::new(bind_ip, 0); // Let the OS choose a port
+        let mut state = self.app_state.write().await;
+        state.add_log(LogLevel::Error, format!("Recording stopped: {}", error));
+    }
+
+    /// Starts saving every received sACN packet to a new capture file.
+    pub async fn start_recording(&self) -> Result<PathBuf> {
+        let result = capture::new_capture_path().and_then(|path| {
+            let writer = CaptureWriter::create(&path)?;
+            if let Some(previous) = self.recorder().replace(writer) {
+                previous.finish()?;
+            }
+            Ok(path)
+        });
+
+        let mut state = self.app_state.write().await;
+        match &result {
+            Ok(path) => state.add_log(
+                LogLevel::Info,
+                format!("Recording sACN traffic to {}", path.display()),
+            ),
+            Err(e) => state.add_log(LogLevel::Error, format!("Failed to start recording: {}", e)),
+        }
+        result
+    }
+
+    pub async fn stop_recording(&self) -> Result<()> {
+        let result = self.finish_recording();
 
-        // Create a new source for sending
-        let mut source = match SacnSource::with_ip("sACN Viewer", bind_addr) {
-            Ok(source) => source,
+        let mut state = self.app_state.write().await;
+        match result {
+            Ok(Some(status)) => {
+                state.add_log(
+                    LogLevel::Info,
+                    format!(
+                        "Stopped recording: {} packets saved to {}",
+                        status.packets,
+                        status.path.display()
+                    ),
+                );
+                Ok(())
+            }
+            Ok(None) => Ok(()),
             Err(e) => {
-                let mut state = self.app_state.write().await;
+                state.add_log(LogLevel::Error, e.to_string());
+                Err(e)
+            }
+        }
+    }
+
+    /// Closes the capture file, e.g. when the app quits.
+    pub fn finish_recording(&self) -> Result<Option<RecordingStatus>> {
+        let writer = self.recorder().take();
+        writer.map(CaptureWriter::finish).transpose()
+    }
+
+    pub fn recording_status(&self) -> Option<RecordingStatus> {
+        self.recorder()
+            .as_ref()
+            .map(|writer| writer.status().clone())
+    }
+
+    /// Recently received packets, for the inspector panel.
+    pub fn inspector(&self) -> MutexGuard<'_, PacketInspector> {
+        self.inspector
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// The loaded replay, for the UI to control.
+    pub fn replay(&self) -> MutexGuard<'_, Option<Replay>> {
+        self.replay.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Loads a capture, pcap or pcapng file for playback, replacing any
+    /// loaded replay. An offline replay starts paused with the first packets
+    /// applied to the viewer, ready to scrub through.
+    pub async fn open_replay(&self, path: PathBuf, offline: bool) -> Result<()> {
+        let result = Replay::open(&path);
+
+        let mut state = self.app_state.write().await;
+        match result {
+            Ok(mut replay) => {
+                if offline {
+                    replay.offline = true;
+                    replay.seek(Duration::ZERO, Instant::now());
+                }
                 state.add_log(
-                    LogLevel::Error,
-                    format!("Failed to create sACN source: {}", e),
+                    LogLevel::Info,
+                    format!(
+                        "Loaded {} for {}: {} packets on {} universes, {:.1} s",
+                        path.display(),
+                        if offline { "offline viewing" } else { "replay" },
+                        replay.packet_count(),
+                        replay.universes().len(),
+                        replay.duration().as_secs_f32()
+                    ),
                 );
-                return Err(anyhow::anyhow!("Failed to create sACN source: {}", e));
+                let rejected = replay.rejected_count();
+                if rejected > 0 {
+                    let reasons: Vec<String> = replay
+                        .rejected()
+                        .iter()
+                        .map(|(reason, count)| format!("{} x {}", count, reason))
+                        .collect();
+                    state.add_log(
+                        LogLevel::Warning,
+                        format!(
+                            "Skipped {} packets in {} rejected by the E1.31 parser: {}",
+                            rejected,
+                            path.display(),
+                            reasons.join("; ")
+                        ),
+                    );
+                }
+                *self.replay() = Some(replay);
+                Ok(())
+            }
+            Err(e) => {
+                state.add_log(LogLevel::Error, format!("Failed to load replay: {}", e));
+                Err(e)
+            }
+        }
+    }
+
+    /// Plays the loaded replay, either through the transmitter or straight
+    /// into the app state when it is in offline mode.
+    pub async fn start_replayer(&self) -> Result<()> {
+        info!("Starting replay engine");
+        let mut replay_time = None;
+
+        loop {
+            let now = Instant::now();
+            let (time, resync, records, offline, remap, wait) = {
+                let mut replay = self.replay();
+                match replay.as_mut() {
+                    Some(replay) => (
+                        replay.offline.then(|| replay.capture_time(now)),
+                        replay.is_resyncing(),
+                        replay.due(now),
+                        replay.offline,
+                        replay.remap.clone(),
+                        replay.next_due_in(now),
+                    ),
+                    None => (None, false, Vec::new(), false, BTreeMap::new(), None),
+                }
+            };
+
+            // Replayed sources time out on the capture's clock, so they
+            // stay put while paused and follow seeks
+            if time != replay_time {
+                replay_time = time;
+                self.app_state.write().await.replay_time = time;
+            }
+
+            // A seek can go back in time, older sequence numbers are expected
+            if resync && offline {
+                let mut state = self.app_state.write().await;
+                state.reset_sequences();
+                state.restart_replayed_stream_timing();
             }
+
+            if !records.is_empty() {
+                let remap_universe = |u: u16| remap.get(&u).copied().unwrap_or(u);
+                if let Err(e) = self.replay_records(&records, offline, remap_universe).await {
+                    if let Some(replay) = self.replay().as_mut() {
+                        replay.pause(Instant::now());
+                    }
+                    let mut state = self.app_state.write().await;
+                    state.add_log(LogLevel::Error, format!("Replay paused: {}", e));
+                }
+            }
+
+            let wait = wait.map_or(REPLAY_IDLE_INTERVAL, |w| w.min(REPLAY_IDLE_INTERVAL));
+            sleep(wait).await;
+        }
+    }
+
+    async fn replay_records(
+        &self,
+        records: &[CaptureRecord],
+        offline: bool,
+        remap_universe: impl Fn(u16) -> u16,
+    ) -> Result<()> {
+        let identity = if offline {
+            None
+        } else {
+            Some(self.transmit_identity().await)
         };
 
-        // Register the universe
-        if let Err(e) = source.register_universe(universe) {
-            let mut state = self.app_state.write().await;
-            state.add_log(
-                LogLevel::Error,
-                format!("Failed to register universe {}: {}", universe, e),
-            );
-            return Err(anyhow::anyhow!("Failed to register universe: {}", e));
+        for record in records {
+            let packet = e131::parse_lenient(&record.payload);
+            if offline {
+                self.app_state
+                    .write()
+                    .await
+                    .check_compliance(&record.payload, record.source.ip());
+                self.inspector()
+                    .push(record.source, &record.payload, &packet);
+            }
+            let Ok(mut packet) = packet else {
+                continue;
+            };
+            match &mut packet.data {
+                PacketData::Data(data) => {
+                    data.universe = remap_universe(data.universe);
+                    if data.sync_address != 0 {
+                        data.sync_address = remap_universe(data.sync_address);
+                    }
+                }
+                PacketData::Sync(sync) => {
+                    sync.sync_address = remap_universe(sync.sync_address);
+                }
+                PacketData::Discovery(_) => {}
+            }
+
+            match &identity {
+                None => {
+                    self.dispatch_packet(packet, record.source, Some(record.timestamp))
+                        .await
+                }
+                Some(identity) => self.transmit_replayed(identity, packet)?,
+            }
         }
+        Ok(())
+    }
 
-        // Convert dmx_data to Vec<u8> with start code
-        let mut data = vec![0u8]; // DMX start code
-        data.extend_from_slice(dmx_data);
+    /// Re-sends a recorded packet from this app's own source.
+    fn transmit_replayed(&self, identity: &SourceIdentity, packet: Packet<'_>) -> Result<()> {
+        let mut transmitter = self.transmitter();
+        match packet.data {
+            PacketData::Data(data) => {
+                if data.stream_terminated() || data.start_code != DMX_START_CODE {
+                    return Ok(());
+                }
 
-        // Send the data
-        let priority = Some(100u8);
-        let dst_ip = None; // Use multicast
-        let sync_uni = None; // No synchronization
+                let mut universe = TransmitUniverse::new(data.universe);
+                universe.priority = data.priority;
+                universe.preview = data.preview_data();
+                universe.sync_universe = data.sync_address;
+                let len = data.slots.len().min(universe.levels.len());
+                universe.levels[..len].copy_from_slice(&data.slots[..len]);
+                transmitter.send(identity, &universe)
+            }
+            PacketData::Sync(sync) => transmitter.sync(identity, sync.sync_address),
+            PacketData::Discovery(_) => Ok(()),
+        }
+    }
 
-        match source.send(&[universe], &data, priority, dst_ip, sync_uni) {
-            Ok(_) => {
-                let mut state = self.app_state.write().await;
+    fn transmitter(&self) -> MutexGuard<'_, Transmitter> {
+        self.transmitter
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Returns the CID, source name and adapter IP the transmitter should use.
+    async fn transmit_identity(&self) -> SourceIdentity {
+        let state = self.app_state.read().await;
+        SourceIdentity {
+            cid: state.source_cid,
+            source_name: state.transmit_session.source_name.clone(),
+            bind_ip: state
+                .get_selected_adapter_ip()
+                .unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED)),
+            artnet_broadcast: state.artnet_broadcast_addr(),
+            artnet_universe_offset: state.artnet_universe_offset,
+        }
+    }
+
+    pub async fn send_dmx(&self, universe: &TransmitUniverse) -> Result<()> {
+        let identity = self.transmit_identity().await;
+        let result = {
+            let mut transmitter = self.transmitter();
+            transmitter
+                .send(&identity, universe)
+                .and_then(|()| match universe.sync_universe {
+                    0 => Ok(()),
+                    sync_universe => transmitter.sync(&identity, sync_universe),
+                })
+        };
+
+        let mut state = self.app_state.write().await;
+        match result {
+            Ok(()) => {
+                let artnet = match universe.artnet {
+                    ArtNetOutput::Off => String::new(),
+                    output => format!(
+                        ", Art-Net {} {}",
+                        PortAddress::from_universe(
+                            universe.universe,
+                            identity.artnet_universe_offset
+                        )
+                        .map_or_else(String::new, |address| address.to_string()),
+                        output.to_string().to_lowercase()
+                    ),
+                };
                 state.add_log(
                     LogLevel::Tx,
                     format!(
-                        "Sent DMX data to universe {}: {} channels",
-                        universe,
-                        dmx_data.len()
+                        "Sent DMX data to universe {}: {} channels, priority {}{}",
+                        universe.universe,
+                        universe.levels.len(),
+                        universe.priority,
+                        artnet
                     ),
                 );
                 Ok(())
             }
             Err(e) => {
+                state.add_log(LogLevel::Error, e.to_string());
+                Err(e)
+            }
+        }
+    }
+
+    /// Streams every enabled universe of the transmit session at the
+    /// configured send rate while auto send is enabled. Universes a route is
+    /// feeding are streamed whether or not auto send is on.
+    pub async fn start_transmitter(&self) -> Result<()> {
+        info!("Starting sACN transmitter");
+        let mut last_error: Option<String> = None;
+
+        loop {
+            let (send_rate, universes) = {
+                let mut state = self.app_state.write().await;
+                // Routed levels are merged HTP with the universe's own levels
+                let routed = state.routed_levels();
+                let universes: Vec<TransmitUniverse> = state
+                    .transmit_session
+                    .universes
+                    .iter()
+                    .filter(|u| {
+                        u.enabled && (state.auto_send_enabled || routed.contains_key(&u.universe))
+                    })
+                    .cloned()
+                    .map(|mut u| {
+                        if let Some(levels) = routed.get(&u.universe) {
+                            for (level, routed) in u.levels.iter_mut().zip(levels) {
+                                *level = (*level).max(*routed);
+                            }
+                        }
+                        u
+                    })
+                    .collect();
+                (state.send_rate.clamp(1, MAX_SEND_RATE), universes)
+            };
+            let identity = self.transmit_identity().await;
+            let now = Instant::now();
+
+            let mut started = Vec::new();
+            let mut stopped = Vec::new();
+            let mut error = None;
+            {
+                let mut transmitter = self.transmitter();
+                let streaming = transmitter.streaming_universes();
+
+                for &universe in &streaming {
+                    if !universes.iter().any(|u| u.universe == universe) {
+                        match transmitter.stop_stream(universe) {
+                            Ok(()) => stopped.push(universe),
+                            Err(e) => error = Some(e.to_string()),
+                        }
+                    }
+                }
+
+                let mut sync_due = BTreeSet::new();
+                for universe in &universes {
+                    match transmitter.stream(&identity, universe, now) {
+                        Ok(sent) => {
+                            if sent && !streaming.contains(&universe.universe) {
+                                started.push(universe.universe);
+                            }
+                            if sent && universe.sync_universe != 0 {
+                                sync_due.insert(universe.sync_universe);
+                            }
+                        }
+                        Err(e) => error = Some(e.to_string()),
+                    }
+                }
+
+                // Latch each sync group once all of its universes are out
+                for sync_universe in sync_due {
+                    if let Err(e) = transmitter.sync(&identity, sync_universe) {
+                        error = Some(e.to_string());
+                    }
+                }
+            }
+
+            if !started.is_empty() || !stopped.is_empty() || error != last_error {
                 let mut state = self.app_state.write().await;
-                state.add_log(LogLevel::Error, format!("Failed to send DMX data: {}", e));
-                Err(anyhow::anyhow!("Failed to send DMX data: {}", e))
+                for universe in started {
+                    state.add_log(
+                        LogLevel::Tx,
+                        format!("Streaming universe {} at {} Hz", universe, send_rate),
+                    );
+                }
+                for universe in stopped {
+                    state.add_log(LogLevel::Tx, format!("Terminated universe {}", universe));
+                }
+                if let Some(ref e) = error {
+                    if error != last_error {
+                        state.add_log(LogLevel::Error, e.clone());
+                    }
+                }
+                last_error = error;
             }
+
+            sleep(Duration::from_secs_f64(1.0 / send_rate as f64)).await;
         }
     }
 
-    pub async fn get_discovered_sources(&self) -> Vec<String> {
-        // This would need to be implemented to track discovered sources
-        // For now, return an empty list
-        Vec::new()
+    /// Sends stream termination for everything being transmitted, e.g. when
+    /// the app quits.
+    pub fn stop_transmitting(&self) {
+        self.transmitter().shutdown();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use e131::fixtures::data_fixture;
+
+    #[tokio::test]
+    async fn records_malformed_packets() {
+        let path = std::env::temp_dir().join(format!(
+            "sacn-viewer-malformed-{}.{}",
+            std::process::id(),
+            capture::CAPTURE_EXTENSION
+        ));
+        let network = SacnNetwork::new(Arc::new(RwLock::new(AppState::new())));
+        network
+            .recorder()
+            .replace(CaptureWriter::create(&path).unwrap());
+
+        let src: SocketAddr = "10.0.0.5:5568".parse().unwrap();
+        let packet = data_fixture();
+        network.handle_packet(&packet, src).await;
+        network.handle_packet(&packet[..120], src).await;
+        network.handle_packet(&packet[..50], src).await;
+        network.finish_recording().unwrap();
+
+        let loaded = capture::load_capture(&path);
+        std::fs::remove_file(&path).unwrap();
+        let records = loaded.unwrap().records;
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[1].payload, &packet[..120]);
+        assert_eq!(records[1].universe, 1, "read from the universe field");
+        assert_eq!(records[2].payload, &packet[..50]);
+        assert_eq!(records[2].universe, 0, "too short for a universe");
     }
 }
@@ -0,0 +1,411 @@
+use super::capture::{packet_universe, CaptureRecord, LoadedCapture};
+use super::e131;
+use anyhow::Result;
+use chrono::DateTime;
+use log::debug;
+use sacn::packet::ACN_SDT_MULTICAST_PORT;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+/// pcap magic numbers as read little-endian, for microsecond and nanosecond
+/// timestamps.
+const PCAP_MAGIC_MICROS: u32 = 0xA1B2_C3D4;
+const PCAP_MAGIC_NANOS: u32 = 0xA1B2_3C4D;
+
+/// pcapng block types and the Section Header byte-order magic.
+const PCAPNG_SECTION_HEADER: u32 = 0x0A0D_0D0A;
+const PCAPNG_INTERFACE_DESCRIPTION: u32 = 1;
+const PCAPNG_OBSOLETE_PACKET: u32 = 2;
+const PCAPNG_SIMPLE_PACKET: u32 = 3;
+const PCAPNG_ENHANCED_PACKET: u32 = 6;
+const PCAPNG_BYTE_ORDER_MAGIC: u32 = 0x1A2B_3C4D;
+
+/// Interface Description option holding the timestamp resolution.
+const PCAPNG_OPTION_TSRESOL: u16 = 9;
+const PCAPNG_OPTION_END: u16 = 0;
+
+const PCAP_HEADER_LEN: usize = 24;
+const PCAP_RECORD_HEADER_LEN: usize = 16;
+
+/// Link-layer header types we can decode.
+const LINKTYPE_NULL: u32 = 0;
+const LINKTYPE_ETHERNET: u32 = 1;
+const LINKTYPE_RAW: u32 = 101;
+const LINKTYPE_RAW_OPENBSD: u32 = 12;
+const LINKTYPE_LINUX_SLL: u32 = 113;
+const LINKTYPE_IPV4: u32 = 228;
+const LINKTYPE_IPV6: u32 = 229;
+const LINKTYPE_LINUX_SLL2: u32 = 276;
+
+const ETHERTYPE_IPV4: u16 = 0x0800;
+const ETHERTYPE_IPV6: u16 = 0x86DD;
+const ETHERTYPE_VLAN: u16 = 0x8100;
+
+const IP_PROTOCOL_UDP: u8 = 17;
+
+/// Reads integers in the byte order the file was written in.
+#[derive(Debug, Clone, Copy)]
+struct ByteOrder {
+    big_endian: bool,
+}
+
+impl ByteOrder {
+    fn u16(self, b: &[u8]) -> u16 {
+        let b = [b[0], b[1]];
+        if self.big_endian {
+            u16::from_be_bytes(b)
+        } else {
+            u16::from_le_bytes(b)
+        }
+    }
+
+    fn u32(self, b: &[u8]) -> u32 {
+        let b = [b[0], b[1], b[2], b[3]];
+        if self.big_endian {
+            u32::from_be_bytes(b)
+        } else {
+            u32::from_le_bytes(b)
+        }
+    }
+}
+
+pub fn is_pcap(data: &[u8]) -> bool {
+    pcap_header(data).is_some()
+}
+
+/// Returns the byte order and whether timestamps are in nanoseconds.
+fn pcap_header(data: &[u8]) -> Option<(ByteOrder, bool)> {
+    let magic = data.get(0..4)?;
+    for big_endian in [false, true] {
+        let order = ByteOrder { big_endian };
+        match order.u32(magic) {
+            PCAP_MAGIC_MICROS => return Some((order, false)),
+            PCAP_MAGIC_NANOS => return Some((order, true)),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Pulls the E1.31 packets sent to UDP port 5568 out of a pcap file. Other
+/// traffic is skipped, a packet cut short at the end of the file is dropped.
+pub fn parse_pcap(data: &[u8]) -> Result<LoadedCapture> {
+    let (order, nanos) = pcap_header(data).ok_or_else(|| anyhow::anyhow!("Not a pcap file"))?;
+    if data.len() < PCAP_HEADER_LEN {
+        return Err(anyhow::anyhow!("pcap header is truncated"));
+    }
+    let link_type = order.u32(&data[20..24]) & 0x0FFF_FFFF;
+
+    let mut capture = LoadedCapture::default();
+    let mut rest = &data[PCAP_HEADER_LEN..];
+    while rest.len() >= PCAP_RECORD_HEADER_LEN {
+        let seconds = order.u32(&rest[0..4]) as i64;
+        let fraction = order.u32(&rest[4..8]) as i64;
+        let captured = order.u32(&rest[8..12]) as usize;
+        let Some(frame) = rest.get(PCAP_RECORD_HEADER_LEN..PCAP_RECORD_HEADER_LEN + captured)
+        else {
+            break;
+        };
+        rest = &rest[PCAP_RECORD_HEADER_LEN + captured..];
+
+        let micros = if nanos {
+            seconds * 1_000_000 + fraction / 1000
+        } else {
+            seconds * 1_000_000 + fraction
+        };
+        decode_frame(&mut capture, link_type, micros, frame);
+    }
+    Ok(capture)
+}
+
+pub fn is_pcapng(data: &[u8]) -> bool {
+    data.len() >= 12 && ByteOrder { big_endian: false }.u32(&data[0..4]) == PCAPNG_SECTION_HEADER
+}
+
+/// A capture interface declared in a pcapng section.
+struct Interface {
+    link_type: u32,
+    ticks_per_second: u64,
+}
+
+impl Interface {
+    fn micros(&self, ticks: u64) -> i64 {
+        (ticks as u128 * 1_000_000 / self.ticks_per_second as u128) as i64
+    }
+}
+
+/// Reads the ticks per second from an Interface Description Block's options.
+fn ticks_per_second(order: ByteOrder, mut options: &[u8]) -> u64 {
+    while options.len() >= 4 {
+        let code = order.u16(&options[0..2]);
+        let len = order.u16(&options[2..4]) as usize;
+        if code == PCAPNG_OPTION_END {
+            break;
+        }
+        if code == PCAPNG_OPTION_TSRESOL && len >= 1 && options.len() > 4 {
+            // High bit set means a power of two, otherwise a power of ten
+            let resolution = options[4];
+            let exponent = (resolution & 0x7F) as u32;
+            let ticks = if resolution & 0x80 != 0 {
+                1u64.checked_shl(exponent)
+            } else {
+                10u64.checked_pow(exponent)
+            };
+            return ticks.filter(|&t| t > 0).unwrap_or(1_000_000);
+        }
+        let padded = (len + 3) & !3;
+        options = options.get(4 + padded..).unwrap_or_default();
+    }
+    1_000_000
+}
+
+/// Pulls the E1.31 packets sent to UDP port 5568 out of a pcapng file, across
+/// every section and interface. Unknown blocks are skipped.
+pub fn parse_pcapng(data: &[u8]) -> Result<LoadedCapture> {
+    if !is_pcapng(data) {
+        return Err(anyhow::anyhow!("Not a pcapng file"));
+    }
+
+    let mut order = ByteOrder { big_endian: false };
+    let mut interfaces: Vec<Interface> = Vec::new();
+    let mut last_micros = 0;
+    let mut capture = LoadedCapture::default();
+    let mut rest = data;
+
+    while rest.len() >= 12 {
+        let block_type = order.u32(&rest[0..4]);
+        if block_type == PCAPNG_SECTION_HEADER {
+            // Each section sets its own byte order and interfaces
+            order = match u32::from_le_bytes([rest[8], rest[9], rest[10], rest[11]]) {
+                PCAPNG_BYTE_ORDER_MAGIC => ByteOrder { big_endian: false },
+                _ => ByteOrder { big_endian: true },
+            };
+            interfaces.clear();
+        }
+
+        let block_len = order.u32(&rest[4..8]) as usize;
+        if block_len < 12 || !block_len.is_multiple_of(4) {
+            return Err(anyhow::anyhow!("Invalid pcapng block length {}", block_len));
+        }
+        let Some(block) = rest.get(..block_len) else {
+            break;
+        };
+        rest = &rest[block_len..];
+        let body = &block[8..block_len - 4];
+
+        match block_type {
+            PCAPNG_INTERFACE_DESCRIPTION if body.len() >= 8 => interfaces.push(Interface {
+                link_type: order.u16(&body[0..2]) as u32,
+                ticks_per_second: ticks_per_second(order, &body[8..]),
+            }),
+            PCAPNG_ENHANCED_PACKET if body.len() >= 20 => {
+                let Some(interface) = interfaces.get(order.u32(&body[0..4]) as usize) else {
+                    continue;
+                };
+                let ticks = (order.u32(&body[4..8]) as u64) << 32 | order.u32(&body[8..12]) as u64;
+                let captured = order.u32(&body[12..16]) as usize;
+                let Some(frame) = body.get(20..20 + captured) else {
+                    continue;
+                };
+                last_micros = interface.micros(ticks);
+                decode_frame(&mut capture, interface.link_type, last_micros, frame);
+            }
+            PCAPNG_OBSOLETE_PACKET if body.len() >= 20 => {
+                let Some(interface) = interfaces.get(order.u16(&body[0..2]) as usize) else {
+                    continue;
+                };
+                let ticks = (order.u32(&body[4..8]) as u64) << 32 | order.u32(&body[8..12]) as u64;
+                let captured = order.u32(&body[12..16]) as usize;
+                let Some(frame) = body.get(20..20 + captured) else {
+                    continue;
+                };
+                last_micros = interface.micros(ticks);
+                decode_frame(&mut capture, interface.link_type, last_micros, frame);
+            }
+            PCAPNG_SIMPLE_PACKET if body.len() >= 4 => {
+                // No timestamp, file it with the packet before
+                let Some(interface) = interfaces.first() else {
+                    continue;
+                };
+                let original = order.u32(&body[0..4]) as usize;
+                let frame = &body[4..];
+                let frame = &frame[..original.min(frame.len())];
+                decode_frame(&mut capture, interface.link_type, last_micros, frame);
+            }
+            _ => {}
+        }
+    }
+    Ok(capture)
+}
+
+/// Adds a captured frame to the capture if it holds an E1.31 packet, read as
+/// leniently as live packets are. Frames to the sACN port that the parser
+/// rejects are counted instead.
+fn decode_frame(capture: &mut LoadedCapture, link_type: u32, micros: i64, frame: &[u8]) {
+    let Some((source, payload)) = udp_payload(link_type, frame) else {
+        return;
+    };
+    let Some(timestamp) = DateTime::from_timestamp_micros(micros) else {
+        return;
+    };
+    match e131::parse_lenient(payload) {
+        Ok(packet) => capture.records.push(CaptureRecord {
+            timestamp,
+            source,
+            universe: packet_universe(&packet),
+            payload: payload.to_vec(),
+        }),
+        Err(e) => {
+            debug!(
+                "Rejected captured packet from {} at {}: {}",
+                source, timestamp, e
+            );
+            *capture.rejected.entry(e.to_string()).or_insert(0) += 1;
+        }
+    }
+}
+
+/// Returns the source address and payload of a UDP datagram to the sACN port.
+fn udp_payload(link_type: u32, frame: &[u8]) -> Option<(SocketAddr, &[u8])> {
+    let ip = match link_type {
+        LINKTYPE_ETHERNET => {
+            let mut ethertype = u16::from_be_bytes([*frame.get(12)?, *frame.get(13)?]);
+            let mut offset = 14;
+            while ethertype == ETHERTYPE_VLAN {
+                ethertype = u16::from_be_bytes([*frame.get(offset + 2)?, *frame.get(offset + 3)?]);
+                offset += 4;
+            }
+            match ethertype {
+                ETHERTYPE_IPV4 | ETHERTYPE_IPV6 => frame.get(offset..)?,
+                _ => return None,
+            }
+        }
+        LINKTYPE_LINUX_SLL => frame.get(16..)?,
+        LINKTYPE_LINUX_SLL2 => frame.get(20..)?,
+        LINKTYPE_NULL => frame.get(4..)?,
+        LINKTYPE_RAW | LINKTYPE_RAW_OPENBSD | LINKTYPE_IPV4 | LINKTYPE_IPV6 => frame,
+        _ => return None,
+    };
+
+    let (source_ip, udp) = match ip.first()? >> 4 {
+        4 => {
+            let header_len = (ip[0] & 0x0F) as usize * 4;
+            let total_len = u16::from_be_bytes([*ip.get(2)?, *ip.get(3)?]) as usize;
+            let fragment = u16::from_be_bytes([*ip.get(6)?, *ip.get(7)?]);
+            // Skip fragments, sACN packets fit in one datagram
+            if *ip.get(9)? != IP_PROTOCOL_UDP || fragment & 0x3FFF != 0 {
+                return None;
+            }
+            let source: [u8; 4] = ip.get(12..16)?.try_into().ok()?;
+            let end = total_len.min(ip.len());
+            (IpAddr::V4(Ipv4Addr::from(source)), ip.get(header_len..end)?)
+        }
+        6 => {
+            // Extension headers aren't followed, multicast sACN doesn't use them
+            if *ip.get(6)? != IP_PROTOCOL_UDP {
+                return None;
+            }
+            let payload_len = u16::from_be_bytes([*ip.get(4)?, *ip.get(5)?]) as usize;
+            let source: [u8; 16] = ip.get(8..24)?.try_into().ok()?;
+            let end = (40 + payload_len).min(ip.len());
+            (IpAddr::V6(Ipv6Addr::from(source)), ip.get(40..end)?)
+        }
+        _ => return None,
+    };
+
+    if udp.len() < 8 {
+        return None;
+    }
+    let source_port = u16::from_be_bytes([udp[0], udp[1]]);
+    let dest_port = u16::from_be_bytes([udp[2], udp[3]]);
+    if dest_port != ACN_SDT_MULTICAST_PORT {
+        return None;
+    }
+    let udp_len = u16::from_be_bytes([udp[4], udp[5]]) as usize;
+    let end = udp_len.clamp(8, udp.len());
+
+    Some((SocketAddr::new(source_ip, source_port), udp.get(8..end)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::e131::fixtures::data_fixture;
+
+    /// An IPv4 UDP datagram from 10.0.0.5:49152 to `port`.
+    fn ipv4_udp(port: u16, payload: &[u8]) -> Vec<u8> {
+        let udp_len = 8 + payload.len() as u16;
+        let total_len = 20 + udp_len;
+        [
+            &[0x45, 0x00][..],
+            &total_len.to_be_bytes(),
+            &[0x00, 0x00, 0x00, 0x00, 0x40, IP_PROTOCOL_UDP, 0x00, 0x00],
+            &[10, 0, 0, 5, 239, 255, 0, 1],
+            &49152u16.to_be_bytes(),
+            &port.to_be_bytes(),
+            &udp_len.to_be_bytes(),
+            &[0x00, 0x00],
+            payload,
+        ]
+        .concat()
+    }
+
+    /// A little-endian, microsecond pcap file of raw IP frames.
+    fn pcap_file(frames: &[(u32, u32, Vec<u8>)]) -> Vec<u8> {
+        let mut file = [
+            &PCAP_MAGIC_MICROS.to_le_bytes()[..],
+            &[0x02, 0x00, 0x04, 0x00],
+            &[0; 8],
+            &65535u32.to_le_bytes(),
+            &LINKTYPE_RAW.to_le_bytes(),
+        ]
+        .concat();
+        for (seconds, micros, frame) in frames {
+            file.extend(seconds.to_le_bytes());
+            file.extend(micros.to_le_bytes());
+            file.extend((frame.len() as u32).to_le_bytes());
+            file.extend((frame.len() as u32).to_le_bytes());
+            file.extend(frame);
+        }
+        file
+    }
+
+    #[test]
+    fn reads_sacn_packets() {
+        let payload = data_fixture();
+        let file = pcap_file(&[
+            (
+                1_700_000_000,
+                250_000,
+                ipv4_udp(ACN_SDT_MULTICAST_PORT, &payload),
+            ),
+            (1_700_000_000, 300_000, ipv4_udp(6454, b"Art-Net\0")),
+        ]);
+
+        let capture = parse_pcap(&file).unwrap();
+        assert_eq!(capture.records.len(), 1, "other ports are skipped");
+        assert!(capture.rejected.is_empty());
+
+        let record = &capture.records[0];
+        assert_eq!(record.timestamp.timestamp_micros(), 1_700_000_000_250_000);
+        assert_eq!(record.source, "10.0.0.5:49152".parse().unwrap());
+        assert_eq!(record.universe, 1);
+        assert_eq!(record.payload, payload);
+    }
+
+    #[test]
+    fn counts_rejected_packets() {
+        let mut bad_preamble = data_fixture();
+        bad_preamble[1] = 0x20;
+        let file = pcap_file(&[
+            (1, 0, ipv4_udp(ACN_SDT_MULTICAST_PORT, &bad_preamble)),
+            (2, 0, ipv4_udp(ACN_SDT_MULTICAST_PORT, &data_fixture())),
+            (3, 0, ipv4_udp(ACN_SDT_MULTICAST_PORT, &bad_preamble)),
+            (4, 0, ipv4_udp(ACN_SDT_MULTICAST_PORT, &[0x00, 0x10])),
+        ]);
+
+        let capture = parse_pcap(&file).unwrap();
+        assert_eq!(capture.records.len(), 1);
+        assert_eq!(capture.rejected.values().sum::<usize>(), 3);
+        assert_eq!(capture.rejected["Invalid preamble size 0x0020"], 2);
+    }
+}
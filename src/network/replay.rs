@@ -0,0 +1,256 @@
+use super::capture::{self, CaptureRecord, LoadedCapture};
+use crate::core::NETWORK_DATA_LOSS_TIMEOUT;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, HashMap};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Slowest and fastest playback speeds offered.
+pub const MIN_REPLAY_SPEED: f32 = 0.1;
+pub const MAX_REPLAY_SPEED: f32 = 10.0;
+
+/// Slices of the timeline packet activity is counted in.
+const ACTIVITY_BUCKETS: usize = 200;
+
+/// A loaded capture being played back, on the network or into the viewer.
+pub struct Replay {
+    path: PathBuf,
+    records: Vec<CaptureRecord>, // sorted by timestamp
+    rejected: BTreeMap<String, usize>,
+    offsets: Vec<Duration>, // of each record from the first
+    universes: BTreeMap<u16, usize>,
+    activity: Vec<usize>,
+    next: usize,
+    position: Duration,                         // while paused
+    playing_since: Option<(Instant, Duration)>, // wall clock and position when play started
+    resync: bool,                               // send the current state after a seek
+    speed: f32,
+    pub looping: bool,
+    pub offline: bool,             // feed the viewer instead of transmitting
+    pub remap: BTreeMap<u16, u16>, // recorded universe to played universe
+}
+
+impl Replay {
+    pub fn open(path: &Path) -> Result<Self> {
+        let LoadedCapture {
+            mut records,
+            rejected,
+        } = capture::load_capture(path)?;
+        if records.is_empty() {
+            let rejected: usize = rejected.values().sum();
+            if rejected > 0 {
+                return Err(anyhow::anyhow!(
+                    "No valid sACN packets in {}, {} rejected by the E1.31 parser",
+                    path.display(),
+                    rejected
+                ));
+            }
+            return Err(anyhow::anyhow!("No sACN packets in {}", path.display()));
+        }
+        records.sort_by_key(|record| record.timestamp);
+
+        let mut universes = BTreeMap::new();
+        for record in &records {
+            *universes.entry(record.universe).or_insert(0) += 1;
+        }
+
+        let first = records[0].timestamp;
+        let offsets: Vec<Duration> = records
+            .iter()
+            .map(|record| (record.timestamp - first).to_std().unwrap_or_default())
+            .collect();
+
+        let duration = offsets.last().copied().unwrap_or_default().as_secs_f64();
+        let mut activity = vec![0; ACTIVITY_BUCKETS];
+        for offset in &offsets {
+            let bucket = if duration > 0.0 {
+                (offset.as_secs_f64() / duration * ACTIVITY_BUCKETS as f64) as usize
+            } else {
+                0
+            };
+            activity[bucket.min(ACTIVITY_BUCKETS - 1)] += 1;
+        }
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            records,
+            rejected,
+            offsets,
+            universes,
+            activity,
+            next: 0,
+            position: Duration::ZERO,
+            playing_since: None,
+            resync: false,
+            speed: 1.0,
+            looping: false,
+            offline: false,
+            remap: BTreeMap::new(),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn packet_count(&self) -> usize {
+        self.records.len()
+    }
+
+    /// Packets to the sACN port the E1.31 parser rejected, counted by reason.
+    pub fn rejected(&self) -> &BTreeMap<String, usize> {
+        &self.rejected
+    }
+
+    pub fn rejected_count(&self) -> usize {
+        self.rejected.values().sum()
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.offsets.last().copied().unwrap_or_default()
+    }
+
+    /// When the first packet was captured.
+    pub fn start_time(&self) -> DateTime<Utc> {
+        self.records[0].timestamp
+    }
+
+    /// Packet counts per recorded universe.
+    pub fn universes(&self) -> &BTreeMap<u16, usize> {
+        &self.universes
+    }
+
+    /// Packet counts in equal slices of the timeline.
+    pub fn activity(&self) -> &[usize] {
+        &self.activity
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playing_since.is_some()
+    }
+
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
+
+    pub fn position(&self, now: Instant) -> Duration {
+        match self.playing_since {
+            Some((since, position)) => {
+                (position + now.duration_since(since).mul_f32(self.speed)).min(self.duration())
+            }
+            None => self.position,
+        }
+    }
+
+    /// When the packets at the current position were captured.
+    pub fn capture_time(&self, now: Instant) -> DateTime<Utc> {
+        self.start_time() + chrono::Duration::from_std(self.position(now)).unwrap_or_default()
+    }
+
+    pub fn play(&mut self, now: Instant) {
+        if self.is_playing() {
+            return;
+        }
+        if self.next >= self.records.len() {
+            self.seek(Duration::ZERO, now);
+        }
+        self.playing_since = Some((now, self.position));
+    }
+
+    pub fn pause(&mut self, now: Instant) {
+        self.position = self.position(now);
+        self.playing_since = None;
+    }
+
+    pub fn set_speed(&mut self, speed: f32, now: Instant) {
+        let position = self.position(now);
+        self.speed = speed.clamp(MIN_REPLAY_SPEED, MAX_REPLAY_SPEED);
+        if self.is_playing() {
+            self.playing_since = Some((now, position));
+        }
+    }
+
+    /// Jumps to a position. The next [`Replay::due`] call first returns the
+    /// latest packet of every source and universe still live at that point
+    /// (including packets exactly at it), so outputs and grids show the
+    /// recorded state straight away.
+    pub fn seek(&mut self, position: Duration, now: Instant) {
+        let position = position.min(self.duration());
+        self.next = self.offsets.partition_point(|&offset| offset <= position);
+        self.resync = true;
+        self.position = position;
+        if self.is_playing() {
+            self.playing_since = Some((now, position));
+        }
+    }
+
+    /// Whether the next [`Replay::due`] call starts over from a seek.
+    pub fn is_resyncing(&self) -> bool {
+        self.resync
+    }
+
+    /// Returns the packets due by now, in order, and advances past them.
+    pub fn due(&mut self, now: Instant) -> Vec<CaptureRecord> {
+        let mut due = Vec::new();
+        if self.resync {
+            self.resync = false;
+            due.extend(self.live_before(self.next));
+        }
+        if !self.is_playing() {
+            return due;
+        }
+
+        let position = self.position(now);
+        while self.next < self.records.len() && self.offsets[self.next] <= position {
+            due.push(self.records[self.next].clone());
+            self.next += 1;
+        }
+
+        if self.next >= self.records.len() {
+            if self.looping {
+                self.seek(Duration::ZERO, now);
+            } else {
+                self.pause(now);
+            }
+        }
+        due
+    }
+
+    /// How long until the next packet is due, None while paused.
+    pub fn next_due_in(&self, now: Instant) -> Option<Duration> {
+        if !self.is_playing() {
+            return None;
+        }
+        let offset = self.offsets.get(self.next)?;
+        Some(
+            offset
+                .saturating_sub(self.position(now))
+                .div_f32(self.speed),
+        )
+    }
+
+    /// The last packet of each source and universe before `index`, skipping
+    /// any older than the network data loss timeout.
+    fn live_before(&self, index: usize) -> Vec<CaptureRecord> {
+        let Some(end) = index.checked_sub(1).map(|i| self.offsets[i]) else {
+            return Vec::new();
+        };
+        let window = NETWORK_DATA_LOSS_TIMEOUT.to_std().unwrap_or_default();
+
+        let start = self.offsets[..index].partition_point(|&offset| offset + window < end);
+
+        let mut latest: HashMap<(SocketAddr, u16), usize> = HashMap::new();
+        for (i, record) in self.records.iter().enumerate().take(index).skip(start) {
+            latest.insert((record.source, record.universe), i);
+        }
+
+        let mut indices: Vec<usize> = latest.into_values().collect();
+        indices.sort();
+        indices
+            .into_iter()
+            .map(|i| self.records[i].clone())
+            .collect()
+    }
+}
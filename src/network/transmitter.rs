@@ -0,0 +1,424 @@
+use super::artnet::{self, ARTNET_PORT};
+use super::DMX_START_CODE;
+use crate::core::{ArtNetOutput, PortAddress, TransmitUniverse};
+use anyhow::Result;
+use log::warn;
+use sacn::source::SacnSource;
+use std::collections::{BTreeSet, HashMap};
+use std::net::{IpAddr, SocketAddr, UdpSocket};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// Highest refresh rate offered, the DMX512 maximum.
+pub const MAX_SEND_RATE: u32 = 44;
+
+/// Once levels stop changing the last frame is repeated this many times
+/// before dropping to keep-alives (ANSI E1.31-2018 Section 6.6.1).
+const UNCHANGED_REPEAT_COUNT: u32 = 3;
+
+/// Interval between keep-alive packets while levels are static.
+const KEEP_ALIVE_INTERVAL: Duration = Duration::from_millis(800);
+
+/// Who the packets come from: the session's CID, name and adapter, and
+/// where Art-Net output goes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceIdentity {
+    pub cid: Uuid,
+    pub source_name: String,
+    pub bind_ip: IpAddr,
+    pub artnet_broadcast: IpAddr,
+    pub artnet_universe_offset: u16,
+}
+
+struct Stream {
+    sent: TransmitUniverse,
+    unchanged: u32,
+    last_sent: Instant,
+}
+
+impl Stream {
+    fn matches(&self, universe: &TransmitUniverse) -> bool {
+        self.sent.levels == universe.levels
+            && self.sent.priority == universe.priority
+            && self.sent.preview == universe.preview
+            && self.sent.sync_universe == universe.sync_universe
+            && self.sent.artnet == universe.artnet
+    }
+}
+
+/// Whether a universe's next frame is due, given its stream so far. Changed
+/// levels are due straight away, static levels are repeated a few times and
+/// then only refreshed at the keep-alive interval.
+fn send_due(stream: Option<&Stream>, universe: &TransmitUniverse, now: Instant) -> bool {
+    match stream {
+        None => true,
+        Some(stream) if !stream.matches(universe) => true,
+        Some(stream) if stream.unchanged < UNCHANGED_REPEAT_COUNT => true,
+        Some(stream) => now.duration_since(stream.last_sent) >= KEEP_ALIVE_INTERVAL,
+    }
+}
+
+/// The stream after sending a universe's frame at `now`.
+fn after_send(stream: Option<&Stream>, universe: &TransmitUniverse, now: Instant) -> Stream {
+    let unchanged = match stream {
+        Some(stream) if stream.matches(universe) => stream.unchanged + 1,
+        _ => 0,
+    };
+    Stream {
+        sent: universe.clone(),
+        unchanged,
+        last_sent: now,
+    }
+}
+
+/// Owns the single `SacnSource` this app transmits with, so every universe
+/// shares one CID and sequence numbers carry on between packets. Art-Net
+/// output goes through a socket of its own.
+pub struct Transmitter {
+    source: Option<SacnSource>,
+    cid: Uuid,
+    bind_ip: IpAddr,
+    streams: HashMap<u16, Stream>,
+    artnet_socket: Option<(IpAddr, UdpSocket)>, // and the adapter it's bound to
+    artnet_sequences: HashMap<PortAddress, u8>,
+    bridged: BTreeSet<u16>, // sACN universes the bridge is sending
+}
+
+impl Transmitter {
+    pub fn new() -> Self {
+        Self {
+            source: None,
+            cid: Uuid::nil(),
+            bind_ip: IpAddr::from([0, 0, 0, 0]),
+            streams: HashMap::new(),
+            artnet_socket: None,
+            artnet_sequences: HashMap::new(),
+            bridged: BTreeSet::new(),
+        }
+    }
+
+    /// Returns the source, creating it on first use and recreating it if the
+    /// adapter or CID changed since it was bound.
+    fn source(&mut self, identity: &SourceIdentity) -> Result<&mut SacnSource> {
+        if self.source.is_some() && (self.cid != identity.cid || self.bind_ip != identity.bind_ip) {
+            self.shutdown();
+        }
+
+        if self.source.is_none() {
+            // Let the OS choose a port so we don't clash with the listener
+            let bind_addr = SocketAddr::new(identity.bind_ip, 0);
+            let source = SacnSource::with_cid_ip(&identity.source_name, identity.cid, bind_addr)
+                .map_err(|e| anyhow::anyhow!("Failed to create sACN source: {}", e))?;
+            self.source = Some(source);
+            self.cid = identity.cid;
+            self.bind_ip = identity.bind_ip;
+        }
+
+        let source = self
+            .source
+            .as_mut()
+            .ok_or_else(|| anyhow::anyhow!("sACN source unavailable"))?;
+        if source.name().ok().as_deref() != Some(identity.source_name.as_str()) {
+            source
+                .set_name(&identity.source_name)
+                .map_err(|e| anyhow::anyhow!("Failed to set source name: {}", e))?;
+        }
+        Ok(source)
+    }
+
+    /// Sends a single packet without starting a stream.
+    pub fn send(&mut self, identity: &SourceIdentity, universe: &TransmitUniverse) -> Result<()> {
+        let source = self.source(identity)?;
+        source.register_universe(universe.universe).map_err(|e| {
+            anyhow::anyhow!("Failed to register universe {}: {}", universe.universe, e)
+        })?;
+
+        // Preview is a source-wide flag in the sacn crate, set it per packet
+        source
+            .set_preview_mode(universe.preview)
+            .map_err(|e| anyhow::anyhow!("Failed to set preview mode: {}", e))?;
+
+        let mut data = vec![DMX_START_CODE];
+        data.extend_from_slice(&universe.levels);
+
+        let dst_ip = None; // Use multicast
+        let sync_uni = match universe.sync_universe {
+            0 => None,
+            sync_universe => {
+                source.register_universe(sync_universe).map_err(|e| {
+                    anyhow::anyhow!("Failed to register sync universe {}: {}", sync_universe, e)
+                })?;
+                Some(sync_universe)
+            }
+        };
+        source
+            .send(
+                &[universe.universe],
+                &data,
+                Some(universe.priority),
+                dst_ip,
+                sync_uni,
+            )
+            .map_err(|e| anyhow::anyhow!("Failed to send DMX data: {}", e))?;
+
+        self.send_artnet(identity, universe)
+    }
+
+    /// Returns the Art-Net socket, binding it to the adapter on first use
+    /// and again if the adapter changed.
+    fn artnet_socket(&mut self, bind_ip: IpAddr) -> Result<&UdpSocket> {
+        if self
+            .artnet_socket
+            .as_ref()
+            .is_some_and(|(ip, _)| *ip != bind_ip)
+        {
+            self.artnet_socket = None;
+        }
+
+        if self.artnet_socket.is_none() {
+            // Let the OS choose a port so we don't clash with the Art-Net listener
+            let socket = UdpSocket::bind(SocketAddr::new(bind_ip, 0))
+                .and_then(|socket| socket.set_broadcast(true).map(|()| socket))
+                .map_err(|e| anyhow::anyhow!("Failed to create Art-Net sender: {}", e))?;
+            self.artnet_socket = Some((bind_ip, socket));
+        }
+
+        self.artnet_socket
+            .as_ref()
+            .map(|(_, socket)| socket)
+            .ok_or_else(|| anyhow::anyhow!("Art-Net sender unavailable"))
+    }
+
+    /// Sends a universe as ArtDmx if it has an Art-Net output, on the port
+    /// address its universe number maps to.
+    fn send_artnet(
+        &mut self,
+        identity: &SourceIdentity,
+        universe: &TransmitUniverse,
+    ) -> Result<()> {
+        if universe.artnet == ArtNetOutput::Off {
+            return Ok(());
+        }
+        let port_address =
+            PortAddress::from_universe(universe.universe, identity.artnet_universe_offset)
+                .ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "Universe {} has no Art-Net port address with offset {}",
+                        universe.universe,
+                        identity.artnet_universe_offset
+                    )
+                })?;
+        self.send_artdmx(identity, universe.artnet, port_address, &universe.levels)
+    }
+
+    /// Sends one ArtDmx packet to a port address.
+    pub fn send_artdmx(
+        &mut self,
+        identity: &SourceIdentity,
+        output: ArtNetOutput,
+        port_address: PortAddress,
+        levels: &[u8],
+    ) -> Result<()> {
+        let target = match output {
+            ArtNetOutput::Off => return Ok(()),
+            ArtNetOutput::Broadcast => identity.artnet_broadcast,
+            ArtNetOutput::Unicast(ip) => ip,
+        };
+
+        // Sequence numbers run 1-255, 0 would turn resequencing off
+        let sequence = self.artnet_sequences.entry(port_address).or_insert(0);
+        *sequence = sequence.wrapping_add(1).max(1);
+        let packet = artnet::dmx_packet(*sequence, port_address, levels);
+
+        self.artnet_socket(identity.bind_ip)?
+            .send_to(&packet, SocketAddr::new(target, ARTNET_PORT))
+            .map(|_| ())
+            .map_err(|e| anyhow::anyhow!("Failed to send Art-Net to {}: {}", target, e))
+    }
+
+    /// Sends a synchronization packet, latching every universe sent with
+    /// this sync universe since the last one.
+    pub fn sync(&mut self, identity: &SourceIdentity, sync_universe: u16) -> Result<()> {
+        let source = self.source(identity)?;
+        source.register_universe(sync_universe).map_err(|e| {
+            anyhow::anyhow!("Failed to register sync universe {}: {}", sync_universe, e)
+        })?;
+        source.send_sync_packet(sync_universe, None).map_err(|e| {
+            anyhow::anyhow!("Failed to send sync on universe {}: {}", sync_universe, e)
+        })
+    }
+
+    /// Sends the next frame of a continuous stream if one is due, see
+    /// [`send_due`]. Returns true if a packet was sent.
+    pub fn stream(
+        &mut self,
+        identity: &SourceIdentity,
+        universe: &TransmitUniverse,
+        now: Instant,
+    ) -> Result<bool> {
+        if !send_due(self.streams.get(&universe.universe), universe, now) {
+            return Ok(false);
+        }
+
+        self.send(identity, universe)?;
+
+        let stream = after_send(self.streams.get(&universe.universe), universe, now);
+        self.streams.insert(universe.universe, stream);
+        Ok(true)
+    }
+
+    pub fn streaming_universes(&self) -> Vec<u16> {
+        let mut universes: Vec<u16> = self.streams.keys().copied().collect();
+        universes.sort();
+        universes
+    }
+
+    /// Ends a stream by sending stream termination packets.
+    pub fn stop_stream(&mut self, universe: u16) -> Result<()> {
+        if self.streams.remove(&universe).is_none() {
+            return Ok(());
+        }
+        self.terminate(universe)
+    }
+
+    fn terminate(&mut self, universe: u16) -> Result<()> {
+        if let Some(source) = self.source.as_mut() {
+            source
+                .terminate_stream(universe, DMX_START_CODE)
+                .map_err(|e| anyhow::anyhow!("Failed to terminate universe {}: {}", universe, e))?;
+        }
+        Ok(())
+    }
+
+    /// Sends a frame of bridged sACN output. The bridge re-sends at its own
+    /// fixed rate, so these aren't tracked as streams.
+    pub fn send_bridged(
+        &mut self,
+        identity: &SourceIdentity,
+        universe: &TransmitUniverse,
+    ) -> Result<()> {
+        self.send(identity, universe)?;
+        self.bridged.insert(universe.universe);
+        Ok(())
+    }
+
+    pub fn bridged_universes(&self) -> Vec<u16> {
+        self.bridged.iter().copied().collect()
+    }
+
+    /// Ends bridged output on a universe. Receivers only see it terminated
+    /// if the bridge was its only sender: a universe being streamed goes
+    /// back to its stream's levels on the next tick, and `session`, the
+    /// transmit session's copy of a universe that's only sent by hand, has
+    /// its levels sent once in place of the bridged ones.
+    pub fn stop_bridged(
+        &mut self,
+        identity: &SourceIdentity,
+        universe: u16,
+        session: Option<&TransmitUniverse>,
+    ) -> Result<()> {
+        if !self.bridged.remove(&universe) {
+            return Ok(());
+        }
+        if let Some(stream) = self.streams.get_mut(&universe) {
+            // Resent as soon as the transmitter next runs
+            stream.unchanged = 0;
+            return Ok(());
+        }
+        match session {
+            Some(session) => self.send(identity, session),
+            None => self.terminate(universe),
+        }
+    }
+
+    /// Terminates every stream and releases the source.
+    pub fn shutdown(&mut self) {
+        for universe in self.streaming_universes() {
+            if let Err(e) = self.stop_stream(universe) {
+                warn!("{}", e);
+            }
+        }
+        for universe in std::mem::take(&mut self.bridged) {
+            if let Err(e) = self.terminate(universe) {
+                warn!("{}", e);
+            }
+        }
+        // Dropping the source terminates anything sent with one-off packets
+        self.source = None;
+        self.artnet_socket = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// When frames go out over `ticks` calls at 44 Hz, in ms from the start,
+    /// with channel 1 raised to full before tick `change_at`.
+    fn sent_at(ticks: u64, change_at: Option<u64>) -> Vec<u64> {
+        let mut universe = TransmitUniverse::new(1);
+        let start = Instant::now();
+        let mut stream: Option<Stream> = None;
+        let mut sent = Vec::new();
+        for tick in 0..ticks {
+            if change_at == Some(tick) {
+                universe.levels[0] = 255;
+            }
+            let ms = tick * 1000 / 44;
+            let now = start + Duration::from_millis(ms);
+            if send_due(stream.as_ref(), &universe, now) {
+                stream = Some(after_send(stream.as_ref(), &universe, now));
+                sent.push(ms);
+            }
+        }
+        sent
+    }
+
+    #[test]
+    fn repeats_static_levels_then_keeps_alive() {
+        let sent = sent_at(88, None);
+
+        // The first frame and three repeats, then one every 800 ms
+        assert_eq!(&sent[..4], &[0, 22, 45, 68]);
+        assert_eq!(&sent[4..], &[886, 1704]);
+    }
+
+    #[test]
+    fn resends_changed_levels_straight_away() {
+        let sent = sent_at(44, Some(20));
+
+        // Quiet after the burst until the change at tick 20 (454 ms), which
+        // starts a new burst
+        assert_eq!(sent, vec![0, 22, 45, 68, 454, 477, 500, 522]);
+    }
+
+    #[test]
+    fn resends_changed_options() {
+        let mut universe = TransmitUniverse::new(1);
+        let start = Instant::now();
+        let mut stream = after_send(None, &universe, start);
+        for _ in 0..UNCHANGED_REPEAT_COUNT {
+            stream = after_send(Some(&stream), &universe, start);
+        }
+        assert!(!send_due(Some(&stream), &universe, start));
+
+        universe.priority = 150;
+        assert!(send_due(Some(&stream), &universe, start));
+        assert_eq!(after_send(Some(&stream), &universe, start).unchanged, 0);
+    }
+
+    #[test]
+    fn stopping_a_stream_forgets_it() {
+        let mut transmitter = Transmitter::new();
+        let universe = TransmitUniverse::new(3);
+        transmitter
+            .streams
+            .insert(3, after_send(None, &universe, Instant::now()));
+        assert_eq!(transmitter.streaming_universes(), vec![3]);
+
+        // No source was bound, so there's nothing to terminate on the wire
+        transmitter.stop_stream(3).unwrap();
+        assert!(transmitter.streaming_universes().is_empty());
+        transmitter.stop_stream(3).unwrap();
+    }
+}
@@ -0,0 +1,202 @@
+use crate::core::{
+    AppState, ArtNetOutput, BridgeDirection, BridgeMapping, PortAddress, MAX_BRIDGE_COUNT,
+    MAX_PRIORITY, MAX_UNIVERSE, MIN_UNIVERSE,
+};
+use crate::network::MAX_SEND_RATE;
+use eframe::egui;
+use std::net::IpAddr;
+
+/// Edits the sACN ⇄ Art-Net bridge mappings and shows what each one is
+/// sending.
+pub fn show_bridge(ui: &mut egui::Ui, state: &mut AppState) {
+    let mut changed = false;
+
+    ui.horizontal(|ui| {
+        let mut enabled = state.bridge_enabled;
+        if ui.checkbox(&mut enabled, "Run bridge").changed() {
+            state.set_bridge_enabled(enabled);
+        }
+
+        let mut rate = state.bridge_rate;
+        if ui
+            .add(
+                egui::DragValue::new(&mut rate)
+                    .range(1..=MAX_SEND_RATE)
+                    .suffix(" Hz"),
+            )
+            .on_hover_text("Every live mapping is re-sent at this rate")
+            .changed()
+        {
+            state.set_bridge_rate(rate);
+        }
+
+        if ui.button("Add Mapping").clicked() {
+            state.add_bridge_mapping(BridgeMapping::new());
+        }
+    });
+
+    if state.bridge_mappings.is_empty() {
+        ui.label("No mappings, add one to convert between sACN and Art-Net");
+        return;
+    }
+
+    let nodes: Vec<(String, IpAddr)> = state
+        .artnet_nodes()
+        .into_iter()
+        .map(|device| (device.name.clone(), device.ip))
+        .collect();
+    let running = state.bridge_enabled;
+    let statuses = state.bridge_status.clone();
+
+    let mut remove = None;
+    egui::Grid::new("bridge_mappings")
+        .num_columns(8)
+        .striped(true)
+        .show(ui, |ui| {
+            ui.strong("On");
+            ui.strong("Direction");
+            ui.strong("sACN");
+            ui.strong("Art-Net");
+            ui.strong("Count");
+            ui.strong("Output");
+            ui.strong("Status");
+            ui.label("");
+            ui.end_row();
+
+            for (index, mapping) in state.bridge_mappings.iter_mut().enumerate() {
+                changed |= ui.checkbox(&mut mapping.enabled, "").changed();
+
+                egui::ComboBox::from_id_source(("bridge_direction", index))
+                    .selected_text(mapping.direction.to_string())
+                    .show_ui(ui, |ui| {
+                        for direction in
+                            [BridgeDirection::SacnToArtNet, BridgeDirection::ArtNetToSacn]
+                        {
+                            changed |= ui
+                                .selectable_value(
+                                    &mut mapping.direction,
+                                    direction,
+                                    direction.to_string(),
+                                )
+                                .changed();
+                        }
+                    });
+
+                changed |= ui
+                    .add(
+                        egui::DragValue::new(&mut mapping.sacn_universe)
+                            .range(MIN_UNIVERSE..=MAX_UNIVERSE),
+                    )
+                    .on_hover_text("First sACN universe")
+                    .changed();
+
+                ui.horizontal(|ui| {
+                    let (mut net, mut sub_net, mut universe) = (
+                        mapping.port_address.net(),
+                        mapping.port_address.sub_net(),
+                        mapping.port_address.universe(),
+                    );
+                    let mut part_changed = false;
+                    part_changed |= ui
+                        .add(egui::DragValue::new(&mut net).range(0..=127))
+                        .on_hover_text("Net")
+                        .changed();
+                    part_changed |= ui
+                        .add(egui::DragValue::new(&mut sub_net).range(0..=15))
+                        .on_hover_text("Sub-Net")
+                        .changed();
+                    part_changed |= ui
+                        .add(egui::DragValue::new(&mut universe).range(0..=15))
+                        .on_hover_text("Universe")
+                        .changed();
+                    if part_changed {
+                        mapping.port_address = PortAddress::from_parts(net, sub_net, universe);
+                        changed = true;
+                    }
+                });
+
+                changed |= ui
+                    .add(egui::DragValue::new(&mut mapping.count).range(1..=MAX_BRIDGE_COUNT))
+                    .on_hover_text("Number of consecutive universes mapped")
+                    .changed();
+
+                match mapping.direction {
+                    BridgeDirection::SacnToArtNet => {
+                        egui::ComboBox::from_id_source(("bridge_output", index))
+                            .selected_text(match mapping.artnet_output {
+                                ArtNetOutput::Unicast(ip) => nodes
+                                    .iter()
+                                    .find(|(_, node_ip)| *node_ip == ip)
+                                    .map_or_else(|| ip.to_string(), |(name, _)| name.clone()),
+                                output => output.to_string(),
+                            })
+                            .show_ui(ui, |ui| {
+                                changed |= ui
+                                    .selectable_value(
+                                        &mut mapping.artnet_output,
+                                        ArtNetOutput::Broadcast,
+                                        "Broadcast",
+                                    )
+                                    .changed();
+                                for (name, ip) in &nodes {
+                                    changed |= ui
+                                        .selectable_value(
+                                            &mut mapping.artnet_output,
+                                            ArtNetOutput::Unicast(*ip),
+                                            format!("{} ({})", name, ip),
+                                        )
+                                        .changed();
+                                }
+                            });
+                    }
+                    BridgeDirection::ArtNetToSacn => {
+                        changed |= ui
+                            .add(
+                                egui::DragValue::new(&mut mapping.priority)
+                                    .range(0..=MAX_PRIORITY)
+                                    .prefix("priority "),
+                            )
+                            .changed();
+                    }
+                }
+
+                let status = statuses.get(index);
+                let (color, text) = match status {
+                    _ if !running || !mapping.enabled => (egui::Color32::GRAY, "off".to_string()),
+                    Some(status) if status.error.is_some() => {
+                        (egui::Color32::RED, status.error.clone().unwrap_or_default())
+                    }
+                    Some(status) if status.live == 0 => {
+                        (egui::Color32::YELLOW, "waiting for input".to_string())
+                    }
+                    Some(status) => (
+                        egui::Color32::GREEN,
+                        format!(
+                            "{}/{} live, {} frames",
+                            status.live,
+                            mapping.pairs().count(),
+                            status.frames
+                        ),
+                    ),
+                    None => (egui::Color32::GRAY, "starting".to_string()),
+                };
+                let label = ui
+                    .colored_label(color, text)
+                    .on_hover_text(mapping.to_string());
+                if status.is_some_and(|status| status.looped) {
+                    label.on_hover_text("Our own output was seen on an input and left out");
+                }
+
+                if ui.button("Remove").clicked() {
+                    remove = Some(index);
+                }
+                ui.end_row();
+            }
+        });
+
+    if let Some(index) = remove {
+        state.remove_bridge_mapping(index);
+    } else if changed {
+        state.save_bridge();
+    }
+}
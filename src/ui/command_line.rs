@@ -0,0 +1,92 @@
+use crate::core::AppState;
+use eframe::egui;
+
+/// Commands kept for recall with the arrow keys.
+const MAX_HISTORY: usize = 100;
+
+/// Console-style level entry, e.g. `1 thru 24 @ 50`.
+pub struct CommandLine {
+    text: String,
+    history: Vec<String>,
+    history_pos: Option<usize>, // index into history while browsing
+}
+
+impl CommandLine {
+    pub fn new() -> Self {
+        Self {
+            text: String::new(),
+            history: Vec::new(),
+            history_pos: None,
+        }
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui, state: &mut AppState, default_universe: u16) {
+        ui.horizontal(|ui| {
+            ui.label("Command:");
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut self.text)
+                    .hint_text("1 thru 24 @ 50, 12 + 14 @ full, U3/1 thru 512 @ 255")
+                    .desired_width(320.0),
+            );
+
+            if response.has_focus() {
+                let (up, down) = ui.input(|i| {
+                    (
+                        i.key_pressed(egui::Key::ArrowUp),
+                        i.key_pressed(egui::Key::ArrowDown),
+                    )
+                });
+                if up {
+                    self.recall(-1);
+                } else if down {
+                    self.recall(1);
+                }
+            }
+
+            let entered = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            if entered || ui.button("Go").clicked() {
+                self.run(state, default_universe);
+                response.request_focus();
+            }
+        });
+    }
+
+    fn run(&mut self, state: &mut AppState, default_universe: u16) {
+        let line = self.text.trim().to_string();
+        if line.is_empty() {
+            return;
+        }
+
+        if self.history.last() != Some(&line) {
+            self.history.push(line.clone());
+            if self.history.len() > MAX_HISTORY {
+                self.history.remove(0);
+            }
+        }
+        self.history_pos = None;
+
+        // Keep a failed command in the box so it can be corrected
+        if state.run_channel_command(&line, default_universe) {
+            self.text.clear();
+        }
+    }
+
+    /// Steps through the history, -1 for older and 1 for newer.
+    fn recall(&mut self, step: isize) {
+        if self.history.is_empty() {
+            return;
+        }
+
+        let pos = match (self.history_pos, step < 0) {
+            (None, true) => Some(self.history.len() - 1),
+            (None, false) => None,
+            (Some(0), true) => Some(0),
+            (Some(pos), true) => Some(pos - 1),
+            (Some(pos), false) if pos + 1 < self.history.len() => Some(pos + 1),
+            (Some(_), false) => None,
+        };
+
+        self.history_pos = pos;
+        self.text = pos.map(|p| self.history[p].clone()).unwrap_or_default();
+    }
+}
@@ -0,0 +1,114 @@
+use crate::core::SourceCompliance;
+use eframe::egui;
+
+/// What the user asked the compliance panel to do.
+pub enum ComplianceAction {
+    Export,
+    Reset,
+}
+
+/// Lists every source the compliance checker has seen with the E1.31 rules
+/// it broke.
+pub fn show_compliance(
+    ui: &mut egui::Ui,
+    sources: &[&SourceCompliance],
+) -> Option<ComplianceAction> {
+    let mut action = None;
+    ui.horizontal(|ui| {
+        let failing = sources
+            .iter()
+            .filter(|source| !source.violations.is_empty())
+            .count();
+        ui.label(format!(
+            "{} source(s), {} with violations",
+            sources.len(),
+            failing
+        ));
+        if ui
+            .button("Export Report")
+            .on_hover_text("Save a text report to the app's data directory")
+            .clicked()
+        {
+            action = Some(ComplianceAction::Export);
+        }
+        if ui.button("Reset").clicked() {
+            action = Some(ComplianceAction::Reset);
+        }
+    });
+
+    if sources.is_empty() {
+        ui.label("No sACN packets received yet");
+        return action;
+    }
+
+    egui::ScrollArea::vertical().show(ui, |ui| {
+        for source in sources {
+            let name = if source.source_name.is_empty() {
+                "<unnamed>"
+            } else {
+                &source.source_name
+            };
+            let count = source.violation_count();
+            let title = if count == 0 {
+                egui::RichText::new(format!("✔ {} ({})", name, source.source_ip))
+                    .color(egui::Color32::LIGHT_GREEN)
+            } else {
+                egui::RichText::new(format!(
+                    "✖ {} ({}) - {} violation(s)",
+                    name, source.source_ip, count
+                ))
+                .color(egui::Color32::LIGHT_RED)
+            };
+
+            egui::CollapsingHeader::new(title)
+                .id_source((source.source_ip, source.cid))
+                .show(ui, |ui| {
+                    ui.label(format!(
+                        "CID: {}",
+                        source
+                            .cid
+                            .map_or("unknown".to_string(), |cid| cid.to_string())
+                    ));
+                    ui.label(format!(
+                        "{} packet(s) checked since {}",
+                        source.packets,
+                        source
+                            .first_seen
+                            .with_timezone(&chrono::Local)
+                            .format("%H:%M:%S")
+                    ));
+
+                    if source.violations.is_empty() {
+                        ui.label("No violations");
+                        return;
+                    }
+                    egui::Grid::new(("compliance_grid", source.source_ip, source.cid))
+                        .num_columns(4)
+                        .striped(true)
+                        .show(ui, |ui| {
+                            ui.strong("Rule");
+                            ui.strong("Count");
+                            ui.strong("Last Seen");
+                            ui.strong("Latest");
+                            ui.end_row();
+
+                            for (rule, violation) in &source.violations {
+                                ui.label(rule.description());
+                                ui.label(violation.count.to_string());
+                                ui.label(
+                                    violation
+                                        .last_seen
+                                        .with_timezone(&chrono::Local)
+                                        .format("%H:%M:%S%.3f")
+                                        .to_string(),
+                                );
+                                ui.label(&violation.detail);
+                                ui.end_row();
+                            }
+                        });
+                });
+        }
+    });
+
+    action
+}
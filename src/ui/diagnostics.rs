@@ -0,0 +1,96 @@
+use crate::core::{Clocks, StreamStats, NETWORK_DATA_LOSS_TIMEOUT};
+use eframe::egui;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Table of receive statistics, one row per source and universe. Returns
+/// true if the user asked to reset the counters.
+pub fn show_stream_stats(
+    ui: &mut egui::Ui,
+    stats: &HashMap<(u16, Uuid), StreamStats>,
+    clocks: Clocks,
+) -> bool {
+    let reset = ui
+        .horizontal(|ui| {
+            ui.label(format!("{} stream(s)", stats.len()));
+            ui.button("Reset").clicked()
+        })
+        .inner;
+
+    if stats.is_empty() {
+        ui.label("No data packets received yet");
+        return reset;
+    }
+
+    let mut rows: Vec<&StreamStats> = stats.values().collect();
+    rows.sort_by_key(|row| (row.universe, *row.cid.as_bytes()));
+
+    egui::ScrollArea::both().show(ui, |ui| {
+        egui::Grid::new("stream_stats_grid")
+            .num_columns(10)
+            .striped(true)
+            .show(ui, |ui| {
+                for heading in [
+                    "Universe",
+                    "Source",
+                    "Packets",
+                    "Rate",
+                    "Interval",
+                    "Jitter",
+                    "Gaps",
+                    "Out of Order",
+                    "Duplicates",
+                    "Last Packet",
+                ] {
+                    ui.strong(heading);
+                }
+                ui.end_row();
+
+                for row in rows {
+                    let now = clocks.now_for(row.replayed);
+                    let since_last = now - row.last_seen;
+                    let stale = since_last > NETWORK_DATA_LOSS_TIMEOUT;
+
+                    ui.label(row.universe.to_string());
+                    ui.label(format!("{} ({})", row.source_name, row.source_ip))
+                        .on_hover_text(format!(
+                            "CID: {}\nFirst seen: {}",
+                            row.cid,
+                            row.first_seen
+                                .with_timezone(&chrono::Local)
+                                .format("%H:%M:%S%.3f")
+                        ));
+                    ui.label(row.packets.to_string());
+                    ui.label(format!("{}/s", row.packets_per_second(now)));
+                    ui.label(
+                        row.last_interval_ms()
+                            .map_or("-".to_string(), |ms| format!("{:.1} ms", ms)),
+                    );
+                    ui.label(format!("{:.2} ms", row.jitter_ms));
+                    counter(ui, row.gaps);
+                    counter(ui, row.out_of_order);
+                    counter(ui, row.duplicates);
+
+                    let text =
+                        format!("{:.1} s ago", since_last.num_milliseconds() as f32 / 1000.0);
+                    if stale {
+                        ui.colored_label(egui::Color32::RED, text);
+                    } else {
+                        ui.label(text);
+                    }
+                    ui.end_row();
+                }
+            });
+    });
+
+    reset
+}
+
+/// An error counter, highlighted once it is non-zero.
+fn counter(ui: &mut egui::Ui, count: u64) {
+    if count == 0 {
+        ui.label("0");
+    } else {
+        ui.colored_label(egui::Color32::YELLOW, count.to_string());
+    }
+}
@@ -0,0 +1,199 @@
+use crate::core::TransmitUniverse;
+use eframe::egui;
+use std::collections::BTreeSet;
+
+/// Faders shown per page.
+const FADERS_PER_PAGE: usize = 32;
+
+/// Step used by the coarse nudge buttons.
+const COARSE_STEP: i16 = 10;
+
+/// Paged bank of 512 faders with multi-select and group editing.
+pub struct FaderBank {
+    page: usize,
+    selected: BTreeSet<usize>, // 0-based channels
+    anchor: Option<usize>,     // last plain or ctrl click, start of shift ranges
+    set_value: u8,
+    fan_start: u8,
+    fan_end: u8,
+}
+
+impl FaderBank {
+    pub fn new() -> Self {
+        Self {
+            page: 0,
+            selected: BTreeSet::new(),
+            anchor: None,
+            set_value: 255,
+            fan_start: 0,
+            fan_end: 255,
+        }
+    }
+
+    /// The channels toolbar actions apply to: the selection, or the whole
+    /// universe when nothing is selected.
+    fn targets(&self) -> Vec<usize> {
+        if self.selected.is_empty() {
+            (0..512).collect()
+        } else {
+            self.selected.iter().copied().collect()
+        }
+    }
+
+    /// Returns true once an edit is finished and the levels should be saved:
+    /// after a toolbar action, a fader drag or any other fader change.
+    pub fn show(&mut self, ui: &mut egui::Ui, universe: &mut TransmitUniverse) -> bool {
+        let mut edited = self.show_toolbar(ui, universe);
+
+        ui.horizontal(|ui| {
+            if ui.button("◀").clicked() {
+                self.page = self.page.saturating_sub(1);
+            }
+
+            let pages = 512 / FADERS_PER_PAGE;
+            egui::ComboBox::from_id_source("fader_page")
+                .selected_text(Self::page_label(self.page))
+                .show_ui(ui, |ui| {
+                    for page in 0..pages {
+                        ui.selectable_value(&mut self.page, page, Self::page_label(page));
+                    }
+                });
+
+            if ui.button("▶").clicked() {
+                self.page = (self.page + 1).min(pages - 1);
+            }
+
+            ui.separator();
+            ui.label(format!("{} selected", self.selected.len()));
+            if ui.small_button("Select Page").clicked() {
+                let first = self.page * FADERS_PER_PAGE;
+                self.selected.extend(first..first + FADERS_PER_PAGE);
+            }
+            if ui.small_button("Select None").clicked() {
+                self.selected.clear();
+                self.anchor = None;
+            }
+        });
+
+        egui::ScrollArea::horizontal()
+            .id_source("fader_bank")
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    let first = self.page * FADERS_PER_PAGE;
+                    for channel in first..first + FADERS_PER_PAGE {
+                        edited |= self.show_fader(ui, universe, channel);
+                    }
+                });
+            });
+        edited
+    }
+
+    fn page_label(page: usize) -> String {
+        let first = page * FADERS_PER_PAGE + 1;
+        format!("{}-{}", first, first + FADERS_PER_PAGE - 1)
+    }
+
+    fn show_toolbar(&mut self, ui: &mut egui::Ui, universe: &mut TransmitUniverse) -> bool {
+        let mut edited = false;
+        ui.horizontal(|ui| {
+            ui.add(egui::DragValue::new(&mut self.set_value).range(0..=255));
+            if ui.button("Set All").clicked() {
+                universe.set_channels(&self.targets(), self.set_value);
+                edited = true;
+            }
+
+            ui.separator();
+            ui.add(egui::DragValue::new(&mut self.fan_start).range(0..=255));
+            ui.label("→");
+            ui.add(egui::DragValue::new(&mut self.fan_end).range(0..=255));
+            if ui.button("Fan").clicked() {
+                universe.fan_channels(&self.targets(), self.fan_start, self.fan_end);
+                edited = true;
+            }
+
+            ui.separator();
+            if ui.button("Clear").clicked() {
+                universe.set_channels(&self.targets(), 0);
+                edited = true;
+            }
+
+            ui.separator();
+            for (label, delta) in [
+                ("--", -COARSE_STEP),
+                ("-", -1),
+                ("+", 1),
+                ("++", COARSE_STEP),
+            ] {
+                if ui
+                    .button(label)
+                    .on_hover_text(format!("Nudge by {:+}", delta))
+                    .clicked()
+                {
+                    universe.nudge_channels(&self.targets(), delta);
+                    edited = true;
+                }
+            }
+        });
+        edited
+    }
+
+    /// Returns true when a drag ends or the level changes without one, e.g.
+    /// from a click or the keyboard.
+    fn show_fader(
+        &mut self,
+        ui: &mut egui::Ui,
+        universe: &mut TransmitUniverse,
+        channel: usize,
+    ) -> bool {
+        ui.vertical(|ui| {
+            let selected = self.selected.contains(&channel);
+            if ui
+                .selectable_label(selected, format!("{}", channel + 1))
+                .on_hover_text("Click to select, Ctrl+click to add, Shift+click for a range")
+                .clicked()
+            {
+                self.click(ui.input(|i| i.modifiers), channel);
+            }
+
+            let before = universe.levels[channel];
+            let response = ui.add(
+                egui::Slider::new(&mut universe.levels[channel], 0..=255)
+                    .orientation(egui::SliderOrientation::Vertical),
+            );
+
+            // Moving a selected fader moves the rest of the selection with it
+            if response.changed() && selected {
+                let delta = universe.levels[channel] as i16 - before as i16;
+                let others: Vec<usize> = self
+                    .selected
+                    .iter()
+                    .copied()
+                    .filter(|&c| c != channel)
+                    .collect();
+                universe.nudge_channels(&others, delta);
+            }
+            response.drag_stopped() || (response.changed() && !response.dragged())
+        })
+        .inner
+    }
+
+    fn click(&mut self, modifiers: egui::Modifiers, channel: usize) {
+        match self.anchor {
+            Some(anchor) if modifiers.shift => {
+                let (from, to) = (anchor.min(channel), anchor.max(channel));
+                self.selected.extend(from..=to);
+            }
+            _ if modifiers.command => {
+                if !self.selected.remove(&channel) {
+                    self.selected.insert(channel);
+                }
+                self.anchor = Some(channel);
+            }
+            _ => {
+                self.selected.clear();
+                self.selected.insert(channel);
+                self.anchor = Some(channel);
+            }
+        }
+    }
+}
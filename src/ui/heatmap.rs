@@ -0,0 +1,87 @@
+use crate::core::UniverseData;
+use eframe::egui;
+use std::collections::HashMap;
+
+/// Each universe tile is 32 x 16 cells, one per channel.
+const TILE_COLUMNS: usize = 32;
+const TILE_ROWS: usize = 16;
+const CELL_SIZE: f32 = 4.0;
+const LABEL_HEIGHT: f32 = 14.0;
+
+/// Draws every universe as a compact heatmap tile, ordered by universe
+/// number. Returns the universe whose tile was clicked.
+pub fn show_heatmap(ui: &mut egui::Ui, universes: &HashMap<u16, UniverseData>) -> Option<u16> {
+    let mut numbers: Vec<u16> = universes.keys().copied().collect();
+    numbers.sort();
+
+    if numbers.is_empty() {
+        ui.label("No universes received yet");
+        return None;
+    }
+
+    let mut clicked = None;
+    ui.horizontal_wrapped(|ui| {
+        for universe in numbers {
+            if show_tile(ui, &universes[&universe]) {
+                clicked = Some(universe);
+            }
+        }
+    });
+    clicked
+}
+
+fn show_tile(ui: &mut egui::Ui, data: &UniverseData) -> bool {
+    let size = egui::vec2(
+        TILE_COLUMNS as f32 * CELL_SIZE,
+        TILE_ROWS as f32 * CELL_SIZE + LABEL_HEIGHT,
+    );
+    let (rect, response) = ui.allocate_exact_size(size, egui::Sense::click());
+    let painter = ui.painter_at(rect);
+
+    let label_color = if data.is_lost() {
+        egui::Color32::RED
+    } else {
+        ui.visuals().text_color()
+    };
+    painter.text(
+        rect.left_top(),
+        egui::Align2::LEFT_TOP,
+        format!("U{}", data.universe),
+        egui::FontId::monospace(11.0),
+        label_color,
+    );
+
+    let origin = rect.left_top() + egui::vec2(0.0, LABEL_HEIGHT);
+    for (i, &value) in data.channels.iter().enumerate() {
+        let min = origin
+            + egui::vec2(
+                (i % TILE_COLUMNS) as f32 * CELL_SIZE,
+                (i / TILE_COLUMNS) as f32 * CELL_SIZE,
+            );
+        painter.rect_filled(
+            egui::Rect::from_min_size(min, egui::vec2(CELL_SIZE, CELL_SIZE)),
+            0.0,
+            egui::Color32::from_gray(value),
+        );
+    }
+
+    let grid = egui::Rect::from_min_max(origin, rect.right_bottom());
+    let outline = if response.hovered() {
+        egui::Stroke::new(1.0, egui::Color32::YELLOW)
+    } else if data.is_lost() {
+        egui::Stroke::new(1.0, egui::Color32::RED)
+    } else {
+        egui::Stroke::new(1.0, ui.visuals().widgets.noninteractive.bg_stroke.color)
+    };
+    painter.rect_stroke(grid, 0.0, outline);
+
+    let active = data.channels.iter().filter(|&&value| value > 0).count();
+    response
+        .on_hover_text(format!(
+            "Universe {}: {} source(s), {} channels above 0\nClick to open",
+            data.universe,
+            data.sources.len(),
+            active
+        ))
+        .clicked()
+}
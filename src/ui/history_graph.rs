@@ -0,0 +1,180 @@
+use super::SOURCE_COLORS;
+use crate::core::{HistorySample, UniverseData, MAX_HISTORY_SECONDS};
+use chrono::{DateTime, Utc};
+use eframe::egui;
+use std::collections::{BTreeSet, VecDeque};
+
+const GRAPH_HEIGHT: f32 = 160.0;
+
+/// Line graphs of selected channels' recent levels.
+pub struct HistoryGraph {
+    channels: BTreeSet<usize>, // 0-based
+    add_channel: u16,
+}
+
+/// The level a channel had at `time`, if history reaches back that far.
+fn value_at(samples: &VecDeque<HistorySample>, time: DateTime<Utc>) -> Option<u8> {
+    let index = samples.partition_point(|&(t, _)| t <= time);
+    index.checked_sub(1).map(|i| samples[i].1)
+}
+
+impl HistoryGraph {
+    pub fn new() -> Self {
+        Self {
+            channels: BTreeSet::new(),
+            add_channel: 1,
+        }
+    }
+
+    /// Adds a channel to the graph, or removes it if already shown.
+    pub fn toggle(&mut self, channel: usize) {
+        if !self.channels.remove(&channel) {
+            self.channels.insert(channel);
+        }
+    }
+
+    fn color(index: usize) -> egui::Color32 {
+        SOURCE_COLORS[index % SOURCE_COLORS.len()]
+    }
+
+    /// Draws the graph. Returns a new history depth in seconds if the user
+    /// changed it.
+    pub fn show(
+        &mut self,
+        ui: &mut egui::Ui,
+        data: &UniverseData,
+        depth_seconds: u32,
+    ) -> Option<u32> {
+        let mut new_depth = None;
+
+        ui.horizontal(|ui| {
+            ui.label("Graph channel:");
+            ui.add(egui::DragValue::new(&mut self.add_channel).range(1..=512));
+            if ui.button("Add").clicked() {
+                self.channels.insert(self.add_channel as usize - 1);
+            }
+
+            ui.separator();
+            let mut depth = depth_seconds;
+            if ui
+                .add(
+                    egui::DragValue::new(&mut depth)
+                        .range(1..=MAX_HISTORY_SECONDS)
+                        .prefix("Depth: ")
+                        .suffix(" s"),
+                )
+                .changed()
+            {
+                new_depth = Some(depth);
+            }
+        });
+
+        let mut remove = None;
+        ui.horizontal_wrapped(|ui| {
+            if self.channels.is_empty() {
+                ui.label("Click channels in the grid or add them above to graph them");
+            }
+            for (index, &channel) in self.channels.iter().enumerate() {
+                let text =
+                    egui::RichText::new(format!("Ch {} ✖", channel + 1)).color(Self::color(index));
+                if ui
+                    .small_button(text)
+                    .on_hover_text("Remove from graph")
+                    .clicked()
+                {
+                    remove = Some(channel);
+                }
+            }
+        });
+        if let Some(channel) = remove {
+            self.channels.remove(&channel);
+        }
+
+        self.draw(ui, data);
+        new_depth
+    }
+
+    fn draw(&self, ui: &mut egui::Ui, data: &UniverseData) {
+        let size = egui::vec2(ui.available_width(), GRAPH_HEIGHT);
+        let (rect, response) = ui.allocate_exact_size(size, egui::Sense::hover());
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(rect, 2.0, ui.visuals().extreme_bg_color);
+
+        let now = Utc::now();
+        let depth = data.history.depth();
+        let start = now - depth;
+        let depth_ms = depth.num_milliseconds().max(1) as f32;
+        let x_at = |time: DateTime<Utc>| {
+            let offset = (time - start).num_milliseconds() as f32 / depth_ms;
+            rect.left() + rect.width() * offset.clamp(0.0, 1.0)
+        };
+        let y_at = |value: u8| rect.bottom() - rect.height() * value as f32 / 255.0;
+
+        // Guides at 0, 50% and full
+        let guide = egui::Stroke::new(1.0, ui.visuals().widgets.noninteractive.bg_stroke.color);
+        for value in [0, 128, 255] {
+            let y = y_at(value);
+            painter.line_segment(
+                [egui::pos2(rect.left(), y), egui::pos2(rect.right(), y)],
+                guide,
+            );
+        }
+        let text_color = ui.visuals().weak_text_color();
+        painter.text(
+            rect.left_top() + egui::vec2(4.0, 2.0),
+            egui::Align2::LEFT_TOP,
+            format!("-{} s", depth.num_seconds()),
+            egui::FontId::monospace(10.0),
+            text_color,
+        );
+        painter.text(
+            rect.right_top() + egui::vec2(-4.0, 2.0),
+            egui::Align2::RIGHT_TOP,
+            "now",
+            egui::FontId::monospace(10.0),
+            text_color,
+        );
+
+        for (index, &channel) in self.channels.iter().enumerate() {
+            let samples = data.history.samples(channel);
+            let mut points = Vec::with_capacity(samples.len() * 2 + 1);
+            for (i, &(time, value)) in samples.iter().enumerate() {
+                // Hold each level until the next sample, a step rather than a ramp
+                if i > 0 {
+                    points.push(egui::pos2(x_at(time), y_at(samples[i - 1].1)));
+                }
+                points.push(egui::pos2(x_at(time), y_at(value)));
+            }
+            if let Some(&(_, value)) = samples.back() {
+                points.push(egui::pos2(rect.right(), y_at(value)));
+            }
+            painter.add(egui::Shape::line(
+                points,
+                egui::Stroke::new(1.5, Self::color(index)),
+            ));
+        }
+
+        if let Some(pointer) = response.hover_pos() {
+            painter.line_segment(
+                [
+                    egui::pos2(pointer.x, rect.top()),
+                    egui::pos2(pointer.x, rect.bottom()),
+                ],
+                egui::Stroke::new(1.0, egui::Color32::YELLOW),
+            );
+
+            let fraction = (pointer.x - rect.left()) / rect.width();
+            let time = start + chrono::Duration::milliseconds((fraction * depth_ms) as i64);
+            let mut text = format!(
+                "{:.2} s ago",
+                (now - time).num_milliseconds() as f32 / 1000.0
+            );
+            for &channel in &self.channels {
+                let value = value_at(data.history.samples(channel), time)
+                    .map_or("-".to_string(), |v| v.to_string());
+                text.push_str(&format!("\nCh {}: {}", channel + 1, value));
+            }
+            response.on_hover_text_at_pointer(text);
+        }
+    }
+}
@@ -0,0 +1,250 @@
+use crate::network::{dissect, Dissection, SacnNetwork};
+use eframe::egui;
+use std::ops::Range;
+use std::sync::Arc;
+
+const LIST_HEIGHT: f32 = 160.0;
+const BYTES_PER_ROW: usize = 16;
+
+/// A pausable list of recent raw packets with a hex dump and decoded PDU
+/// tree of the selected one.
+pub struct InspectorPanel {
+    selected: Option<u64>,
+    dissection: Option<(u64, Dissection)>, // of the selected packet
+    selected_field: Option<Range<usize>>,
+    hovered_field: Option<Range<usize>>,
+    filter: String,
+}
+
+impl InspectorPanel {
+    pub fn new() -> Self {
+        Self {
+            selected: None,
+            dissection: None,
+            selected_field: None,
+            hovered_field: None,
+            filter: String::new(),
+        }
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui, network: &Arc<SacnNetwork>) {
+        let mut inspector = network.inspector();
+
+        ui.horizontal(|ui| {
+            let pause_text = if inspector.paused {
+                "▶ Resume"
+            } else {
+                "⏸ Pause"
+            };
+            if ui.button(pause_text).clicked() {
+                inspector.paused = !inspector.paused;
+            }
+            if ui.button("Clear").clicked() {
+                inspector.clear();
+                self.selected = None;
+            }
+            ui.add(
+                egui::TextEdit::singleline(&mut self.filter)
+                    .hint_text("Filter, e.g. U1 or 10.0.0.5")
+                    .desired_width(160.0),
+            );
+            ui.label(format!("{} packet(s)", inspector.packets().len()));
+        });
+
+        let filter = self.filter.trim().to_lowercase();
+        let rows: Vec<_> = inspector
+            .packets()
+            .iter()
+            .rev()
+            .filter(|packet| {
+                filter.is_empty()
+                    || packet.summary.to_lowercase().contains(&filter)
+                    || packet.source.to_string().contains(&filter)
+            })
+            .collect();
+
+        let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+        egui::ScrollArea::vertical()
+            .id_source("inspector_list")
+            .max_height(LIST_HEIGHT)
+            .auto_shrink([false, true])
+            .show_rows(ui, row_height, rows.len(), |ui, range| {
+                for packet in &rows[range] {
+                    let mut text = egui::RichText::new(format!(
+                        "{} {:<21} {}",
+                        packet
+                            .timestamp
+                            .with_timezone(&chrono::Local)
+                            .format("%H:%M:%S%.3f"),
+                        packet.source.to_string(),
+                        packet.summary
+                    ))
+                    .monospace();
+                    if packet.malformed {
+                        text = text.color(egui::Color32::LIGHT_RED);
+                    }
+                    if ui
+                        .selectable_label(self.selected == Some(packet.id), text)
+                        .clicked()
+                    {
+                        self.selected = Some(packet.id);
+                        self.selected_field = None;
+                    }
+                }
+            });
+
+        ui.separator();
+
+        let Some(packet) = self.selected.and_then(|id| inspector.get(id)) else {
+            ui.label("Select a packet to inspect it");
+            return;
+        };
+        if self.dissection.as_ref().map(|(id, _)| *id) != Some(packet.id) {
+            self.dissection = Some((packet.id, dissect(&packet.payload)));
+        }
+        let Some((_, dissection)) = &self.dissection else {
+            return;
+        };
+
+        let highlight = self
+            .hovered_field
+            .take()
+            .or_else(|| self.selected_field.clone());
+        let mut clicked_byte = None;
+        let mut clicked_field = None;
+        ui.columns(2, |columns| {
+            clicked_byte = Self::show_hex_dump(&mut columns[0], &packet.payload, &highlight);
+            clicked_field = Self::show_tree(
+                &mut columns[1],
+                dissection,
+                &highlight,
+                &mut self.hovered_field,
+            );
+        });
+
+        // Clicking a byte selects the field it belongs to
+        if let Some(offset) = clicked_byte {
+            self.selected_field = dissection
+                .layers
+                .iter()
+                .flat_map(|layer| &layer.fields)
+                .find(|field| field.range.contains(&offset))
+                .map(|field| field.range.clone());
+        }
+        if clicked_field.is_some() {
+            self.selected_field = clicked_field;
+        }
+    }
+
+    /// Draws the packet bytes, highlighting a field. Returns the offset of
+    /// a clicked byte.
+    fn show_hex_dump(
+        ui: &mut egui::Ui,
+        payload: &[u8],
+        highlight: &Option<Range<usize>>,
+    ) -> Option<usize> {
+        let mut clicked = None;
+        let highlight_color = ui.visuals().selection.bg_fill;
+
+        egui::ScrollArea::vertical()
+            .id_source("inspector_hex")
+            .show(ui, |ui| {
+                ui.spacing_mut().item_spacing.x = 4.0;
+                for (row, bytes) in payload.chunks(BYTES_PER_ROW).enumerate() {
+                    ui.horizontal(|ui| {
+                        let start = row * BYTES_PER_ROW;
+                        ui.label(
+                            egui::RichText::new(format!("{:04X}", start))
+                                .monospace()
+                                .weak(),
+                        );
+                        for (i, byte) in bytes.iter().enumerate() {
+                            let offset = start + i;
+                            let mut text = egui::RichText::new(format!("{:02X}", byte)).monospace();
+                            if highlight
+                                .as_ref()
+                                .is_some_and(|range| range.contains(&offset))
+                            {
+                                text = text.background_color(highlight_color);
+                            }
+                            if ui
+                                .add(egui::Label::new(text).sense(egui::Sense::click()))
+                                .clicked()
+                            {
+                                clicked = Some(offset);
+                            }
+                        }
+
+                        let ascii: String = bytes
+                            .iter()
+                            .map(|&byte| {
+                                if byte.is_ascii_graphic() || byte == b' ' {
+                                    byte as char
+                                } else {
+                                    '.'
+                                }
+                            })
+                            .collect();
+                        ui.label(egui::RichText::new(ascii).monospace().weak());
+                    });
+                }
+            });
+        clicked
+    }
+
+    /// Draws the decoded layers and fields. Returns the byte range of a
+    /// clicked field.
+    fn show_tree(
+        ui: &mut egui::Ui,
+        dissection: &Dissection,
+        highlight: &Option<Range<usize>>,
+        hovered: &mut Option<Range<usize>>,
+    ) -> Option<Range<usize>> {
+        let mut clicked = None;
+
+        match &dissection.error {
+            Some(error) => {
+                ui.colored_label(egui::Color32::LIGHT_RED, format!("Discarded: {}", error));
+            }
+            None => {
+                ui.colored_label(egui::Color32::LIGHT_GREEN, "Valid E1.31 packet");
+            }
+        }
+
+        egui::ScrollArea::vertical()
+            .id_source("inspector_tree")
+            .show(ui, |ui| {
+                for layer in &dissection.layers {
+                    egui::CollapsingHeader::new(format!(
+                        "{} (bytes {}-{})",
+                        layer.name,
+                        layer.range.start,
+                        layer.range.end.saturating_sub(1)
+                    ))
+                    .id_source(layer.name)
+                    .default_open(true)
+                    .show(ui, |ui| {
+                        for field in &layer.fields {
+                            let response = ui
+                                .selectable_label(
+                                    highlight.as_ref() == Some(&field.range),
+                                    format!("{}: {}", field.name, field.value),
+                                )
+                                .on_hover_text(format!(
+                                    "Bytes {}-{}",
+                                    field.range.start,
+                                    field.range.end - 1
+                                ));
+                            if response.hovered() {
+                                *hovered = Some(field.range.clone());
+                            }
+                            if response.clicked() {
+                                clicked = Some(field.range.clone());
+                            }
+                        }
+                    });
+                }
+            });
+        clicked
+    }
+}
@@ -1,15 +1,105 @@
-use crate::core::{AppState, LogLevel};
-use crate::network::SacnNetwork;
+use crate::core::{
+    AppState, ArtNetNode, ArtNetOutput, ListenerStatus, LogLevel, MergeMode, PortAddress, Protocol,
+    MAX_ARTNET_UNIVERSE_OFFSET, MAX_PRIORITY, MAX_SOURCE_NAME_LEN, MAX_UNIVERSE, MIN_UNIVERSE,
+};
+use crate::network::{SacnNetwork, MAX_SEND_RATE};
 use eframe::egui;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
+mod bridge;
+mod command_line;
+mod compliance;
+mod diagnostics;
+mod fader_bank;
+mod heatmap;
+mod history_graph;
+mod inspector_panel;
+mod replay_panel;
+mod routing_panel;
+
+use command_line::CommandLine;
+use compliance::ComplianceAction;
+use fader_bank::FaderBank;
+use history_graph::HistoryGraph;
+use inspector_panel::InspectorPanel;
+use replay_panel::ReplayPanel;
+use routing_panel::RoutingPanel;
+
+/// Colours used to tell sources apart in the universe view.
+const SOURCE_COLORS: [egui::Color32; 6] = [
+    egui::Color32::LIGHT_BLUE,
+    egui::Color32::LIGHT_GREEN,
+    egui::Color32::GOLD,
+    egui::Color32::LIGHT_RED,
+    egui::Color32::from_rgb(200, 150, 255),
+    egui::Color32::from_rgb(255, 170, 80),
+];
+
+/// Colour of a listener's status line.
+fn listener_color(status: &ListenerStatus) -> egui::Color32 {
+    match status {
+        ListenerStatus::Listening(_) => egui::Color32::GREEN,
+        ListenerStatus::Rebinding => egui::Color32::YELLOW,
+        ListenerStatus::Stopped => egui::Color32::GRAY,
+        ListenerStatus::Failed(_) => egui::Color32::RED,
+    }
+}
+
+/// The details an Art-Net node gave in its poll reply.
+fn show_artnet_node(ui: &mut egui::Ui, node: &ArtNetNode, universe_offset: u16) {
+    if !node.long_name.is_empty() {
+        ui.label(format!("Long name: {}", node.long_name));
+    }
+    ui.label(format!(
+        "Firmware: {}.{}",
+        node.firmware >> 8,
+        node.firmware & 0xff
+    ));
+    for (i, port) in node.ports.iter().enumerate() {
+        let describe =
+            |direction: &str, address: PortAddress| match address.to_universe(universe_offset) {
+                Some(universe) => format!("{} {} (universe {})", direction, address, universe),
+                None => format!("{} {}", direction, address),
+            };
+        let directions: Vec<String> = port
+            .output
+            .map(|address| describe("out", address))
+            .into_iter()
+            .chain(port.input.map(|address| describe("in", address)))
+            .collect();
+        if !directions.is_empty() {
+            ui.label(format!("Port {}: {}", i + 1, directions.join(", ")));
+        }
+    }
+    if !node.node_report.is_empty() {
+        ui.label(format!("Report: {}", node.node_report))
+            .on_hover_text("The node's latest status report");
+    }
+}
+
 pub struct MainWindow {
     app_state: Arc<RwLock<AppState>>,
     network: Arc<SacnNetwork>,
-    dmx_send_values: [u8; 512],
-    send_universe: u16,
+    send_universe: u16, // transmit universe being edited
+    new_transmit_universe: u16,
     show_hex: bool,
+    show_sources: bool,
+    show_priority: bool,
+    show_history: bool,
+    show_overview: bool,
+    show_diagnostics: bool,
+    show_compliance: bool,
+    show_inspector: bool,
+    show_bridge: bool,
+    show_routing: bool,
+    subscribe_universe: u16,
+    fader_bank: FaderBank,
+    command_line: CommandLine,
+    replay_panel: ReplayPanel,
+    history_graph: HistoryGraph,
+    inspector_panel: InspectorPanel,
+    routing_panel: RoutingPanel,
 }
 
 impl MainWindow {
@@ -17,9 +107,188 @@ impl MainWindow {
         Self {
             app_state,
             network,
-            dmx_send_values: [0; 512],
             send_universe: 1,
+            new_transmit_universe: 1,
             show_hex: false,
+            show_sources: false,
+            show_priority: false,
+            show_history: false,
+            show_overview: false,
+            show_diagnostics: false,
+            show_compliance: false,
+            show_inspector: false,
+            show_bridge: false,
+            show_routing: false,
+            subscribe_universe: 1,
+            fader_bank: FaderBank::new(),
+            command_line: CommandLine::new(),
+            replay_panel: ReplayPanel::new(),
+            history_graph: HistoryGraph::new(),
+            inspector_panel: InspectorPanel::new(),
+            routing_panel: RoutingPanel::new(),
+        }
+    }
+
+    fn show_dmx_sender(&mut self, ui: &mut egui::Ui) {
+        ui.heading("DMX Sender");
+
+        let Ok(mut state) = self.app_state.try_write() else {
+            return;
+        };
+        let mut session_changed = false;
+
+        ui.horizontal(|ui| {
+            ui.label("Source Name:");
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut state.transmit_session.source_name)
+                    .char_limit(MAX_SOURCE_NAME_LEN),
+            );
+            session_changed |= response.lost_focus();
+
+            ui.separator();
+            let mut auto_send = state.auto_send_enabled;
+            if ui.checkbox(&mut auto_send, "Auto Send").changed() {
+                state.set_auto_send(auto_send);
+            }
+
+            let mut send_rate = state.send_rate;
+            if ui
+                .add(
+                    egui::DragValue::new(&mut send_rate)
+                        .range(1..=MAX_SEND_RATE)
+                        .suffix(" Hz"),
+                )
+                .changed()
+            {
+                state.set_send_rate(send_rate);
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Add Universe:");
+            ui.add(
+                egui::DragValue::new(&mut self.new_transmit_universe)
+                    .range(MIN_UNIVERSE..=MAX_UNIVERSE),
+            );
+            if ui.button("Add").clicked() {
+                state.add_transmit_universe(self.new_transmit_universe);
+                self.send_universe = self.new_transmit_universe;
+            }
+        });
+
+        let nodes: Vec<(String, std::net::IpAddr)> = state
+            .artnet_nodes()
+            .into_iter()
+            .map(|device| (device.name.clone(), device.ip))
+            .collect();
+
+        let mut remove = None;
+        egui::Grid::new("transmit_universes")
+            .num_columns(8)
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label("Universe");
+                ui.label("Enabled");
+                ui.label("Priority");
+                ui.label("Preview");
+                ui.label("Sync");
+                ui.label("Art-Net");
+                ui.label("");
+                ui.label("");
+                ui.end_row();
+
+                for universe in &mut state.transmit_session.universes {
+                    if ui
+                        .selectable_label(
+                            self.send_universe == universe.universe,
+                            universe.universe.to_string(),
+                        )
+                        .on_hover_text("Edit with the channel controls")
+                        .clicked()
+                    {
+                        self.send_universe = universe.universe;
+                    }
+                    session_changed |= ui.checkbox(&mut universe.enabled, "").changed();
+                    session_changed |= ui
+                        .add(egui::DragValue::new(&mut universe.priority).range(0..=MAX_PRIORITY))
+                        .changed();
+                    session_changed |= ui.checkbox(&mut universe.preview, "").changed();
+                    session_changed |= ui
+                        .add(
+                            egui::DragValue::new(&mut universe.sync_universe)
+                                .range(0..=MAX_UNIVERSE),
+                        )
+                        .on_hover_text(
+                            "Universes with the same sync universe latch together, 0 for none",
+                        )
+                        .changed();
+                    egui::ComboBox::from_id_source(("artnet_output", universe.universe))
+                        .selected_text(match universe.artnet {
+                            ArtNetOutput::Unicast(ip) => nodes
+                                .iter()
+                                .find(|(_, node_ip)| *node_ip == ip)
+                                .map_or_else(|| ip.to_string(), |(name, _)| name.clone()),
+                            output => output.to_string(),
+                        })
+                        .show_ui(ui, |ui| {
+                            for output in [ArtNetOutput::Off, ArtNetOutput::Broadcast] {
+                                session_changed |= ui
+                                    .selectable_value(
+                                        &mut universe.artnet,
+                                        output,
+                                        output.to_string(),
+                                    )
+                                    .changed();
+                            }
+                            for (name, ip) in &nodes {
+                                session_changed |= ui
+                                    .selectable_value(
+                                        &mut universe.artnet,
+                                        ArtNetOutput::Unicast(*ip),
+                                        format!("{} ({})", name, ip),
+                                    )
+                                    .changed();
+                            }
+                        })
+                        .response
+                        .on_hover_text("Also send this universe as ArtDmx");
+
+                    if ui.button("Send DMX").clicked() {
+                        let network = self.network.clone();
+                        let universe = universe.clone();
+
+                        tokio::spawn(async move {
+                            if let Err(e) = network.send_dmx(&universe).await {
+                                log::error!("Failed to send DMX: {}", e);
+                            }
+                        });
+                    }
+                    if ui.button("Remove").clicked() {
+                        remove = Some(universe.universe);
+                    }
+                    ui.end_row();
+                }
+            });
+
+        if let Some(universe) = remove {
+            state.remove_transmit_universe(universe);
+        } else if session_changed {
+            state.save_transmit_session();
+        }
+
+        self.command_line.show(ui, &mut state, self.send_universe);
+
+        let Some(universe) = state.transmit_session.universe_mut(self.send_universe) else {
+            ui.label(format!(
+                "Universe {} is not in the transmit session, add it to edit its channels",
+                self.send_universe
+            ));
+            return;
+        };
+
+        ui.label(format!("Channels for universe {}:", universe.universe));
+        if self.fader_bank.show(ui, universe) {
+            state.save_transmit_session();
         }
     }
 }
@@ -48,6 +317,7 @@ impl eframe::App for MainWindow {
                                 .clicked()
                             {
                                 state.update_adapter_selection(None);
+                                self.network.restart_listener();
                             }
 
                             for adapter in &state.network_adapters.clone() {
@@ -60,12 +330,17 @@ impl eframe::App for MainWindow {
                                     .clicked()
                                 {
                                     state.update_adapter_selection(Some(adapter.name.clone()));
+                                    self.network.restart_listener();
                                 }
                             }
                         });
 
                     if ui.button("Refresh").clicked() {
+                        let previous_ip = state.get_selected_adapter_ip();
                         state.refresh_network_adapters();
+                        if state.get_selected_adapter_ip() != previous_ip {
+                            self.network.restart_listener();
+                        }
                     }
                 }
 
@@ -74,6 +349,11 @@ impl eframe::App for MainWindow {
                 ui.add(egui::DragValue::new(&mut self.send_universe).range(1..=63999));
                 ui.separator();
                 ui.checkbox(&mut self.show_hex, "Show Hex");
+                ui.checkbox(&mut self.show_diagnostics, "Diagnostics");
+                ui.checkbox(&mut self.show_compliance, "Compliance");
+                ui.checkbox(&mut self.show_inspector, "Inspector");
+                ui.checkbox(&mut self.show_bridge, "Bridge");
+                ui.checkbox(&mut self.show_routing, "Routing");
             });
         });
 
@@ -83,8 +363,96 @@ impl eframe::App for MainWindow {
             .show(ctx, |ui| {
                 ui.heading("Network Status");
 
+                let mut artnet_universe_offset = None;
                 if let Ok(state) = self.app_state.try_read() {
                     ui.group(|ui| {
+                        ui.horizontal(|ui| {
+                            ui.colored_label(
+                                listener_color(&state.listener_status),
+                                format!("Listener {}", state.listener_status),
+                            );
+
+                            if state.listener_status == ListenerStatus::Stopped {
+                                if ui.small_button("Start").clicked() {
+                                    self.network.restart_listener();
+                                }
+                            } else if ui.small_button("Stop").clicked() {
+                                self.network.stop_listener();
+                            }
+                        });
+
+                        ui.horizontal(|ui| {
+                            ui.colored_label(
+                                listener_color(&state.artnet_status),
+                                format!("Art-Net {}", state.artnet_status),
+                            );
+
+                            if state.artnet_status == ListenerStatus::Stopped {
+                                if ui.small_button("Start").clicked() {
+                                    self.network.restart_artnet_listener();
+                                }
+                            } else if ui.small_button("Stop").clicked() {
+                                self.network.stop_artnet_listener();
+                            }
+                        });
+
+                        ui.horizontal(|ui| {
+                            ui.label("Art-Net 0:0:0 is universe");
+                            let mut offset = state.artnet_universe_offset;
+                            if ui
+                                .add(
+                                    egui::DragValue::new(&mut offset)
+                                        .range(0..=MAX_ARTNET_UNIVERSE_OFFSET),
+                                )
+                                .on_hover_text(
+                                    "Added to Art-Net port addresses to get the universe they show on",
+                                )
+                                .changed()
+                            {
+                                artnet_universe_offset = Some(offset);
+                            }
+                        });
+
+                        ui.horizontal(|ui| {
+                            if let Some(recording) = self.network.recording_status() {
+                                let elapsed = chrono::Utc::now() - recording.started;
+                                ui.colored_label(
+                                    egui::Color32::RED,
+                                    format!(
+                                        "● Recording {}s: {} packets, {} KB",
+                                        elapsed.num_seconds(),
+                                        recording.packets,
+                                        recording.bytes / 1024
+                                    ),
+                                )
+                                .on_hover_text(recording.path.display().to_string());
+
+                                if ui.small_button("Stop").clicked() {
+                                    let network = self.network.clone();
+                                    tokio::spawn(async move {
+                                        if let Err(e) = network.stop_recording().await {
+                                            log::error!("Failed to stop recording: {}", e);
+                                        }
+                                    });
+                                }
+                            } else {
+                                ui.label("Capture idle");
+                                if ui
+                                    .small_button("Record")
+                                    .on_hover_text("Save every received sACN packet to a file")
+                                    .clicked()
+                                {
+                                    let network = self.network.clone();
+                                    tokio::spawn(async move {
+                                        if let Err(e) = network.start_recording().await {
+                                            log::error!("Failed to start recording: {}", e);
+                                        }
+                                    });
+                                }
+                            }
+                        });
+
+                        ui.separator();
                         ui.label("Selected Adapter:");
                         if let Some(ref adapter_name) = state.selected_adapter {
                             if let Some(adapter) = state
@@ -113,17 +481,112 @@ impl eframe::App for MainWindow {
                     });
                 }
 
+                if let Some(offset) = artnet_universe_offset {
+                    if let Ok(mut state) = self.app_state.try_write() {
+                        state.set_artnet_universe_offset(offset);
+                    }
+                }
+
+                ui.separator();
+                ui.heading("Replay");
+                ui.group(|ui| self.replay_panel.show(ui, &self.network));
+
+                ui.separator();
+                ui.heading("Subscriptions");
+
+                if let Ok(mut state) = self.app_state.try_write() {
+                    ui.group(|ui| {
+                        let mut auto_subscribe = state.auto_subscribe;
+                        if ui
+                            .checkbox(&mut auto_subscribe, "Join discovered universes")
+                            .changed()
+                        {
+                            state.set_auto_subscribe(auto_subscribe);
+                        }
+
+                        ui.horizontal(|ui| {
+                            ui.add(
+                                egui::DragValue::new(&mut self.subscribe_universe)
+                                    .range(MIN_UNIVERSE..=MAX_UNIVERSE),
+                            );
+                            if ui.button("Subscribe").clicked() {
+                                state.subscribe_universe(self.subscribe_universe);
+                            }
+                        });
+
+                        let mut unsubscribe = None;
+                        ui.horizontal_wrapped(|ui| {
+                            for &universe in &state.subscribed_universes {
+                                if ui
+                                    .small_button(format!("{} ✖", universe))
+                                    .on_hover_text("Unsubscribe")
+                                    .clicked()
+                                {
+                                    unsubscribe = Some(universe);
+                                }
+                            }
+                        });
+                        if let Some(universe) = unsubscribe {
+                            state.unsubscribe_universe(universe);
+                        }
+
+                        ui.separator();
+                        ui.label(format!("Joined: {:?}", state.joined_universes));
+
+                        let mut gates: Vec<_> = state.sync_gates.values().collect();
+                        gates.sort_by_key(|gate| (gate.universe, *gate.cid.as_bytes()));
+                        for gate in gates {
+                            let (color, status) = if gate.waiting {
+                                (egui::Color32::RED, "waiting on missing sync")
+                            } else {
+                                (egui::Color32::YELLOW, "sync-gated")
+                            };
+                            ui.colored_label(
+                                color,
+                                format!(
+                                    "• Universe {} ({}) {} on universe {}",
+                                    gate.universe, gate.source_name, status, gate.sync_address
+                                ),
+                            );
+                        }
+                        for discovery in state.discovered_universes.values() {
+                            ui.label(format!(
+                                "• {} announces {:?}",
+                                discovery.source_name,
+                                discovery.universes()
+                            ));
+                        }
+                    });
+                }
+
                 ui.separator();
                 ui.heading("Discovered Devices");
 
                 if let Ok(state) = self.app_state.try_read() {
                     egui::ScrollArea::vertical().show(ui, |ui| {
-                        for (ip, device) in &state.devices {
+                        let mut devices: Vec<_> = state.devices.values().collect();
+                        devices.sort_by(|a, b| a.name.cmp(&b.name));
+
+                        for device in devices {
                             ui.group(|ui| {
-                                ui.label(format!("IP: {}", ip));
-                                ui.label(format!("Source: {}", device.source_name));
-                                ui.label(format!("Priority: {}", device.priority));
+                                let title = format!("{}: {}", device.protocol, device.name);
+                                if device.lost {
+                                    ui.colored_label(
+                                        egui::Color32::RED,
+                                        format!("{} (lost)", title),
+                                    );
+                                } else {
+                                    ui.label(title);
+                                }
+                                ui.label(format!("IP: {}", device.ip));
+                                if device.protocol == Protocol::Sacn {
+                                    ui.label(format!("CID: {}", device.id));
+                                    ui.label(format!("Priority: {}", device.priority));
+                                }
                                 ui.label(format!("Universes: {:?}", device.universes));
+                                if let Some(node) = &device.node {
+                                    show_artnet_node(ui, node, state.artnet_universe_offset);
+                                }
                                 ui.label(format!(
                                     "Last seen: {}",
                                     device.last_seen.format("%H:%M:%S")
@@ -166,6 +629,66 @@ impl eframe::App for MainWindow {
                 }
             });
 
+        let mut reset_stats = false;
+        egui::Window::new("Diagnostics")
+            .open(&mut self.show_diagnostics)
+            .default_width(720.0)
+            .show(ctx, |ui| {
+                if let Ok(state) = self.app_state.try_read() {
+                    reset_stats =
+                        diagnostics::show_stream_stats(ui, &state.stream_stats, state.clocks());
+                }
+            });
+        if reset_stats {
+            if let Ok(mut state) = self.app_state.try_write() {
+                state.reset_stream_stats();
+            }
+        }
+
+        let mut compliance_action = None;
+        egui::Window::new("E1.31 Compliance")
+            .open(&mut self.show_compliance)
+            .default_width(640.0)
+            .show(ctx, |ui| {
+                if let Ok(state) = self.app_state.try_read() {
+                    compliance_action =
+                        compliance::show_compliance(ui, &state.compliance_sources());
+                }
+            });
+        if let Some(action) = compliance_action {
+            if let Ok(mut state) = self.app_state.try_write() {
+                match action {
+                    ComplianceAction::Export => state.export_compliance_report(),
+                    ComplianceAction::Reset => state.reset_compliance(),
+                }
+            }
+        }
+
+        egui::Window::new("Packet Inspector")
+            .open(&mut self.show_inspector)
+            .default_size([760.0, 560.0])
+            .show(ctx, |ui| {
+                self.inspector_panel.show(ui, &self.network);
+            });
+
+        egui::Window::new("sACN ⇄ Art-Net Bridge")
+            .open(&mut self.show_bridge)
+            .default_width(820.0)
+            .show(ctx, |ui| {
+                if let Ok(mut state) = self.app_state.try_write() {
+                    bridge::show_bridge(ui, &mut state);
+                }
+            });
+
+        egui::Window::new("Routing")
+            .open(&mut self.show_routing)
+            .default_width(820.0)
+            .show(ctx, |ui| {
+                if let Ok(mut state) = self.app_state.try_write() {
+                    self.routing_panel.show(ui, &mut state);
+                }
+            });
+
         // Central panel for universe view
         egui::CentralPanel::default().show(ctx, |ui| {
             ui.horizontal(|ui| {
@@ -193,21 +716,101 @@ impl eframe::App for MainWindow {
                                 );
                             }
                         });
+
+                    ui.separator();
+                    ui.label("Merge:");
+                    let mut merge_mode = state.merge_mode;
+                    ui.radio_value(&mut merge_mode, MergeMode::Htp, "HTP");
+                    ui.radio_value(&mut merge_mode, MergeMode::Ltp, "LTP");
+                    if merge_mode != state.merge_mode {
+                        state.set_merge_mode(merge_mode);
+                    }
                 }
+
+                ui.checkbox(&mut self.show_sources, "Show Sources");
+                ui.checkbox(&mut self.show_priority, "Show Priority");
+                ui.checkbox(&mut self.show_history, "Show History");
+                ui.checkbox(&mut self.show_overview, "Overview")
+                    .on_hover_text("Show every universe as a heatmap tile");
             });
 
             ui.separator();
 
+            let mut history_seconds = None;
+            let mut open_universe = None;
             if let Ok(state) = self.app_state.try_read() {
-                if let Some(selected_universe) = state.selected_universe {
+                if self.show_overview {
+                    egui::ScrollArea::vertical()
+                        .id_source("heatmap")
+                        .max_height(ui.available_height() / 2.0)
+                        .show(ui, |ui| {
+                            open_universe = heatmap::show_heatmap(ui, &state.universes);
+                        });
+                } else if let Some(selected_universe) = state.selected_universe {
                     if let Some(universe_data) = state.universes.get(&selected_universe) {
                         ui.label(format!(
-                            "Universe {} - Source: {} - Last Updated: {}",
+                            "Universe {} - {} source(s) - Last Updated: {}",
                             universe_data.universe,
-                            universe_data.source_ip,
+                            universe_data.sources.len(),
                             universe_data.last_updated.format("%H:%M:%S%.3f")
                         ));
 
+                        if universe_data.is_lost() {
+                            ui.colored_label(
+                                egui::Color32::RED,
+                                "Universe lost: all sources timed out or terminated",
+                            );
+                        }
+
+                        for gate in state.universe_sync_gates(selected_universe) {
+                            if gate.waiting {
+                                ui.colored_label(
+                                    egui::Color32::RED,
+                                    format!(
+                                        "{} is waiting on missing sync on universe {}",
+                                        gate.source_name, gate.sync_address
+                                    ),
+                                );
+                            } else {
+                                ui.colored_label(
+                                    egui::Color32::YELLOW,
+                                    format!(
+                                        "{} is synchronized on universe {}",
+                                        gate.source_name, gate.sync_address
+                                    ),
+                                );
+                            }
+                        }
+
+                        let mut sources: Vec<_> = universe_data.sources.values().collect();
+                        sources.sort_by_key(|source| *source.cid.as_bytes());
+
+                        for (index, source) in sources.iter().enumerate() {
+                            ui.colored_label(
+                                SOURCE_COLORS[index % SOURCE_COLORS.len()],
+                                format!(
+                                    "■ {} ({}) - Priority: {}{} - Seq: {} - Last Updated: {}",
+                                    source.source_name,
+                                    source.source_ip,
+                                    source.priority,
+                                    if source.address_priorities.is_some() {
+                                        " (per-address)"
+                                    } else {
+                                        ""
+                                    },
+                                    source.sequence,
+                                    source.last_updated.format("%H:%M:%S%.3f")
+                                ),
+                            )
+                            .on_hover_text(format!("CID: {}", source.cid));
+                        }
+
+                        if self.show_history {
+                            history_seconds =
+                                self.history_graph
+                                    .show(ui, universe_data, state.history_seconds);
+                        }
+
                         // DMX channel grid
                         egui::ScrollArea::both().show(ui, |ui| {
                             egui::Grid::new("dmx_grid")
@@ -217,20 +820,46 @@ impl eframe::App for MainWindow {
                                     for (i, &value) in universe_data.channels.iter().enumerate() {
                                         let channel = i + 1;
 
-                                        let color = if value == 0 {
+                                        let winner = universe_data.winners[i].and_then(|cid| {
+                                            sources.iter().position(|source| source.cid == cid)
+                                        });
+
+                                        let color = if self.show_sources {
+                                            winner.map_or(egui::Color32::DARK_GRAY, |index| {
+                                                SOURCE_COLORS[index % SOURCE_COLORS.len()]
+                                            })
+                                        } else if value == 0 {
                                             egui::Color32::BLACK
                                         } else {
                                             let intensity = value as f32 / 255.0;
                                             egui::Color32::from_gray((intensity * 255.0) as u8)
                                         };
 
-                                        let text = if self.show_hex {
+                                        let mut text = if self.show_hex {
                                             format!("{:02X}", value)
                                         } else {
                                             format!("{}", value)
                                         };
+                                        if self.show_priority {
+                                            let priority = winner.map_or(0, |index| {
+                                                sources[index].channel_priority(i)
+                                            });
+                                            text.push_str(&format!(" p{}", priority));
+                                        }
 
-                                        ui.colored_label(color, format!("{}:{}", channel, text));
+                                        let label = ui
+                                            .colored_label(color, format!("{}:{}", channel, text))
+                                            .interact(egui::Sense::click());
+                                        if self.show_history && label.clicked() {
+                                            self.history_graph.toggle(i);
+                                        }
+                                        if let Some(index) = winner {
+                                            label.on_hover_text(format!(
+                                                "Driven by {} at priority {}",
+                                                sources[index].source_name,
+                                                sources[index].channel_priority(i)
+                                            ));
+                                        }
 
                                         if i % 16 == 15 {
                                             ui.end_row();
@@ -242,47 +871,32 @@ impl eframe::App for MainWindow {
                 }
             }
 
-            ui.separator();
-
-            // DMX Sender section
-            ui.heading("DMX Sender");
-
-            ui.horizontal(|ui| {
-                ui.label("Send to Universe:");
-                ui.add(egui::DragValue::new(&mut self.send_universe).range(1..=63999));
-
-                if ui.button("Send DMX").clicked() {
-                    let network = self.network.clone();
-                    let universe = self.send_universe;
-                    let dmx_data = self.dmx_send_values;
+            if let Some(universe) = open_universe {
+                if let Ok(mut state) = self.app_state.try_write() {
+                    state.selected_universe = Some(universe);
+                    self.show_overview = false;
+                }
+            }
 
-                    tokio::spawn(async move {
-                        if let Err(e) = network.send_dmx(universe, &dmx_data).await {
-                            log::error!("Failed to send DMX: {}", e);
-                        }
-                    });
+            if let Some(seconds) = history_seconds {
+                if let Ok(mut state) = self.app_state.try_write() {
+                    state.set_history_seconds(seconds);
                 }
-            });
+            }
 
-            // Simple channel controls (first 16 channels)
-            ui.label("Channel Controls (1-16):");
-            egui::Grid::new("channel_controls")
-                .num_columns(4)
-                .show(ui, |ui| {
-                    for i in 0..16 {
-                        ui.vertical(|ui| {
-                            ui.label(format!("Ch {}", i + 1));
-                            ui.add(
-                                egui::Slider::new(&mut self.dmx_send_values[i], 0..=255)
-                                    .orientation(egui::SliderOrientation::Vertical),
-                            );
-                        });
+            ui.separator();
 
-                        if i % 4 == 3 {
-                            ui.end_row();
-                        }
-                    }
-                });
+            self.show_dmx_sender(ui);
         });
     }
+
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        self.network.stop_transmitting();
+        if let Err(e) = self.network.finish_recording() {
+            log::error!("Failed to finish recording: {}", e);
+        }
+        if let Ok(mut state) = self.app_state.try_write() {
+            state.save_transmit_session();
+        }
+    }
 }
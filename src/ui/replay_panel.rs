@@ -0,0 +1,206 @@
+use crate::core::{MAX_UNIVERSE, MIN_UNIVERSE};
+use crate::network::{SacnNetwork, MAX_REPLAY_SPEED, MIN_REPLAY_SPEED};
+use eframe::egui;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const TIMELINE_HEIGHT: f32 = 32.0;
+
+/// Controls for loading and playing back a recorded capture.
+pub struct ReplayPanel {
+    path: String,
+    remap_from: u16,
+    remap_to: u16,
+}
+
+impl ReplayPanel {
+    pub fn new() -> Self {
+        Self {
+            path: String::new(),
+            remap_from: 1,
+            remap_to: 1,
+        }
+    }
+
+    /// Draws packet activity over the capture with a playhead. Returns the
+    /// fraction of the timeline clicked or dragged to.
+    fn show_timeline(ui: &mut egui::Ui, activity: &[usize], playhead: f32) -> Option<f32> {
+        let size = egui::vec2(ui.available_width(), TIMELINE_HEIGHT);
+        let (rect, response) = ui.allocate_exact_size(size, egui::Sense::click_and_drag());
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(rect, 2.0, ui.visuals().extreme_bg_color);
+
+        let busiest = activity.iter().copied().max().unwrap_or(0).max(1) as f32;
+        let width = rect.width() / activity.len().max(1) as f32;
+        for (i, &count) in activity.iter().enumerate() {
+            if count == 0 {
+                continue;
+            }
+            let height = rect.height() * count as f32 / busiest;
+            let x = rect.left() + i as f32 * width;
+            painter.rect_filled(
+                egui::Rect::from_min_max(
+                    egui::pos2(x, rect.bottom() - height),
+                    egui::pos2(x + width, rect.bottom()),
+                ),
+                0.0,
+                egui::Color32::from_rgb(80, 130, 200),
+            );
+        }
+
+        let x = rect.left() + rect.width() * playhead.clamp(0.0, 1.0);
+        painter.line_segment(
+            [egui::pos2(x, rect.top()), egui::pos2(x, rect.bottom())],
+            egui::Stroke::new(2.0, egui::Color32::YELLOW),
+        );
+
+        if response.clicked() || response.dragged() {
+            let pointer = response.interact_pointer_pos()?;
+            return Some(((pointer.x - rect.left()) / rect.width()).clamp(0.0, 1.0));
+        }
+        None
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui, network: &Arc<SacnNetwork>) {
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut self.path)
+                    .hint_text(".sacncap, .pcap or .pcapng file")
+                    .desired_width(180.0),
+            );
+            let open = ui
+                .button("Open")
+                .on_hover_text("Load for replay onto the network");
+            let import = ui
+                .button("Import")
+                .on_hover_text("Load into the viewer only and scrub through it");
+            if (open.clicked() || import.clicked()) && !self.path.trim().is_empty() {
+                let network = network.clone();
+                let path = PathBuf::from(self.path.trim());
+                let offline = import.clicked();
+                tokio::spawn(async move {
+                    if let Err(e) = network.open_replay(path, offline).await {
+                        log::error!("Failed to open replay: {}", e);
+                    }
+                });
+            }
+        });
+
+        let mut replay = network.replay();
+        let Some(loaded) = replay.as_mut() else {
+            ui.label("No capture loaded");
+            return;
+        };
+        let now = Instant::now();
+
+        let name = loaded
+            .path()
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let rejected = match loaded.rejected_count() {
+            0 => String::new(),
+            count => format!(", {} rejected", count),
+        };
+        ui.label(format!(
+            "{}: {} packets{}",
+            name,
+            loaded.packet_count(),
+            rejected
+        ))
+        .on_hover_text(loaded.path().display().to_string());
+
+        let mut close = false;
+        ui.horizontal(|ui| {
+            if loaded.is_playing() {
+                if ui.button("⏸ Pause").clicked() {
+                    loaded.pause(now);
+                }
+            } else if ui.button("▶ Play").clicked() {
+                loaded.play(now);
+            }
+            close = ui.button("⏹ Close").clicked();
+        });
+
+        let duration = loaded.duration().as_secs_f32();
+        let mut position = loaded.position(now).as_secs_f32();
+        if let Some(seek) =
+            Self::show_timeline(ui, loaded.activity(), position / duration.max(f32::EPSILON))
+        {
+            loaded.seek(Duration::from_secs_f32(seek * duration), now);
+        }
+        if ui
+            .add(egui::Slider::new(&mut position, 0.0..=duration).suffix(" s"))
+            .changed()
+        {
+            loaded.seek(Duration::from_secs_f32(position), now);
+        }
+
+        let captured_at = loaded.start_time()
+            + chrono::Duration::from_std(loaded.position(now)).unwrap_or_default();
+        ui.label(format!(
+            "Captured at {}",
+            captured_at
+                .with_timezone(&chrono::Local)
+                .format("%Y-%m-%d %H:%M:%S%.3f")
+        ));
+        ui.label(format!(
+            "Universes: {}",
+            loaded
+                .universes()
+                .iter()
+                .map(|(universe, packets)| format!("{} ({})", universe, packets))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+
+        ui.horizontal(|ui| {
+            let mut speed = loaded.speed();
+            if ui
+                .add(
+                    egui::DragValue::new(&mut speed)
+                        .range(MIN_REPLAY_SPEED..=MAX_REPLAY_SPEED)
+                        .speed(0.05)
+                        .suffix("x"),
+                )
+                .changed()
+            {
+                loaded.set_speed(speed, now);
+            }
+            ui.checkbox(&mut loaded.looping, "Loop");
+            ui.checkbox(&mut loaded.offline, "Offline")
+                .on_hover_text("Feed the viewer only, nothing is transmitted");
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Remap:");
+            ui.add(egui::DragValue::new(&mut self.remap_from).range(MIN_UNIVERSE..=MAX_UNIVERSE));
+            ui.label("→");
+            ui.add(egui::DragValue::new(&mut self.remap_to).range(MIN_UNIVERSE..=MAX_UNIVERSE));
+            if ui.button("Map").clicked() {
+                loaded.remap.insert(self.remap_from, self.remap_to);
+            }
+        });
+
+        let mut unmap = None;
+        ui.horizontal_wrapped(|ui| {
+            for (&from, &to) in &loaded.remap {
+                if ui
+                    .small_button(format!("{} → {} ✖", from, to))
+                    .on_hover_text("Remove mapping")
+                    .clicked()
+                {
+                    unmap = Some(from);
+                }
+            }
+        });
+        if let Some(from) = unmap {
+            loaded.remap.remove(&from);
+        }
+
+        if close {
+            *replay = None;
+        }
+    }
+}
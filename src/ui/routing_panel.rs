@@ -0,0 +1,212 @@
+use crate::core::{AppState, ChannelPatch, Route, MAX_ROUTE_SCALE, MAX_UNIVERSE, MIN_UNIVERSE};
+use eframe::egui;
+
+/// Routing matrix: received universes patched into transmit universes, with
+/// a remap table editor for the selected route.
+pub struct RoutingPanel {
+    input_universe: u16,
+    output_universe: u16,
+    selected: Option<usize>, // route whose remap table is shown
+    patch_input: u16,
+    patch_output: u16,
+}
+
+impl RoutingPanel {
+    pub fn new() -> Self {
+        Self {
+            input_universe: 1,
+            output_universe: 1,
+            selected: None,
+            patch_input: 1,
+            patch_output: 1,
+        }
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui, state: &mut AppState) {
+        ui.horizontal(|ui| {
+            ui.label("Route universe");
+            ui.add(
+                egui::DragValue::new(&mut self.input_universe).range(MIN_UNIVERSE..=MAX_UNIVERSE),
+            );
+            ui.label("to transmit universe");
+            let outputs: Vec<u16> = state
+                .transmit_session
+                .universes
+                .iter()
+                .map(|u| u.universe)
+                .collect();
+            egui::ComboBox::from_id_source("route_output")
+                .selected_text(self.output_universe.to_string())
+                .show_ui(ui, |ui| {
+                    for universe in outputs {
+                        ui.selectable_value(
+                            &mut self.output_universe,
+                            universe,
+                            universe.to_string(),
+                        );
+                    }
+                });
+            if ui.button("Add Route").clicked() {
+                state.add_route(Route::new(self.input_universe, self.output_universe));
+            }
+        });
+
+        if state.routes.is_empty() {
+            ui.label("No routes, add one to send a received universe back out");
+            return;
+        }
+
+        let statuses = state.route_status.clone();
+        let mut changed = false;
+        let mut remove = None;
+        egui::Grid::new("routes")
+            .num_columns(9)
+            .striped(true)
+            .show(ui, |ui| {
+                ui.strong("On");
+                ui.strong("Input");
+                ui.strong("Channels");
+                ui.strong("Output");
+                ui.strong("From");
+                ui.strong("Scale");
+                ui.strong("Remap");
+                ui.strong("Status");
+                ui.label("");
+                ui.end_row();
+
+                for (index, route) in state.routes.iter_mut().enumerate() {
+                    changed |= ui.checkbox(&mut route.enabled, "").changed();
+                    changed |= ui
+                        .add(
+                            egui::DragValue::new(&mut route.input_universe)
+                                .range(MIN_UNIVERSE..=MAX_UNIVERSE),
+                        )
+                        .changed();
+
+                    ui.horizontal(|ui| {
+                        changed |= ui
+                            .add(egui::DragValue::new(&mut route.first_channel).range(1..=512))
+                            .on_hover_text("First input channel")
+                            .changed();
+                        ui.label("×");
+                        changed |= ui
+                            .add(egui::DragValue::new(&mut route.channel_count).range(0..=512))
+                            .on_hover_text("Number of channels, 0 for the remap table only")
+                            .changed();
+                    });
+
+                    changed |= ui
+                        .add(
+                            egui::DragValue::new(&mut route.output_universe)
+                                .range(MIN_UNIVERSE..=MAX_UNIVERSE),
+                        )
+                        .changed();
+                    changed |= ui
+                        .add(egui::DragValue::new(&mut route.output_channel).range(1..=512))
+                        .on_hover_text("Output channel the first input channel lands on")
+                        .changed();
+                    changed |= ui
+                        .add(
+                            egui::DragValue::new(&mut route.scale)
+                                .range(0..=MAX_ROUTE_SCALE)
+                                .suffix("%"),
+                        )
+                        .changed();
+
+                    let label = format!("{} patches", route.remap.len());
+                    if ui
+                        .selectable_label(self.selected == Some(index), label)
+                        .clicked()
+                    {
+                        self.selected = if self.selected == Some(index) {
+                            None
+                        } else {
+                            Some(index)
+                        };
+                    }
+
+                    match statuses.get(index) {
+                        _ if !route.enabled => {
+                            ui.colored_label(egui::Color32::GRAY, "off");
+                        }
+                        Some(status) if status.error.is_some() => {
+                            ui.colored_label(
+                                egui::Color32::RED,
+                                status.error.clone().unwrap_or_default(),
+                            );
+                        }
+                        Some(status) if status.live => {
+                            ui.colored_label(egui::Color32::GREEN, "live");
+                        }
+                        Some(_) => {
+                            ui.colored_label(egui::Color32::YELLOW, "waiting for input");
+                        }
+                        None => {
+                            ui.colored_label(egui::Color32::GRAY, "starting");
+                        }
+                    }
+
+                    if ui.button("Remove").clicked() {
+                        remove = Some(index);
+                    }
+                    ui.end_row();
+                }
+            });
+
+        if let Some(index) = self.selected {
+            match state.routes.get_mut(index) {
+                Some(route) => changed |= self.show_remap(ui, route),
+                None => self.selected = None,
+            }
+        }
+
+        if let Some(index) = remove {
+            self.selected = None;
+            state.remove_route(index);
+        } else if changed {
+            state.save_routes();
+        }
+    }
+
+    /// Edits one route's remap table. Returns true if it changed.
+    fn show_remap(&mut self, ui: &mut egui::Ui, route: &mut Route) -> bool {
+        let mut changed = false;
+        ui.separator();
+        ui.strong(format!("Remap table for {}", route));
+        ui.label("Each patch sends one input channel to a different output channel.");
+
+        let mut remove = None;
+        for (index, patch) in route.remap.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                changed |= ui
+                    .add(egui::DragValue::new(&mut patch.input).range(1..=512))
+                    .changed();
+                ui.label("→");
+                changed |= ui
+                    .add(egui::DragValue::new(&mut patch.output).range(1..=512))
+                    .changed();
+                if ui.small_button("Remove").clicked() {
+                    remove = Some(index);
+                }
+            });
+        }
+        if let Some(index) = remove {
+            route.remap.remove(index);
+            changed = true;
+        }
+
+        ui.horizontal(|ui| {
+            ui.add(egui::DragValue::new(&mut self.patch_input).range(1..=512));
+            ui.label("→");
+            ui.add(egui::DragValue::new(&mut self.patch_output).range(1..=512));
+            if ui.button("Add Patch").clicked() {
+                route.remap.push(ChannelPatch {
+                    input: self.patch_input,
+                    output: self.patch_output,
+                });
+                changed = true;
+            }
+        });
+        changed
+    }
+}
@@ -11,7 +11,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("Starting sACN test sender using sacn crate...");
     println!("This test sender uses the same sacn crate as the main application");
     println!("for better compatibility and standards compliance.");
-    println!("");
+    println!();
 
     // Create a local address for the sender
     let local_addr = SocketAddr::new(IpAddr::V4([0, 0, 0, 0].into()), ACN_SDT_MULTICAST_PORT + 1);
@@ -48,7 +48,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     }
 
     println!("Test complete!");
-    println!("");
+    println!();
     println!("The main sACN viewer application now uses the professional sacn crate");
     println!("which provides:");
     println!("- Standards-compliant sACN implementation (ANSI E1.31-2018)");